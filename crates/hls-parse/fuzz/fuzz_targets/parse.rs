@@ -0,0 +1,35 @@
+//! Feeds arbitrary input to every playlist parser. No input may cause a panic.
+//! Run from `crates/hls-parse` with `cargo +nightly fuzz run parse`.
+
+#![no_main]
+
+use hls_parse::{HlsPlaylist, HlsPlaylistRef, MediaPlaylist, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = HlsPlaylist::from_bytes(data);
+    let Ok(data) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(playlist) = HlsPlaylist::parse_master(data) {
+        let _ = playlist.to_string();
+        let _ = playlist.validate();
+    }
+    let (playlist, _) = HlsPlaylist::parse_lenient(data);
+    let _ = playlist.to_string();
+    let _ = HlsPlaylist::from_str_prefix(data);
+    let lenient = ParseOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let _ = HlsPlaylist::from_str_with_options(data, &lenient);
+    if let Ok(playlist) = HlsPlaylistRef::parse(data) {
+        let _ = playlist.into_owned();
+    }
+
+    if let Ok(playlist) = MediaPlaylist::parse(data) {
+        let _ = playlist.validate();
+        let _ = playlist.total_duration();
+    }
+});
@@ -1,13 +1,30 @@
 //! Builders that are 1:1 with types in the `types` module,
 //! with optional fields for parsing compatibility.
 
+use std::collections::HashMap;
 use std::str::FromStr;
 
-use anyhow::Context;
-
+use crate::ParseOptions;
 use crate::constants::*;
+use crate::error::HlsParseError;
+use crate::types::borrowed::TagRef;
+use crate::types::date_range::DateRange;
+use crate::types::define::Definition;
 use crate::types::media::Audio;
 use crate::types::media::AudioChannelInfo;
+use crate::types::media::SubtitleRendition;
+use crate::types::media::VideoRendition;
+use crate::types::segment::ByteRange;
+use crate::types::segment::EncryptionKey;
+use crate::types::segment::KeyMethod;
+use crate::types::segment::MediaInitialization;
+use crate::types::segment::Part;
+use crate::types::segment::PreloadHint;
+use crate::types::segment::RenditionReport;
+use crate::types::segment::Segment;
+use crate::types::segment::Skip;
+use crate::types::steering::ContentSteering;
+use crate::types::stream_info::CpcEntry;
 use crate::types::stream_info::IframeStreamInfo;
 use crate::types::stream_info::Resolution;
 use crate::types::stream_info::StreamInfo;
@@ -19,49 +36,118 @@ pub(crate) struct AudioBuilder {
     group_id: Option<String>,
     name: Option<String>,
     language: Option<String>,
+    assoc_language: Option<String>,
     default: Option<bool>,
     auto_select: Option<bool>,
     channel_info: Option<AudioChannelInfo>,
+    characteristics: Option<Vec<String>>,
+    stable_rendition_id: Option<String>,
+    sample_rate: Option<u32>,
+    bit_depth: Option<u8>,
     uri: Option<String>,
+    attribute_order: Vec<String>,
+    /// Source text of the tag, if kept
+    pub(crate) raw: Option<String>,
+    lenient: bool,
+    error: Option<HlsParseError>,
 }
 
 impl AudioBuilder {
-    /// Consume self, producing Ok(`Audio`) if required fields are present.
-    pub(crate) fn build(self) -> anyhow::Result<Audio> {
-        let error_prefix = "missing HLS audio param ";
+    /// Create an empty builder that incorporates params according to `options`.
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            // TYPE is parsed ahead of the other params, as it determines the builder used
+            attribute_order: vec![P_TYPE.to_owned()],
+            lenient: options.lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Consume self, producing Ok(`Audio`) if required fields are present
+    /// and all params were converted successfully.
+    /// `line` is where the tag starts, reported if `GROUP-ID` is missing.
+    pub(crate) fn build(self, line: usize) -> Result<Audio, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let Some(group_id) = self.group_id else {
+            return Err(HlsParseError::MissingGroupId {
+                name: self.name,
+                language: self.language,
+                line,
+            });
+        };
+        check_missing(
+            "audio",
+            &[
+                (P_NAME, self.name.is_none()),
+                (P_LANGUAGE, self.language.is_none()),
+                (P_DEFAULT, self.default.is_none()),
+                (P_AUTOSELECT, self.auto_select.is_none()),
+                (P_CHANNELS, self.channel_info.is_none()),
+                (P_URI, self.uri.is_none()),
+            ],
+        )?;
         Ok(Audio {
-            group_id: self.group_id.with_context(|| format!("{error_prefix}{P_GROUP_ID}"))?,
-            name: self.name.with_context(|| format!("{error_prefix}{P_NAME}"))?,
-            language: self.language.with_context(|| format!("{error_prefix}{P_LANGUAGE}"))?,
-            default: self.default.with_context(|| format!("{error_prefix}{P_DEFAULT}"))?,
-            auto_select: self.auto_select.with_context(|| format!("{error_prefix}{P_AUTOSELECT}"))?,
-            channel_info: self.channel_info.with_context(|| format!("{error_prefix}{P_CHANNELS}"))?,
-            uri: self.uri.with_context(|| format!("{error_prefix}{P_URI}"))?,
+            group_id,
+            name: self.name.ok_or(HlsParseError::MissingField(P_NAME))?,
+            language: self.language.ok_or(HlsParseError::MissingField(P_LANGUAGE))?,
+            assoc_language: self.assoc_language,
+            default: self.default.ok_or(HlsParseError::MissingField(P_DEFAULT))?,
+            auto_select: self.auto_select.ok_or(HlsParseError::MissingField(P_AUTOSELECT))?,
+            channel_info: self.channel_info.ok_or(HlsParseError::MissingField(P_CHANNELS))?,
+            characteristics: self.characteristics,
+            stable_rendition_id: self.stable_rendition_id,
+            sample_rate: self.sample_rate,
+            bit_depth: self.bit_depth,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            attribute_order: self.attribute_order,
+            raw: self.raw,
         })
     }
 
     /// Incorporates the given parameter (name, value) into the builder,
-    /// failing if the name doesn't match or necessary conversion of a parameter value fails.
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
     pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
         let (param_name, param_value) = param_tuple;
+        self.attribute_order.push(param_name.to_owned());
         match param_name {
             P_GROUP_ID => self.group_id = Some(param_value.to_owned()),
             P_NAME => self.name = Some(param_value.to_owned()),
             P_LANGUAGE => self.language = Some(param_value.to_owned()),
+            P_ASSOC_LANGUAGE => self.assoc_language = Some(param_value.to_owned()),
             P_DEFAULT => {
-                self.default = Some(bool_from_param_str(param_value).expect(
-                    format!("failed to parse {P_DEFAULT} param from YES/NO value").as_str(),
-                ))
+                self.default = record(
+                    &mut self.error,
+                    bool_from_param_str(P_DEFAULT, param_value, self.lenient),
+                )
             }
             P_AUTOSELECT => {
-                self.auto_select = Some(bool_from_param_str(param_value).expect(
-                    format!("failed to parse {P_AUTOSELECT} param from YES/NO value").as_str(),
-                ))
+                self.auto_select = record(
+                    &mut self.error,
+                    bool_from_param_str(P_AUTOSELECT, param_value, self.lenient),
+                )
             }
             P_CHANNELS => {
-                self.channel_info = Some(
-                    AudioChannelInfo::from_str(param_value)
-                        .expect(format!("failed to parse {P_CHANNELS} param value").as_str()),
+                self.channel_info = record(
+                    &mut self.error,
+                    value_from_param_str(P_CHANNELS, param_value),
+                )
+            }
+            P_CHARACTERISTICS => {
+                self.characteristics = Some(param_value.split(',').map(|x| x.to_owned()).collect())
+            }
+            P_STABLE_RENDITION_ID => self.stable_rendition_id = Some(param_value.to_owned()),
+            P_SAMPLE_RATE => {
+                self.sample_rate = record(
+                    &mut self.error,
+                    int_from_param_str(P_SAMPLE_RATE, param_value),
+                )
+            }
+            P_BIT_DEPTH => {
+                self.bit_depth = record(
+                    &mut self.error,
+                    int_from_param_str(P_BIT_DEPTH, param_value),
                 )
             }
             P_URI => self.uri = Some(param_value.to_owned()),
@@ -74,48 +160,280 @@ impl AudioBuilder {
 /// Builder proxy for `crate::types::stream_info::StreamInfoCommon`
 #[derive(Debug, Default)]
 pub(crate) struct StreamInfoCommonBuilder {
-    bandwidth: Option<usize>,
+    bandwidth: Option<u64>,
     codecs: Option<Vec<String>>,
+    supplemental_codecs: Option<Vec<String>>,
     resolution: Option<Resolution>,
     video_range: Option<String>,
+    stable_variant_id: Option<String>,
+    allowed_cpc: Option<Vec<CpcEntry>>,
     pub(crate) uri: Option<String>,
+    /// Param names in source order, including those incorporated by the enclosing builder
+    attribute_order: Vec<String>,
+    /// Source text of the tag, if kept
+    pub(crate) raw: Option<String>,
+    error: Option<HlsParseError>,
 }
 
 impl StreamInfoCommonBuilder {
-    fn build(self) -> anyhow::Result<StreamInfoCommon> {
-        let error_prefix = "missing HLS video param ";
+    /// Required params, each paired with whether it's missing.
+    fn required_params(&self) -> [(&'static str, bool); 5] {
+        [
+            (P_BANDWIDTH, self.bandwidth.is_none()),
+            (P_CODECS, self.codecs.is_none()),
+            (P_RESOLUTION, self.resolution.is_none()),
+            (P_VIDEO_RANGE, self.video_range.is_none()),
+            (P_URI, self.uri.is_none()),
+        ]
+    }
+
+    fn build(self) -> Result<StreamInfoCommon, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
         Ok(StreamInfoCommon {
-            bandwidth: self.bandwidth.with_context(|| format!("{error_prefix}{P_BANDWIDTH}"))?,
-            codecs: self.codecs.with_context(|| format!("{error_prefix}{P_CODECS}"))?,
-            resolution: self.resolution.with_context(|| format!("{error_prefix}{P_RESOLUTION}"))?,
-            video_range: self.video_range.with_context(|| format!("{error_prefix}{P_VIDEO_RANGE}"))?,
-            uri: self.uri.with_context(|| format!("{error_prefix}{P_URI}"))?,
+            bandwidth: self.bandwidth.ok_or(HlsParseError::MissingField(P_BANDWIDTH))?,
+            codecs: self.codecs.ok_or(HlsParseError::MissingField(P_CODECS))?,
+            supplemental_codecs: self.supplemental_codecs,
+            resolution: self.resolution.ok_or(HlsParseError::MissingField(P_RESOLUTION))?,
+            video_range: self.video_range.ok_or(HlsParseError::MissingField(P_VIDEO_RANGE))?,
+            stable_variant_id: self.stable_variant_id,
+            allowed_cpc: self.allowed_cpc,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            attribute_order: self.attribute_order,
+            raw: self.raw,
         })
     }
 
-    /// Incorporates the given parameter, returning an Err if the name doesn't match, and failing if param value conversion fails.
-    // NOTE: different from other `incorporate` calls, this call can fail as it's nested in other types. Errors are handled on the caller side.
-    fn incorporate(&mut self, param_tuple: (&str, &str)) -> anyhow::Result<()> {
+    /// Incorporates the given parameter, returning false if the name doesn't match.
+    /// Conversion failures are returned from `build()`.
+    // NOTE: different from other `incorporate` calls, this call can fail as it's nested in other types. Failures are handled on the caller side.
+    fn incorporate(&mut self, param_tuple: (&str, &str)) -> bool {
         let (param_name, param_value) = param_tuple;
         match param_name {
             P_BANDWIDTH => {
-                self.bandwidth = Some(
-                    usize::from_str(param_value)
-                        .expect(format!("failed to parse {P_BANDWIDTH} param as int").as_str()),
+                self.bandwidth = record(
+                    &mut self.error,
+                    int_from_param_str(P_BANDWIDTH, param_value),
                 )
             }
             P_CODECS => self.codecs = Some(param_value.split(',').map(|x| x.to_owned()).collect()),
+            P_SUPPLEMENTAL_CODECS => {
+                self.supplemental_codecs =
+                    Some(param_value.split(',').map(|x| x.to_owned()).collect())
+            }
             P_RESOLUTION => {
-                self.resolution = Some(
-                    Resolution::from_str(param_value)
-                        .expect(format!("failed to parse {P_RESOLUTION} param").as_str()),
+                self.resolution = record(
+                    &mut self.error,
+                    value_from_param_str(P_RESOLUTION, param_value),
                 )
             }
             P_VIDEO_RANGE => self.video_range = Some(param_value.to_owned()),
+            P_STABLE_VARIANT_ID => self.stable_variant_id = Some(param_value.to_owned()),
+            P_ALLOWED_CPC => {
+                self.allowed_cpc = record(
+                    &mut self.error,
+                    param_value
+                        .split(',')
+                        .map(|entry| value_from_param_str(P_ALLOWED_CPC, entry))
+                        .collect(),
+                )
+            }
+            P_URI => self.uri = Some(param_value.to_owned()),
+            _ => return false,
+        }
+        self.attribute_order.push(param_name.to_owned());
+        true
+    }
+}
+
+/// Builder proxy for `crate::types::media::VideoRendition`
+#[derive(Default, Debug)]
+pub(crate) struct VideoRenditionBuilder {
+    group_id: Option<String>,
+    name: Option<String>,
+    language: Option<String>,
+    default: Option<bool>,
+    auto_select: Option<bool>,
+    stable_rendition_id: Option<String>,
+    uri: Option<String>,
+    attribute_order: Vec<String>,
+    /// Source text of the tag, if kept
+    pub(crate) raw: Option<String>,
+    lenient: bool,
+    error: Option<HlsParseError>,
+}
+
+impl VideoRenditionBuilder {
+    /// Create an empty builder that incorporates params according to `options`.
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            // TYPE is parsed ahead of the other params, as it determines the builder used
+            attribute_order: vec![P_TYPE.to_owned()],
+            lenient: options.lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Consume self, producing Ok(`VideoRendition`) if required fields are present
+    /// and all params were converted successfully.
+    /// `line` is where the tag starts, reported if `GROUP-ID` is missing.
+    pub(crate) fn build(self, line: usize) -> Result<VideoRendition, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let Some(group_id) = self.group_id else {
+            return Err(HlsParseError::MissingGroupId {
+                name: self.name,
+                language: self.language,
+                line,
+            });
+        };
+        Ok(VideoRendition {
+            group_id,
+            name: self.name.ok_or(HlsParseError::MissingField(P_NAME))?,
+            language: self.language,
+            // Both default to NO when absent
+            default: self.default.unwrap_or_default(),
+            auto_select: self.auto_select.unwrap_or_default(),
+            stable_rendition_id: self.stable_rendition_id,
+            uri: self.uri,
+            attribute_order: self.attribute_order,
+            raw: self.raw,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        self.attribute_order.push(param_name.to_owned());
+        match param_name {
+            P_GROUP_ID => self.group_id = Some(param_value.to_owned()),
+            P_NAME => self.name = Some(param_value.to_owned()),
+            P_LANGUAGE => self.language = Some(param_value.to_owned()),
+            P_DEFAULT => {
+                self.default = record(
+                    &mut self.error,
+                    bool_from_param_str(P_DEFAULT, param_value, self.lenient),
+                )
+            }
+            P_AUTOSELECT => {
+                self.auto_select = record(
+                    &mut self.error,
+                    bool_from_param_str(P_AUTOSELECT, param_value, self.lenient),
+                )
+            }
+            P_STABLE_RENDITION_ID => self.stable_rendition_id = Some(param_value.to_owned()),
+            P_URI => self.uri = Some(param_value.to_owned()),
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::media::SubtitleRendition`
+#[derive(Default, Debug)]
+pub(crate) struct SubtitleRenditionBuilder {
+    group_id: Option<String>,
+    name: Option<String>,
+    language: Option<String>,
+    assoc_language: Option<String>,
+    default: Option<bool>,
+    auto_select: Option<bool>,
+    forced: Option<bool>,
+    characteristics: Option<Vec<String>>,
+    stable_rendition_id: Option<String>,
+    uri: Option<String>,
+    attribute_order: Vec<String>,
+    /// Source text of the tag, if kept
+    pub(crate) raw: Option<String>,
+    lenient: bool,
+    error: Option<HlsParseError>,
+}
+
+impl SubtitleRenditionBuilder {
+    /// Create an empty builder that incorporates params according to `options`.
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            // TYPE is parsed ahead of the other params, as it determines the builder used
+            attribute_order: vec![P_TYPE.to_owned()],
+            lenient: options.lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Consume self, producing Ok(`SubtitleRendition`) if required fields are present
+    /// and all params were converted successfully.
+    /// `line` is where the tag starts, reported if `GROUP-ID` is missing.
+    pub(crate) fn build(self, line: usize) -> Result<SubtitleRendition, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let Some(group_id) = self.group_id else {
+            return Err(HlsParseError::MissingGroupId {
+                name: self.name,
+                language: self.language,
+                line,
+            });
+        };
+        check_missing(
+            "subtitles",
+            &[(P_NAME, self.name.is_none()), (P_URI, self.uri.is_none())],
+        )?;
+        Ok(SubtitleRendition {
+            group_id,
+            name: self.name.ok_or(HlsParseError::MissingField(P_NAME))?,
+            language: self.language,
+            assoc_language: self.assoc_language,
+            // All default to NO when absent
+            default: self.default.unwrap_or_default(),
+            auto_select: self.auto_select.unwrap_or_default(),
+            forced: self.forced.unwrap_or_default(),
+            characteristics: self.characteristics,
+            stable_rendition_id: self.stable_rendition_id,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            attribute_order: self.attribute_order,
+            raw: self.raw,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        self.attribute_order.push(param_name.to_owned());
+        match param_name {
+            P_GROUP_ID => self.group_id = Some(param_value.to_owned()),
+            P_NAME => self.name = Some(param_value.to_owned()),
+            P_LANGUAGE => self.language = Some(param_value.to_owned()),
+            P_ASSOC_LANGUAGE => self.assoc_language = Some(param_value.to_owned()),
+            P_DEFAULT => {
+                self.default = record(
+                    &mut self.error,
+                    bool_from_param_str(P_DEFAULT, param_value, self.lenient),
+                )
+            }
+            P_AUTOSELECT => {
+                self.auto_select = record(
+                    &mut self.error,
+                    bool_from_param_str(P_AUTOSELECT, param_value, self.lenient),
+                )
+            }
+            P_FORCED => {
+                self.forced = record(
+                    &mut self.error,
+                    bool_from_param_str(P_FORCED, param_value, self.lenient),
+                )
+            }
+            P_CHARACTERISTICS => {
+                self.characteristics = Some(param_value.split(',').map(|x| x.to_owned()).collect())
+            }
+            P_STABLE_RENDITION_ID => self.stable_rendition_id = Some(param_value.to_owned()),
             P_URI => self.uri = Some(param_value.to_owned()),
-            _ => anyhow::bail!("param not covered by common stream info"),
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
         }
-        Ok(())
+        self
     }
 }
 
@@ -123,46 +441,71 @@ impl StreamInfoCommonBuilder {
 #[derive(Debug, Default)]
 pub(crate) struct StreamInfoBuilder {
     pub(crate) common: StreamInfoCommonBuilder,
-    average_bandwidth: Option<usize>,
+    average_bandwidth: Option<u64>,
     frame_rate: Option<f32>,
-    audio_codec: Option<String>,
+    audio_group: Option<String>,
+    video_group: Option<String>,
     closed_captions: Option<String>,
+    pathway_id: Option<String>,
+    score: Option<f32>,
+    program_id: Option<usize>,
+    error: Option<HlsParseError>,
 }
 
 impl StreamInfoBuilder {
-    /// Consume self, producing Ok(`StreamInfo`) if required fields are present.
-    pub(crate) fn build(self) -> anyhow::Result<StreamInfo> {
-        let error_prefix = "missing HLS video param ";
+    /// Consume self, producing Ok(`StreamInfo`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(mut self) -> Result<StreamInfo, HlsParseError> {
+        // Conversion failures take precedence over missing params
+        if let Some(e) = self.error.or_else(|| self.common.error.take()) {
+            return Err(e);
+        }
+        let mut required = self.common.required_params().to_vec();
+        required.push((P_CLOSED_CAPTIONS, self.closed_captions.is_none()));
+        check_missing("stream", &required)?;
         Ok(StreamInfo {
             common: self.common.build()?,
-            average_bandwidth: self.average_bandwidth.with_context(|| format!("{error_prefix}{P_AVERAGE_BANDWIDTH}"))?,
-            frame_rate: self.frame_rate.with_context(|| format!("{error_prefix}{P_FRAME_RATE}"))?,
-            audio_codec: self.audio_codec.with_context(|| format!("{error_prefix}{P_AUDIO}"))?,
-            closed_captions: self.closed_captions.with_context(|| format!("{error_prefix}{P_CLOSED_CAPTIONS}"))?,
+            average_bandwidth: self.average_bandwidth,
+            frame_rate: self.frame_rate,
+            audio_group: self.audio_group,
+            video_group: self.video_group,
+            closed_captions: self.closed_captions.ok_or(HlsParseError::MissingField(P_CLOSED_CAPTIONS))?,
+            pathway_id: self.pathway_id,
+            program_id: self.program_id,
+            score: self.score,
         })
     }
 
     /// Incorporates the given parameter (name, value) into the builder,
-    /// failing if the name doesn't match or necessary conversion of a parameter value fails.
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
     pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
         let (param_name, param_value) = param_tuple;
-        if let Err(_) = self.common.incorporate(param_tuple) {
+        if !self.common.incorporate(param_tuple) {
             match param_name {
                 P_AVERAGE_BANDWIDTH => {
-                    self.average_bandwidth = Some(usize::from_str(param_value).expect(
-                        format!("failed to parse {P_AVERAGE_BANDWIDTH} param as int").as_str(),
-                    ))
+                    self.average_bandwidth = record(
+                        &mut self.error,
+                        int_from_param_str(P_AVERAGE_BANDWIDTH, param_value),
+                    )
                 }
                 P_FRAME_RATE => {
                     self.frame_rate =
-                        Some(f32::from_str(param_value).expect(
-                            format!("failed to parse {P_FRAME_RATE} param as int").as_str(),
-                        ))
+                        record(&mut self.error, frame_rate_from_param_str(param_value))
                 }
-                P_AUDIO => self.audio_codec = Some(param_value.to_owned()),
+                P_AUDIO => self.audio_group = Some(param_value.to_owned()),
+                P_VIDEO => self.video_group = Some(param_value.to_owned()),
                 P_CLOSED_CAPTIONS => self.closed_captions = Some(param_value.to_owned()),
+                P_PATHWAY_ID => self.pathway_id = Some(param_value.to_owned()),
+                P_SCORE => self.score = record(&mut self.error, score_from_param_str(param_value)),
+                P_PROGRAM_ID => {
+                    self.program_id = record(
+                        &mut self.error,
+                        int_from_param_str(P_PROGRAM_ID, param_value),
+                    )
+                }
                 _ => unreachable!("unhandled param {param_name} passed from parser"),
             }
+            self.common.attribute_order.push(param_name.to_owned());
         }
         self
     }
@@ -172,33 +515,744 @@ impl StreamInfoBuilder {
 #[derive(Debug, Default)]
 pub(crate) struct IframeStreamInfoBuilder {
     pub(crate) common: StreamInfoCommonBuilder,
+    average_bandwidth: Option<u64>,
+    error: Option<HlsParseError>,
 }
 
 impl IframeStreamInfoBuilder {
     /// Consume self, producing Ok`IframeStreamInfo`) if required fields are present.
-    pub(crate) fn build(self) -> anyhow::Result<IframeStreamInfo> {
+    pub(crate) fn build(mut self) -> Result<IframeStreamInfo, HlsParseError> {
+        // Conversion failures take precedence over missing params
+        if let Some(e) = self.error.or_else(|| self.common.error.take()) {
+            return Err(e);
+        }
+        check_missing("iframe stream", &self.common.required_params())?;
         Ok(IframeStreamInfo {
             common: self.common.build()?,
+            average_bandwidth: self.average_bandwidth,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        if !self.common.incorporate(param_tuple) {
+            match param_name {
+                P_AVERAGE_BANDWIDTH => {
+                    self.average_bandwidth = record(
+                        &mut self.error,
+                        int_from_param_str(P_AVERAGE_BANDWIDTH, param_value),
+                    )
+                }
+                _ => unreachable!("unhandled param {param_name} passed from parser"),
+            }
+            self.common.attribute_order.push(param_name.to_owned());
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::segment::Segment`.
+/// Filled in from the tags preceding a segment URI, and from state carried across segments.
+#[derive(Debug, Default)]
+pub(crate) struct SegmentBuilder {
+    pub(crate) duration: Option<f32>,
+    pub(crate) title: Option<String>,
+    pub(crate) uri: Option<String>,
+    pub(crate) keys: Vec<EncryptionKey>,
+    pub(crate) map: Option<MediaInitialization>,
+    pub(crate) bitrate: Option<u64>,
+    pub(crate) parts: Vec<Part>,
+    pub(crate) gap: bool,
+}
+
+impl SegmentBuilder {
+    /// Consume self, producing Ok(`Segment`) if required fields are present.
+    pub(crate) fn build(self) -> Result<Segment, HlsParseError> {
+        let uri = self.uri.ok_or(HlsParseError::MissingField(P_URI))?;
+        Ok(Segment {
+            duration: self.duration.ok_or(HlsParseError::MissingField("#EXTINF"))?,
+            title: self.title,
+            uri,
+            keys: self.keys,
+            map: self.map,
+            bitrate: self.bitrate,
+            parts: self.parts,
+            gap: self.gap,
+        })
+    }
+}
+
+/// Builder proxy for `crate::types::segment::EncryptionKey`
+#[derive(Debug, Default)]
+pub(crate) struct KeyBuilder {
+    method: Option<String>,
+    uri: Option<String>,
+    iv: Option<String>,
+    key_format: Option<String>,
+    key_format_versions: Option<String>,
+}
+
+impl KeyBuilder {
+    /// Consume self, producing Ok(`EncryptionKey`) if required fields are present,
+    /// or Ok(None) if the key's method is `NONE` (i.e. subsequent segments aren't encrypted).
+    pub(crate) fn build(self) -> Result<Option<EncryptionKey>, HlsParseError> {
+        let method = self.method.ok_or(HlsParseError::MissingField(P_METHOD))?;
+        if method == "NONE" {
+            return Ok(None);
+        }
+        Ok(Some(EncryptionKey {
+            method: value_from_param_str::<KeyMethod>(P_METHOD, &method)?,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            iv: self.iv,
+            key_format: self.key_format,
+            key_format_versions: self.key_format_versions,
+        }))
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_METHOD => self.method = Some(param_value.to_owned()),
+            P_URI => self.uri = Some(param_value.to_owned()),
+            P_IV => self.iv = Some(param_value.to_owned()),
+            P_KEYFORMAT => self.key_format = Some(param_value.to_owned()),
+            P_KEYFORMATVERSIONS => self.key_format_versions = Some(param_value.to_owned()),
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::segment::MediaInitialization`
+#[derive(Debug, Default)]
+pub(crate) struct MapBuilder {
+    uri: Option<String>,
+    byte_range: Option<ByteRange>,
+    error: Option<HlsParseError>,
+}
+
+impl MapBuilder {
+    /// Consume self, producing Ok(`MediaInitialization`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<MediaInitialization, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        Ok(MediaInitialization {
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            byte_range: self.byte_range,
         })
     }
 
     /// Incorporates the given parameter (name, value) into the builder,
-    /// failing if the name doesn't match or necessary conversion of a parameter value fails.
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
     pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
-        if let Err(_) = self.common.incorporate(param_tuple) {
-            unreachable!("unhandled param {} passed from parser", param_tuple.0);
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_URI => self.uri = Some(param_value.to_owned()),
+            P_BYTERANGE => {
+                self.byte_range = record(
+                    &mut self.error,
+                    value_from_param_str(P_BYTERANGE, param_value),
+                )
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
         }
         self
     }
 }
 
-/// Matches an HLS boolean parameter value. Throws an error if not exactly YES or NO.
-fn bool_from_param_str(s: &str) -> anyhow::Result<bool> {
-    if s == "YES" {
+/// Builder proxy for `crate::types::segment::Part`
+#[derive(Debug, Default)]
+pub(crate) struct PartBuilder {
+    duration: Option<f32>,
+    uri: Option<String>,
+    independent: Option<bool>,
+    byte_range: Option<ByteRange>,
+    gap: Option<bool>,
+    lenient: bool,
+    error: Option<HlsParseError>,
+}
+
+impl PartBuilder {
+    /// Create an empty builder that incorporates params according to `options`.
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            lenient: options.lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Consume self, producing Ok(`Part`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<Part, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        check_missing(
+            "part",
+            &[
+                (P_DURATION, self.duration.is_none()),
+                (P_URI, self.uri.is_none()),
+            ],
+        )?;
+        Ok(Part {
+            duration: self.duration.ok_or(HlsParseError::MissingField(P_DURATION))?,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            // Both default to NO when absent
+            independent: self.independent.unwrap_or_default(),
+            byte_range: self.byte_range,
+            gap: self.gap.unwrap_or_default(),
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_DURATION => {
+                self.duration = record(
+                    &mut self.error,
+                    value_from_param_str(P_DURATION, param_value),
+                )
+            }
+            P_URI => self.uri = Some(param_value.to_owned()),
+            P_INDEPENDENT => {
+                self.independent = record(
+                    &mut self.error,
+                    bool_from_param_str(P_INDEPENDENT, param_value, self.lenient),
+                )
+            }
+            P_BYTERANGE => {
+                self.byte_range = record(
+                    &mut self.error,
+                    value_from_param_str(P_BYTERANGE, param_value),
+                )
+            }
+            P_GAP => {
+                self.gap = record(
+                    &mut self.error,
+                    bool_from_param_str(P_GAP, param_value, self.lenient),
+                )
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::segment::PreloadHint`
+#[derive(Debug, Default)]
+pub(crate) struct PreloadHintBuilder {
+    hint_type: Option<String>,
+    uri: Option<String>,
+    byte_range_start: Option<usize>,
+    byte_range_length: Option<usize>,
+    error: Option<HlsParseError>,
+}
+
+impl PreloadHintBuilder {
+    /// Consume self, producing Ok(`PreloadHint`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<PreloadHint, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        check_missing(
+            "preload hint",
+            &[
+                (P_TYPE, self.hint_type.is_none()),
+                (P_URI, self.uri.is_none()),
+            ],
+        )?;
+        let hint_type = self.hint_type.ok_or(HlsParseError::MissingField(P_TYPE))?;
+        Ok(PreloadHint {
+            hint_type: value_from_param_str(P_TYPE, &hint_type)?,
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            byte_range_start: self.byte_range_start,
+            byte_range_length: self.byte_range_length,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_TYPE => self.hint_type = Some(param_value.to_owned()),
+            P_URI => self.uri = Some(param_value.to_owned()),
+            P_BYTERANGE_START => {
+                self.byte_range_start = record(
+                    &mut self.error,
+                    int_from_param_str(P_BYTERANGE_START, param_value),
+                )
+            }
+            P_BYTERANGE_LENGTH => {
+                self.byte_range_length = record(
+                    &mut self.error,
+                    int_from_param_str(P_BYTERANGE_LENGTH, param_value),
+                )
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::segment::RenditionReport`
+#[derive(Debug, Default)]
+pub(crate) struct RenditionReportBuilder {
+    uri: Option<String>,
+    last_msn: Option<usize>,
+    last_part: Option<usize>,
+    error: Option<HlsParseError>,
+}
+
+impl RenditionReportBuilder {
+    /// Consume self, producing Ok(`RenditionReport`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<RenditionReport, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        Ok(RenditionReport {
+            uri: self.uri.ok_or(HlsParseError::MissingField(P_URI))?,
+            last_msn: self.last_msn,
+            last_part: self.last_part,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_URI => self.uri = Some(param_value.to_owned()),
+            P_LAST_MSN => {
+                self.last_msn = record(
+                    &mut self.error,
+                    int_from_param_str(P_LAST_MSN, param_value),
+                )
+            }
+            P_LAST_PART => {
+                self.last_part = record(
+                    &mut self.error,
+                    int_from_param_str(P_LAST_PART, param_value),
+                )
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::segment::Skip`
+#[derive(Debug, Default)]
+pub(crate) struct SkipBuilder {
+    skipped_segments: Option<usize>,
+    recently_removed_dateranges: Option<Vec<String>>,
+    error: Option<HlsParseError>,
+}
+
+impl SkipBuilder {
+    /// Consume self, producing Ok(`Skip`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<Skip, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        Ok(Skip {
+            skipped_segments: self
+                .skipped_segments
+                .ok_or(HlsParseError::MissingField(P_SKIPPED_SEGMENTS))?,
+            recently_removed_dateranges: self.recently_removed_dateranges,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_SKIPPED_SEGMENTS => {
+                self.skipped_segments = record(
+                    &mut self.error,
+                    int_from_param_str(P_SKIPPED_SEGMENTS, param_value),
+                )
+            }
+            // Date range IDs are separated by tabs
+            P_RECENTLY_REMOVED_DATERANGES => {
+                self.recently_removed_dateranges =
+                    Some(param_value.split('\t').map(|x| x.to_owned()).collect())
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::steering::ContentSteering`
+#[derive(Debug, Default)]
+pub(crate) struct ContentSteeringBuilder {
+    server_uri: Option<String>,
+    pathway_id: Option<String>,
+    attribute_order: Vec<String>,
+}
+
+impl ContentSteeringBuilder {
+    /// Consume self, producing Ok(`ContentSteering`) if required fields are present.
+    pub(crate) fn build(self) -> Result<ContentSteering, HlsParseError> {
+        Ok(ContentSteering {
+            server_uri: self.server_uri.ok_or(HlsParseError::MissingField(P_SERVER_URI))?,
+            pathway_id: self.pathway_id,
+            attribute_order: self.attribute_order,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        self.attribute_order.push(param_name.to_owned());
+        match param_name {
+            P_SERVER_URI => self.server_uri = Some(param_value.to_owned()),
+            P_PATHWAY_ID => self.pathway_id = Some(param_value.to_owned()),
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::define::Definition`
+#[derive(Debug, Default)]
+pub(crate) struct DefinitionBuilder {
+    name: Option<String>,
+    value: Option<String>,
+    import: Option<String>,
+    query_param: Option<String>,
+}
+
+impl DefinitionBuilder {
+    /// Consume self, producing Ok(`Definition`) if exactly one of `NAME`, `IMPORT` and
+    /// `QUERYPARAM` is present, along with `VALUE` for `NAME`.
+    pub(crate) fn build(self) -> Result<Definition, HlsParseError> {
+        match (self.name, self.import, self.query_param) {
+            (Some(name), None, None) => Ok(Definition::Value {
+                value: self.value.ok_or(HlsParseError::MissingField(P_VALUE))?,
+                name,
+            }),
+            (None, Some(name), None) if self.value.is_none() => Ok(Definition::Import(name)),
+            (None, None, Some(name)) if self.value.is_none() => Ok(Definition::QueryParam(name)),
+            (None, None, None) => Err(HlsParseError::MissingField(P_NAME)),
+            _ => Err(HlsParseError::InvalidElement(format!(
+                "#EXT-X-DEFINE must have exactly one of {P_NAME} (with {P_VALUE}), {P_IMPORT} or {P_QUERYPARAM}"
+            ))),
+        }
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_NAME => self.name = Some(param_value.to_owned()),
+            P_VALUE => self.value = Some(param_value.to_owned()),
+            P_IMPORT => self.import = Some(param_value.to_owned()),
+            P_QUERYPARAM => self.query_param = Some(param_value.to_owned()),
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Builder proxy for `crate::types::date_range::DateRange`
+#[derive(Debug, Default)]
+pub(crate) struct DateRangeBuilder {
+    id: Option<String>,
+    class: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    duration: Option<f32>,
+    planned_duration: Option<f32>,
+    end_on_next: Option<bool>,
+    client_attributes: HashMap<String, String>,
+    scte35_cmd: Option<Vec<u8>>,
+    scte35_out: Option<Vec<u8>>,
+    scte35_in: Option<Vec<u8>>,
+    lenient: bool,
+    error: Option<HlsParseError>,
+}
+
+impl DateRangeBuilder {
+    /// Create an empty builder that incorporates params according to `options`.
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            lenient: options.lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Consume self, producing Ok(`DateRange`) if required fields are present
+    /// and all params were converted successfully.
+    pub(crate) fn build(self) -> Result<DateRange, HlsParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        Ok(DateRange {
+            id: self.id.ok_or(HlsParseError::MissingField(P_ID))?,
+            class: self.class,
+            start_date: self.start_date.ok_or(HlsParseError::MissingField(P_START_DATE))?,
+            end_date: self.end_date,
+            duration: self.duration,
+            planned_duration: self.planned_duration,
+            end_on_next: self.end_on_next.unwrap_or(false),
+            client_attributes: self.client_attributes,
+            scte35_cmd: self.scte35_cmd,
+            scte35_out: self.scte35_out,
+            scte35_in: self.scte35_in,
+        })
+    }
+
+    /// Incorporates the given parameter (name, value) into the builder,
+    /// failing if the name doesn't match. Conversion failures are returned from `build()`.
+    pub(crate) fn incorporate(mut self, param_tuple: (&str, &str)) -> Self {
+        let (param_name, param_value) = param_tuple;
+        match param_name {
+            P_ID => self.id = Some(param_value.to_owned()),
+            P_CLASS => self.class = Some(param_value.to_owned()),
+            P_START_DATE => self.start_date = Some(param_value.to_owned()),
+            P_END_DATE => self.end_date = Some(param_value.to_owned()),
+            P_DURATION => {
+                self.duration = record(
+                    &mut self.error,
+                    value_from_param_str(P_DURATION, param_value),
+                )
+            }
+            P_PLANNED_DURATION => {
+                self.planned_duration = record(
+                    &mut self.error,
+                    value_from_param_str(P_PLANNED_DURATION, param_value),
+                )
+            }
+            P_END_ON_NEXT => {
+                self.end_on_next = record(
+                    &mut self.error,
+                    bool_from_param_str(P_END_ON_NEXT, param_value, self.lenient),
+                )
+            }
+            P_SCTE35_CMD => {
+                self.scte35_cmd = record(
+                    &mut self.error,
+                    hex_from_param_str(P_SCTE35_CMD, param_value),
+                )
+            }
+            P_SCTE35_OUT => {
+                self.scte35_out = record(
+                    &mut self.error,
+                    hex_from_param_str(P_SCTE35_OUT, param_value),
+                )
+            }
+            P_SCTE35_IN => {
+                self.scte35_in = record(
+                    &mut self.error,
+                    hex_from_param_str(P_SCTE35_IN, param_value),
+                )
+            }
+            _ if param_name.starts_with(P_CLIENT_ATTRIBUTE_PREFIX) => {
+                self.client_attributes.insert(param_name.to_owned(), param_value.to_owned());
+            }
+            _ => unreachable!("unhandled param {param_name} passed from parser"),
+        }
+        self
+    }
+}
+
+/// Returns an error listing every missing param of an element of type `kind`,
+/// so that all can be fixed at once. Each param name is paired with whether it's missing.
+fn check_missing(kind: &'static str, params: &[(&'static str, bool)]) -> Result<(), HlsParseError> {
+    let missing = params
+        .iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(param, _)| *param)
+        .collect::<Vec<_>>();
+    match missing.as_slice() {
+        [] => Ok(()),
+        [param] => Err(HlsParseError::MissingField(param)),
+        _ => Err(HlsParseError::MissingFields {
+            kind,
+            params: missing,
+        }),
+    }
+}
+
+/// Keeps the first error from converting a param value, to be returned when building.
+/// Returns the converted value on success.
+fn record<T>(error: &mut Option<HlsParseError>, res: Result<T, HlsParseError>) -> Option<T> {
+    match res {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error.get_or_insert(e);
+            None
+        }
+    }
+}
+
+/// Matches an HLS boolean parameter value. Throws an error if not exactly YES or NO,
+/// or if `lenient`, YES or NO in any case.
+fn bool_from_param_str(param: &'static str, s: &str, lenient: bool) -> Result<bool, HlsParseError> {
+    if s == "YES" || (lenient && s.eq_ignore_ascii_case("YES")) {
         Ok(true)
-    } else if s == "NO" {
+    } else if s == "NO" || (lenient && s.eq_ignore_ascii_case("NO")) {
         Ok(false)
     } else {
-        anyhow::bail!("could not match {s} to str repr of boolean value (YES/NO)")
+        Err(HlsParseError::InvalidValue {
+            param,
+            value: s.to_owned(),
+        })
+    }
+}
+
+/// Parses an HLS integer parameter value. Values that overflow `T` are an error.
+fn int_from_param_str<T: FromStr>(param: &'static str, s: &str) -> Result<T, HlsParseError> {
+    T::from_str(s).map_err(|_| HlsParseError::InvalidInt {
+        param,
+        value: s.to_owned(),
+    })
+}
+
+/// Parses an HLS `SCORE` parameter value, which must be a non-negative decimal.
+/// `-0` parses to `0.0`, so that scores compare the same under `==` and `total_cmp`.
+fn score_from_param_str(s: &str) -> Result<f32, HlsParseError> {
+    match f32::from_str(s) {
+        Ok(score) if score >= 0.0 => Ok(score.abs()),
+        _ => Err(HlsParseError::InvalidValue {
+            param: P_SCORE,
+            value: s.to_owned(),
+        }),
+    }
+}
+
+/// Parses an HLS `FRAME-RATE` parameter value, which must be a positive decimal without sign or exponent.
+/// Representations of the same rate (e.g. `30` and `30.000`) parse to the same value.
+fn frame_rate_from_param_str(s: &str) -> Result<f32, HlsParseError> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, "0"));
+    let is_decimal = !int.is_empty()
+        && !frac.is_empty()
+        && int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit());
+    match f32::from_str(s) {
+        Ok(rate) if is_decimal && rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(HlsParseError::InvalidValue {
+            param: P_FRAME_RATE,
+            value: s.to_owned(),
+        }),
+    }
+}
+
+/// Parses an HLS hexadecimal-sequence parameter value (`0x...`) into its bytes.
+fn hex_from_param_str(param: &'static str, s: &str) -> Result<Vec<u8>, HlsParseError> {
+    let invalid = || HlsParseError::InvalidValue {
+        param,
+        value: s.to_owned(),
+    };
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .ok_or_else(invalid)?;
+    if digits.is_empty() || digits.len() % 2 != 0 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+/// Parses an HLS parameter value into any type implementing `FromStr`.
+pub(crate) fn value_from_param_str<T: FromStr>(
+    param: &'static str,
+    s: &str,
+) -> Result<T, HlsParseError> {
+    T::from_str(s).map_err(|_| HlsParseError::InvalidValue {
+        param,
+        value: s.to_owned(),
+    })
+}
+
+// ---------- Conversion of borrowed tags, by folding their params into builders ----------
+
+impl TagRef<'_> {
+    /// Fold this tag's params into `builder`.
+    fn fold<B>(&self, builder: B, incorporate: impl Fn(B, (&str, &str)) -> B) -> B {
+        self.params.iter().fold(builder, |b, &param| incorporate(b, param))
+    }
+
+    /// Source text of this tag, if `options` keep it.
+    fn kept_raw(&self, options: &ParseOptions) -> Option<String> {
+        options.keep_raw.then(|| self.raw.to_owned())
+    }
+
+    pub(crate) fn to_audio(&self, options: &ParseOptions) -> Result<Audio, HlsParseError> {
+        let mut builder = self.fold(AudioBuilder::new(options), AudioBuilder::incorporate);
+        builder.raw = self.kept_raw(options);
+        builder.build(self.line)
+    }
+
+    pub(crate) fn to_video_rendition(
+        &self,
+        options: &ParseOptions,
+    ) -> Result<VideoRendition, HlsParseError> {
+        let mut builder = self.fold(
+            VideoRenditionBuilder::new(options),
+            VideoRenditionBuilder::incorporate,
+        );
+        builder.raw = self.kept_raw(options);
+        builder.build(self.line)
+    }
+
+    pub(crate) fn to_subtitle_rendition(
+        &self,
+        options: &ParseOptions,
+    ) -> Result<SubtitleRendition, HlsParseError> {
+        let mut builder = self.fold(
+            SubtitleRenditionBuilder::new(options),
+            SubtitleRenditionBuilder::incorporate,
+        );
+        builder.raw = self.kept_raw(options);
+        builder.build(self.line)
+    }
+
+    pub(crate) fn to_stream_info(
+        &self,
+        options: &ParseOptions,
+    ) -> Result<StreamInfo, HlsParseError> {
+        let mut builder = self.fold(StreamInfoBuilder::default(), StreamInfoBuilder::incorporate);
+        builder.common.uri = self.uri.map(str::to_owned);
+        builder.common.raw = self.kept_raw(options);
+        builder.build()
+    }
+
+    pub(crate) fn to_iframe_stream_info(
+        &self,
+        options: &ParseOptions,
+    ) -> Result<IframeStreamInfo, HlsParseError> {
+        let mut builder = self.fold(
+            IframeStreamInfoBuilder::default(),
+            IframeStreamInfoBuilder::incorporate,
+        );
+        builder.common.raw = self.kept_raw(options);
+        builder.build()
+    }
+
+    pub(crate) fn to_content_steering(&self) -> Result<ContentSteering, HlsParseError> {
+        self.fold(
+            ContentSteeringBuilder::default(),
+            ContentSteeringBuilder::incorporate,
+        )
+        .build()
     }
 }
@@ -1,18 +1,62 @@
 //! Constants used for HLS parsing.
 //! P_* are strs that match HLS parameter names.
 
-pub(crate) const P_AUDIO: &'static str = "AUDIO";
-pub(crate) const P_AUTOSELECT: &'static str = "AUTOSELECT";
-pub(crate) const P_AVERAGE_BANDWIDTH: &'static str = "AVERAGE-BANDWIDTH";
-pub(crate) const P_BANDWIDTH: &'static str = "BANDWIDTH";
-pub(crate) const P_CHANNELS: &'static str = "CHANNELS";
-pub(crate) const P_CLOSED_CAPTIONS: &'static str = "CLOSED-CAPTIONS";
-pub(crate) const P_CODECS: &'static str = "CODECS";
-pub(crate) const P_DEFAULT: &'static str = "DEFAULT";
-pub(crate) const P_FRAME_RATE: &'static str = "FRAME-RATE";
-pub(crate) const P_GROUP_ID: &'static str = "GROUP-ID";
-pub(crate) const P_LANGUAGE: &'static str = "LANGUAGE";
-pub(crate) const P_NAME: &'static str = "NAME";
-pub(crate) const P_RESOLUTION: &'static str = "RESOLUTION";
-pub(crate) const P_URI: &'static str = "URI";
-pub(crate) const P_VIDEO_RANGE: &'static str = "VIDEO-RANGE";
+pub(crate) const P_ASSOC_LANGUAGE: &str = "ASSOC-LANGUAGE";
+pub(crate) const P_ALLOWED_CPC: &str = "ALLOWED-CPC";
+pub(crate) const P_AUDIO: &str = "AUDIO";
+pub(crate) const P_AUTOSELECT: &str = "AUTOSELECT";
+pub(crate) const P_AVERAGE_BANDWIDTH: &str = "AVERAGE-BANDWIDTH";
+pub(crate) const P_BANDWIDTH: &str = "BANDWIDTH";
+pub(crate) const P_BIT_DEPTH: &str = "BIT-DEPTH";
+pub(crate) const P_BYTERANGE: &str = "BYTERANGE";
+pub(crate) const P_BYTERANGE_LENGTH: &str = "BYTERANGE-LENGTH";
+pub(crate) const P_BYTERANGE_START: &str = "BYTERANGE-START";
+pub(crate) const P_CHANNELS: &str = "CHANNELS";
+pub(crate) const P_CHARACTERISTICS: &str = "CHARACTERISTICS";
+pub(crate) const P_CLASS: &str = "CLASS";
+pub(crate) const P_CLIENT_ATTRIBUTE_PREFIX: &str = "X-";
+pub(crate) const P_CLOSED_CAPTIONS: &str = "CLOSED-CAPTIONS";
+pub(crate) const P_CODECS: &str = "CODECS";
+pub(crate) const P_DEFAULT: &str = "DEFAULT";
+pub(crate) const P_DURATION: &str = "DURATION";
+pub(crate) const P_END_DATE: &str = "END-DATE";
+pub(crate) const P_END_ON_NEXT: &str = "END-ON-NEXT";
+pub(crate) const P_FORCED: &str = "FORCED";
+pub(crate) const P_FRAME_RATE: &str = "FRAME-RATE";
+pub(crate) const P_GAP: &str = "GAP";
+pub(crate) const P_GROUP_ID: &str = "GROUP-ID";
+pub(crate) const P_ID: &str = "ID";
+pub(crate) const P_IMPORT: &str = "IMPORT";
+pub(crate) const P_INDEPENDENT: &str = "INDEPENDENT";
+pub(crate) const P_INSTREAM_ID: &str = "INSTREAM-ID";
+pub(crate) const P_IV: &str = "IV";
+pub(crate) const P_KEYFORMAT: &str = "KEYFORMAT";
+pub(crate) const P_KEYFORMATVERSIONS: &str = "KEYFORMATVERSIONS";
+pub(crate) const P_LAST_MSN: &str = "LAST-MSN";
+pub(crate) const P_LAST_PART: &str = "LAST-PART";
+pub(crate) const P_LANGUAGE: &str = "LANGUAGE";
+pub(crate) const P_METHOD: &str = "METHOD";
+pub(crate) const P_NAME: &str = "NAME";
+pub(crate) const P_PART_TARGET: &str = "PART-TARGET";
+pub(crate) const P_PATHWAY_ID: &str = "PATHWAY-ID";
+pub(crate) const P_PLANNED_DURATION: &str = "PLANNED-DURATION";
+pub(crate) const P_PROGRAM_ID: &str = "PROGRAM-ID";
+pub(crate) const P_QUERYPARAM: &str = "QUERYPARAM";
+pub(crate) const P_RECENTLY_REMOVED_DATERANGES: &str = "RECENTLY-REMOVED-DATERANGES";
+pub(crate) const P_RESOLUTION: &str = "RESOLUTION";
+pub(crate) const P_SAMPLE_RATE: &str = "SAMPLE-RATE";
+pub(crate) const P_SCORE: &str = "SCORE";
+pub(crate) const P_SCTE35_CMD: &str = "SCTE35-CMD";
+pub(crate) const P_SCTE35_IN: &str = "SCTE35-IN";
+pub(crate) const P_SCTE35_OUT: &str = "SCTE35-OUT";
+pub(crate) const P_SERVER_URI: &str = "SERVER-URI";
+pub(crate) const P_SKIPPED_SEGMENTS: &str = "SKIPPED-SEGMENTS";
+pub(crate) const P_STABLE_RENDITION_ID: &str = "STABLE-RENDITION-ID";
+pub(crate) const P_STABLE_VARIANT_ID: &str = "STABLE-VARIANT-ID";
+pub(crate) const P_START_DATE: &str = "START-DATE";
+pub(crate) const P_SUPPLEMENTAL_CODECS: &str = "SUPPLEMENTAL-CODECS";
+pub(crate) const P_TYPE: &str = "TYPE";
+pub(crate) const P_URI: &str = "URI";
+pub(crate) const P_VALUE: &str = "VALUE";
+pub(crate) const P_VIDEO: &str = "VIDEO";
+pub(crate) const P_VIDEO_RANGE: &str = "VIDEO-RANGE";
@@ -0,0 +1,250 @@
+//! Typed errors returned when parsing an HLS playlist.
+
+use std::fmt::Display;
+
+use crate::constants::*;
+
+/// Reasons an HLS playlist can fail to parse.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum HlsParseError {
+    /// Input didn't match any supported HLS element. Holds where and why.
+    Syntax(ParseError),
+    /// A required param (or tag) is missing. Holds the param name.
+    MissingField(&'static str),
+    /// Several required params of an element are missing.
+    /// Holds the kind of element (e.g. `audio`) and the param names.
+    MissingFields {
+        kind: &'static str,
+        params: Vec<&'static str>,
+    },
+    /// A param expected to be an integer couldn't be parsed as one.
+    InvalidInt { param: &'static str, value: String },
+    /// A param value couldn't be converted to its expected type.
+    InvalidValue { param: &'static str, value: String },
+    /// An `#EXT-X-MEDIA` tag is missing its mandatory `GROUP-ID`.
+    /// Holds what identifies the rendition otherwise, to locate it in the playlist.
+    MissingGroupId {
+        name: Option<String>,
+        language: Option<String>,
+        /// 1-based line number of the tag
+        line: usize,
+    },
+    /// The playlist doesn't begin with the `#EXTM3U` header.
+    MissingHeader,
+    /// Playlist bytes aren't valid UTF-8. Holds the byte offset of the first invalid sequence.
+    InvalidUtf8 { offset: usize },
+    /// An element was recognized, but is invalid in the context of the playlist.
+    InvalidElement(String),
+    /// A tag that may appear at most once occurs again with a different value.
+    DuplicateTag {
+        tag: &'static str,
+        first: String,
+        second: String,
+        /// 1-based line number of the second occurrence
+        line: usize,
+    },
+    /// A playlist contains both master-only and media-only tags, so it's neither kind.
+    /// Holds the first tag of each kind and its 1-based line number.
+    MixedPlaylist {
+        master_tag: String,
+        master_line: usize,
+        media_tag: String,
+        media_line: usize,
+    },
+    /// An `#EXT-X-*` tag isn't known to this crate, with `ParseOptions::error_on_unknown_tags` set.
+    UnknownTag {
+        /// Name of the tag, e.g. `#EXT-X-CUSTOM`
+        tag: String,
+        /// 1-based line number of the tag
+        line: usize,
+    },
+    /// A media segment follows `#EXT-X-ENDLIST`, which ends the playlist.
+    /// Accepted with `ParseOptions::lenient` set.
+    SegmentAfterEndList {
+        /// 1-based line number of the segment's URI
+        line: usize,
+    },
+    /// A variable reference (`{$name}`) names a variable that no `#EXT-X-DEFINE` declares.
+    UndefinedVariable {
+        name: String,
+        /// 1-based line number of the reference
+        line: usize,
+    },
+    /// An `#EXT-X-DEFINE` declares a variable whose name an earlier one already declared.
+    DuplicateVariable {
+        name: String,
+        /// 1-based line number of the second declaration
+        line: usize,
+    },
+}
+
+impl Display for HlsParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HlsParseError::Syntax(e) => write!(f, "{e}"),
+            HlsParseError::MissingField(param) => write!(f, "missing HLS param {param}"),
+            HlsParseError::MissingFields { kind, params } => {
+                write!(f, "missing HLS {kind} params: {}", params.join(", "))
+            }
+            HlsParseError::InvalidInt { param, value } => {
+                write!(f, "failed to parse HLS param {param} as int: {value}")
+            }
+            HlsParseError::InvalidValue { param, value } => {
+                write!(f, "invalid value for HLS param {param}: {value}")
+            }
+            HlsParseError::MissingGroupId {
+                name,
+                language,
+                line,
+            } => {
+                write!(
+                    f,
+                    "missing HLS param {P_GROUP_ID} on #EXT-X-MEDIA at line {line}"
+                )?;
+                if let Some(name) = name {
+                    write!(f, " ({P_NAME}={name:?}")?;
+                    match language {
+                        Some(language) => write!(f, ", {P_LANGUAGE}={language:?})"),
+                        None => write!(f, ")"),
+                    }
+                } else if let Some(language) = language {
+                    write!(f, " ({P_LANGUAGE}={language:?})")
+                } else {
+                    Ok(())
+                }
+            }
+            HlsParseError::MissingHeader => write!(f, "playlist must begin with #EXTM3U"),
+            HlsParseError::InvalidUtf8 { offset } => {
+                write!(f, "playlist is not valid UTF-8 at byte offset {offset}")
+            }
+            HlsParseError::InvalidElement(e) => write!(f, "{e}"),
+            HlsParseError::DuplicateTag {
+                tag,
+                first,
+                second,
+                line,
+            } => write!(
+                f,
+                "duplicate {tag} at line {line} conflicts with earlier value: {second} != {first}"
+            ),
+            HlsParseError::MixedPlaylist {
+                master_tag,
+                master_line,
+                media_tag,
+                media_line,
+            } => write!(
+                f,
+                "playlist mixes master tag {master_tag} at line {master_line} with media tag {media_tag} at line {media_line}"
+            ),
+            HlsParseError::UnknownTag { tag, line } => {
+                write!(f, "unknown tag {tag} at line {line}")
+            }
+            HlsParseError::SegmentAfterEndList { line } => {
+                write!(f, "segment at line {line} follows #EXT-X-ENDLIST")
+            }
+            HlsParseError::UndefinedVariable { name, line } => {
+                write!(f, "undefined variable {name:?} referenced at line {line}")
+            }
+            HlsParseError::DuplicateVariable { name, line } => {
+                write!(f, "variable {name:?} declared again at line {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HlsParseError {}
+
+/// Location and description of input that isn't valid HLS syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// 1-based line number where parsing failed
+    pub line: usize,
+    /// 1-based column, in characters, where parsing failed
+    pub column: usize,
+    /// Human-readable description, including the offending text
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+/// Kinds of syntax errors, describing why a `ParseError` occurred.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A line doesn't match any supported HLS element.
+    UnrecognizedElement,
+    /// An element parsed, but is followed by unexpected input, e.g. when parsing a single tag.
+    TrailingInput,
+    /// A URI line in a master playlist doesn't follow the `#EXT-X-STREAM-INF` tag it belongs to.
+    UnexpectedUri,
+}
+
+/// A line or element skipped when parsing a playlist on a best-effort basis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    /// 1-based line number where the skipped line or element starts
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Problems found when validating a successfully parsed playlist.
+/// These don't prevent parsing, but indicate a playlist that doesn't follow the HLS spec.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// A language param isn't a well-formed BCP-47 language tag.
+    InvalidLanguageTag { param: &'static str, value: String },
+    /// More than one rendition in a group is `DEFAULT=YES`. Holds the `GROUP-ID`.
+    MultipleDefaultsInGroup(String),
+    /// A rendition is `DEFAULT=YES` without being `AUTOSELECT=YES`.
+    DefaultNotAutoselect { group_id: String, name: String },
+    /// The declared `#EXT-X-VERSION` is lower than the playlist's tags and attributes require.
+    /// A missing version tag declares version 1.
+    VersionTooLow { declared: usize, required: usize },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidLanguageTag { param, value } => {
+                write!(f, "HLS param {param} is not a BCP-47 language tag: {value}")
+            }
+            ValidationError::MultipleDefaultsInGroup(group_id) => {
+                write!(
+                    f,
+                    "more than one rendition in group {group_id:?} is {P_DEFAULT}=YES"
+                )
+            }
+            ValidationError::DefaultNotAutoselect { group_id, name } => {
+                write!(
+                    f,
+                    "rendition {name:?} in group {group_id:?} is {P_DEFAULT}=YES but not {P_AUTOSELECT}=YES"
+                )
+            }
+            ValidationError::VersionTooLow { declared, required } => {
+                write!(
+                    f,
+                    "#EXT-X-VERSION is {declared}, but the playlist's tags require version {required}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
@@ -0,0 +1,78 @@
+//! Handwritten checks for BCP-47 language tags (RFC 5646), as used by `LANGUAGE` and `ASSOC-LANGUAGE`.
+//!
+//! Tags are checked for being well-formed only, i.e. matching the `langtag` or `privateuse` syntax.
+//! Subtags aren't checked against the IANA registry, and irregular grandfathered tags are rejected.
+//! Primary language subtags must be 2-3 letters, as no longer subtags are registered.
+
+use std::iter::Peekable;
+
+/// Whether `tag` is a well-formed BCP-47 language tag, compared case-insensitively.
+pub(crate) fn is_well_formed_language_tag(tag: &str) -> bool {
+    if tag
+        .split('-')
+        .any(|s| s.is_empty() || s.len() > 8 || !s.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return false;
+    }
+    let mut subtags = tag.split('-').peekable();
+
+    // Tags may consist solely of private use subtags
+    if subtags.peek().is_some_and(|s| s.eq_ignore_ascii_case("x")) {
+        return private_use(&mut subtags) && subtags.next().is_none();
+    }
+
+    // language, with up to 3 extended language subtags
+    if !subtags.next().is_some_and(|s| is_alpha(s, 2..=3)) {
+        return false;
+    }
+    for _ in 0..3 {
+        if subtags.next_if(|s| is_alpha(s, 3..=3)).is_none() {
+            break;
+        }
+    }
+    // script
+    subtags.next_if(|s| is_alpha(s, 4..=4));
+    // region
+    subtags.next_if(|s| is_alpha(s, 2..=2) || (s.len() == 3 && is_digits(s)));
+    // variants
+    while subtags
+        .next_if(|s| s.len() >= 5 || (s.len() == 4 && s.starts_with(|c: char| c.is_ascii_digit())))
+        .is_some()
+    {}
+    // extensions: a singleton followed by one or more 2-8 char subtags
+    while subtags
+        .next_if(|s| s.len() == 1 && !s.eq_ignore_ascii_case("x"))
+        .is_some()
+    {
+        if subtags.next_if(|s| s.len() >= 2).is_none() {
+            return false;
+        }
+        while subtags.next_if(|s| s.len() >= 2).is_some() {}
+    }
+    // private use
+    if subtags.peek().is_some_and(|s| s.eq_ignore_ascii_case("x")) && !private_use(&mut subtags) {
+        return false;
+    }
+
+    subtags.next().is_none()
+}
+
+/// Consume an `x` singleton and the 1-8 char subtags that follow it, of which there must be at least one.
+fn private_use<'a>(subtags: &mut Peekable<impl Iterator<Item = &'a str>>) -> bool {
+    subtags.next();
+    let mut count = 0;
+    while subtags.next().is_some() {
+        count += 1;
+    }
+    count > 0
+}
+
+/// Whether `s` is made of ASCII letters, with a length in `len`.
+fn is_alpha(s: &str, len: std::ops::RangeInclusive<usize>) -> bool {
+    len.contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Whether `s` is made of ASCII digits.
+fn is_digits(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_digit())
+}
@@ -5,40 +5,1093 @@
 
 mod builders;
 mod constants;
+mod error;
+#[cfg(feature = "bcp47")]
+mod language;
+pub mod parse;
 mod parsers;
+pub mod prelude;
+mod serializers;
+mod table;
 pub mod types;
+mod writer;
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::{fmt::Display, str::FromStr};
 
+use constants::*;
+pub use error::{HlsParseError, ParseError, ParseErrorKind, ParseWarning, ValidationError};
+/// The `nom` version used by [`mod@parse`], for composing its parsers with your own.
+pub use nom;
+pub use table::TableOptions;
+use types::define::Definition;
+use types::media::{Audio, InstreamId, SubtitleRendition, VideoRendition};
+use types::segment::KeyMethod;
+use types::steering::ContentSteering;
+use types::stream_info::{
+    BitrateSummary, Codec, HdrFormat, IframeStreamInfo, Resolution, StreamInfo,
+};
+pub use writer::PlaylistWriter;
+
+/// Parse a master or media playlist, whichever `data` is, detected from the tags it contains.
+/// See `Playlist::parse` for a typed error.
+pub fn parse(data: &str) -> anyhow::Result<Playlist> {
+    Ok(Playlist::parse(data)?)
+}
+
+/// Parse an HLS playlist, returning a typed error that callers can match on,
+/// rather than the `anyhow::Error` returned by `HlsPlaylist::from_str`.
+pub fn parse_hls_playlist_typed(data: &str) -> Result<HlsPlaylist, HlsParseError> {
+    parsers::parse_hls_playlist(data, &ParseOptions::default())
+}
+
+/// Estimated size in bytes of the variant `stream`, whose segments are listed by `media`:
+/// its peak bandwidth over the total duration of its segments.
+/// A media playlist without segments is estimated at 0 bytes.
+pub fn estimate_variant_size(stream: &StreamInfo, media: &MediaPlaylist) -> u64 {
+    (stream.common.bandwidth as f64 / 8.0 * media.total_duration()).round() as u64
+}
+
+/// Minimum `#EXT-X-VERSION` a playlist must declare to use the attribute named `attr`,
+/// per the protocol version compatibility rules of the HLS spec (RFC 8216, section 7).
+/// Attributes available in every version return 1.
+pub fn min_version_for_attr(attr: &str) -> usize {
+    match attr {
+        P_IV => 2,
+        P_KEYFORMAT | P_KEYFORMATVERSIONS => 5,
+        P_QUERYPARAM => 11,
+        _ if attr.starts_with("REQ-") => 12,
+        _ => 1,
+    }
+}
+
+/// Minimum `#EXT-X-VERSION` a playlist must declare to use the `#EXT-X-DEFINE` tags `definitions`.
+fn min_version_for_definitions(definitions: &[Definition]) -> usize {
+    definitions
+        .iter()
+        .map(|d| match d {
+            Definition::QueryParam(_) => min_version_for_attr(P_QUERYPARAM),
+            Definition::Value { .. } | Definition::Import(_) => 8,
+        })
+        .fold(1, usize::max)
+}
+
+/// Report `declared`, a playlist's `#EXT-X-VERSION` (0 if absent, i.e. version 1),
+/// if it's lower than the `required` version.
+fn check_version(declared: usize, required: usize, errors: &mut Vec<ValidationError>) {
+    let declared = declared.max(1);
+    if declared < required {
+        errors.push(ValidationError::VersionTooLow { declared, required });
+    }
+}
+
 /// Represents a parsed HLS playlist, supporting various `#EXT-X-*` extensions.
-#[derive(Default, Debug)]
+///
+/// `==` compares every field, including the order of streams and comments.
+/// See `semantically_equal` to ignore such cosmetic differences.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct HlsPlaylist {
     // FIXME: These fields contain `Vec`s wrapped in other types, in order to impl std::fmt::Display.
     //        Unwrap these, for ergonomics (avoid `.inner`), and implement display of these types another way.
     pub audio_streams: types::media::AudioStreams,
+    /// Alternative video renditions (`#EXT-X-MEDIA:TYPE=VIDEO`), referenced by a stream's `VIDEO` param
+    pub video_renditions: types::media::VideoRenditions,
+    /// Subtitle renditions (`#EXT-X-MEDIA:TYPE=SUBTITLES`)
+    pub subtitles: Vec<types::media::SubtitleRendition>,
     pub streams: types::stream_info::Streams,
     pub iframe_streams: types::stream_info::IframeStreams,
     /// Playlist protocol version
     pub version: usize,
+    /// Whether `#EXT-X-INDEPENDENT-SEGMENTS` is present
+    pub independent_segments: bool,
+    /// Content steering server and initial pathway (`#EXT-X-CONTENT-STEERING`)
+    pub content_steering: Option<types::steering::ContentSteering>,
+    /// Variables declared by `#EXT-X-DEFINE`, in playlist order
+    pub definitions: Vec<types::define::Definition>,
+    /// `#EXT-X-*` tags not recognized by the parser, preserved for serialization
+    pub unknown_tags: Vec<types::unknown::UnknownTag>,
+    /// Plain comment lines (not `#EXT` tags), without the leading `#`, in playlist order
+    pub comments: Vec<String>,
+}
+
+/// Either kind of HLS playlist, for input that may be a master or media playlist.
+#[derive(Debug, Clone)]
+pub enum Playlist {
+    Master(HlsPlaylist),
+    Media(MediaPlaylist),
+}
+
+impl Playlist {
+    /// Parse a master or media playlist, detected from the tags it contains.
+    /// Fails if it contains both master-only tags (e.g. `#EXT-X-STREAM-INF`)
+    /// and media-only tags (e.g. `#EXTINF`), rather than ignoring those of one kind.
+    pub fn parse(data: &str) -> Result<Self, HlsParseError> {
+        parsers::parse_playlist(data, &ParseOptions::default())
+    }
+}
+
+/// Represents a parsed HLS media playlist: the segments that make up a single stream.
+#[derive(Default, Debug, Clone)]
+#[non_exhaustive]
+pub struct MediaPlaylist {
+    /// Playlist protocol version
+    pub version: usize,
+    /// Upper bound on segment duration in seconds (`#EXT-X-TARGETDURATION`)
+    pub target_duration: usize,
+    /// Media sequence number of the first segment (`#EXT-X-MEDIA-SEQUENCE`)
+    pub media_sequence: usize,
+    /// Whether `#EXT-X-I-FRAMES-ONLY` is present, i.e. each segment is a single I-frame
+    pub iframes_only: bool,
+    /// Whether `#EXT-X-ENDLIST` is present, i.e. no more segments will be added
+    pub end_list: bool,
+    /// Variables declared by `#EXT-X-DEFINE`, in playlist order
+    pub definitions: Vec<types::define::Definition>,
+    pub segments: Vec<types::segment::Segment>,
+    /// First initialization section (`#EXT-X-MAP`) in the playlist.
+    /// See each segment's `map` for the one that applies to it, as it may change mid-playlist.
+    pub map: Option<types::segment::MediaInitialization>,
+    /// Date ranges (`#EXT-X-DATERANGE`), e.g. ad markers, in playlist order
+    pub date_ranges: Vec<types::date_range::DateRange>,
+    /// `#EXT-X-*` tags not recognized by the parser, in playlist order
+    pub unknown_tags: Vec<types::unknown::UnknownTag>,
+    /// Target duration of partial segments in seconds (`#EXT-X-PART-INF`), for low-latency playlists
+    pub part_target: Option<f32>,
+    /// Parts after the last segment, belonging to a segment that isn't complete yet
+    pub trailing_parts: Vec<types::segment::Part>,
+    /// Resources the client may request before they're listed (`#EXT-X-PRELOAD-HINT`)
+    pub preload_hints: Vec<types::segment::PreloadHint>,
+    /// Latest segments of other renditions (`#EXT-X-RENDITION-REPORT`), in playlist order
+    pub rendition_reports: Vec<types::segment::RenditionReport>,
+    /// Segments omitted from a delta playlist update (`#EXT-X-SKIP`), which precede `segments`
+    pub skip: Option<types::segment::Skip>,
+}
+
+/// Options controlling how strictly an HLS playlist is parsed.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    /// Tolerate sloppy encoders: match param names regardless of case,
+    /// trim param values, accept YES/NO in any case, discard `; comments` after a tag's params,
+    /// don't require the `#EXTM3U` header, and keep media segments following `#EXT-X-ENDLIST`.
+    pub lenient: bool,
+    /// Fail on a repeated single-valued tag (e.g. `#EXT-X-VERSION`) whose value differs
+    /// from its first occurrence. When unset, the last occurrence wins. Set by default.
+    pub strict_duplicates: bool,
+    /// Record the source text of each audio, video rendition and (iframe) stream tag in its
+    /// `raw` field, e.g. to diff parsed data against the original. The text is recorded as written,
+    /// before variable references are substituted. Unset by default.
+    pub keep_raw: bool,
+    /// Leave variable references (`{$name}`) in attribute values and URIs as they are,
+    /// rather than substituting values declared by `#EXT-X-DEFINE`. Unset by default.
+    pub keep_variable_references: bool,
+    /// Fail on the first `#EXT-X-*` tag that isn't part of HLS as known to this crate, e.g. `#EXT-X-CUSTOM`,
+    /// rather than keeping it in `unknown_tags`. HLS tags kept there (e.g. `#EXT-X-START`) are still accepted.
+    /// Unset by default.
+    pub error_on_unknown_tags: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            lenient: false,
+            strict_duplicates: true,
+            keep_raw: false,
+            keep_variable_references: false,
+            error_on_unknown_tags: false,
+        }
+    }
+}
+
+/// What a master playlist contains, as reported by `HlsPlaylist::feature_summary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FeatureSummary {
+    /// Number of audio renditions
+    pub audio_count: usize,
+    /// Number of video streams (`#EXT-X-STREAM-INF`)
+    pub video_count: usize,
+    /// Number of iframe streams
+    pub iframe_count: usize,
+    /// See `HlsPlaylist::has_atmos`
+    pub has_atmos: bool,
+    /// See `HlsPlaylist::has_dolby_vision`
+    pub has_dolby_vision: bool,
+    /// Whether any video or iframe stream is HDR (`VIDEO-RANGE` of `PQ` or `HLG`)
+    pub has_hdr: bool,
+    /// Whether any `#EXT-X-MEDIA:TYPE=SUBTITLES` rendition is present
+    pub has_subtitles: bool,
+    /// Protocol version declared by `#EXT-X-VERSION`, or 1 if the playlist doesn't declare one
+    pub declared_version: usize,
+    /// Highest protocol version required by any tag or attribute the playlist uses,
+    /// i.e. the version it must declare at least, per `HlsPlaylist::min_required_version`
+    pub required_version: usize,
+}
+
+/// Segment changes between two versions of a media playlist, as reported by `MediaPlaylist::diff`.
+/// Each segment is paired with its media sequence number.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct PlaylistDiff<'a> {
+    /// Segments of the new playlist that the old one lacks, in playlist order
+    pub added: Vec<(usize, &'a types::segment::Segment)>,
+    /// Segments of the old playlist that the new one lacks, in playlist order
+    pub removed: Vec<(usize, &'a types::segment::Segment)>,
+    /// Segments in both playlists whose other attributes (e.g. duration or key) differ,
+    /// in playlist order, each with its old and new version
+    pub changed: Vec<(
+        usize,
+        &'a types::segment::Segment,
+        &'a types::segment::Segment,
+    )>,
+}
+
+impl PlaylistDiff<'_> {
+    /// Whether the playlists have the same segments.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A master playlist whose tag params borrow from the input, avoiding a copy of each value.
+/// Params are converted into typed fields by `into_owned`, which `HlsPlaylist` parsing delegates to.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HlsPlaylistRef<'a> {
+    pub audio_streams: Vec<types::borrowed::TagRef<'a>>,
+    pub video_renditions: Vec<types::borrowed::TagRef<'a>>,
+    pub subtitles: Vec<types::borrowed::TagRef<'a>>,
+    pub streams: Vec<types::borrowed::TagRef<'a>>,
+    pub iframe_streams: Vec<types::borrowed::TagRef<'a>>,
+    /// Playlist protocol version
+    pub version: usize,
+    /// Whether `#EXT-X-INDEPENDENT-SEGMENTS` is present
+    pub independent_segments: bool,
+    pub content_steering: Option<types::borrowed::TagRef<'a>>,
+    pub definitions: Vec<types::define::Definition>,
+    pub unknown_tags: Vec<types::borrowed::UnknownTagRef<'a>>,
+    pub comments: Vec<&'a str>,
+    /// Options the playlist was parsed with, which also apply to param conversion
+    options: ParseOptions,
+}
+
+impl<'a> HlsPlaylistRef<'a> {
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            audio_streams: Vec::new(),
+            video_renditions: Vec::new(),
+            subtitles: Vec::new(),
+            streams: Vec::new(),
+            iframe_streams: Vec::new(),
+            version: 0,
+            independent_segments: false,
+            content_steering: None,
+            definitions: Vec::new(),
+            unknown_tags: Vec::new(),
+            comments: Vec::new(),
+            options: *options,
+        }
+    }
+
+    /// Parse a master playlist, borrowing tag params from `data` rather than copying them.
+    /// Variable references (`{$name}`) are left as they are, since substituting them
+    /// would need values that don't borrow from `data`.
+    pub fn parse(data: &'a str) -> Result<Self, HlsParseError> {
+        Self::parse_with_options(data, &ParseOptions::default())
+    }
+
+    /// Parse a master playlist as `parse` does, with parsing behavior controlled by `options`.
+    pub fn parse_with_options(
+        data: &'a str,
+        options: &ParseOptions,
+    ) -> Result<Self, HlsParseError> {
+        parsers::parse_hls_playlist_ref(data, options)
+    }
+
+    /// Convert into an owned `HlsPlaylist`, failing on the first param that doesn't convert
+    /// (e.g. a non-numeric `BANDWIDTH`) or required param that is missing.
+    pub fn into_owned(self) -> Result<HlsPlaylist, HlsParseError> {
+        parsers::playlist_from_ref(self, |e, _| Err(e))
+    }
+}
+
+impl HlsPlaylist {
+    /// Parse an HLS playlist, with parsing behavior controlled by `options`.
+    pub fn from_str_with_options(data: &str, options: &ParseOptions) -> anyhow::Result<Self> {
+        Ok(parsers::parse_hls_playlist(data, options)?)
+    }
+
+    /// Parse as much of the start of `data` as forms an HLS playlist, e.g. a playlist followed by unrelated data.
+    /// Returns the playlist and the unparsed remainder, which begins at the first line that isn't a playlist element.
+    pub fn from_str_prefix(data: &str) -> Result<(Self, &str), HlsParseError> {
+        parsers::parse_hls_playlist_prefix(data, &ParseOptions::default())
+    }
+
+    /// Fetch an HLS playlist over HTTP and parse it, as `from_str` does.
+    /// Fails if the request fails or the server responds with an error status.
+    #[cfg(feature = "fetch")]
+    pub fn from_url(url: &str) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let data = reqwest::blocking::get(url)
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to GET playlist from {url}"))?
+            .text()?;
+        Self::from_str(&data)
+    }
+
+    /// Parse an HLS playlist from raw bytes, which must be UTF-8.
+    /// Unlike converting to `&str` first, failure reports the offset of the first invalid byte sequence.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, HlsParseError> {
+        Self::from_bytes_with_options(data, &ParseOptions::default())
+    }
+
+    /// Parse an HLS playlist from raw bytes, as `from_bytes` does, with parsing behavior controlled by `options`.
+    /// With `lenient` set, invalid UTF-8 sequences are replaced with U+FFFD rather than failing.
+    pub fn from_bytes_with_options(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<Self, HlsParseError> {
+        let text = match std::str::from_utf8(data) {
+            Ok(text) => std::borrow::Cow::Borrowed(text),
+            Err(_) if options.lenient => String::from_utf8_lossy(data),
+            Err(e) => {
+                return Err(HlsParseError::InvalidUtf8 {
+                    offset: e.valid_up_to(),
+                });
+            }
+        };
+        parsers::parse_hls_playlist(&text, options)
+    }
+
+    /// Parse a master playlist, failing if it contains media playlist tags (e.g. `#EXTINF`),
+    /// rather than ignoring them as `from_str` does.
+    pub fn parse_master(data: &str) -> Result<Self, HlsParseError> {
+        parsers::parse_master_playlist_only(data, &ParseOptions::default())
+    }
+
+    /// Parse an HLS playlist on a best-effort basis, for playlists from sources that can't be fixed.
+    /// Lines and elements that fail to parse are skipped, with a warning for each,
+    /// rather than failing the whole parse as `from_str` does.
+    pub fn parse_lenient(data: &str) -> (Self, Vec<ParseWarning>) {
+        parsers::parse_hls_playlist_lenient(data, &ParseOptions::default())
+    }
+
+    /// Check the playlist for problems that don't prevent parsing, returning all that are found.
+    /// Audio groups may have at most one `DEFAULT=YES` rendition, which must also be `AUTOSELECT=YES`,
+    /// and the declared version must be at least `min_required_version`.
+    /// With the `bcp47` feature, this includes language params that aren't well-formed BCP-47 tags.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        check_version(self.version, self.min_required_version(), &mut errors);
+        let mut default_groups = HashSet::new();
+        let mut reported_groups = HashSet::new();
+        for audio in self.audio_streams.inner.iter().filter(|a| a.default) {
+            if !audio.auto_select {
+                errors.push(ValidationError::DefaultNotAutoselect {
+                    group_id: audio.group_id.clone(),
+                    name: audio.name.clone(),
+                });
+            }
+            if !default_groups.insert(&audio.group_id) && reported_groups.insert(&audio.group_id) {
+                errors.push(ValidationError::MultipleDefaultsInGroup(
+                    audio.group_id.clone(),
+                ));
+            }
+        }
+        #[cfg(feature = "bcp47")]
+        for audio in self.audio_streams.inner.iter() {
+            let languages = std::iter::once((P_LANGUAGE, &audio.language))
+                .chain(audio.assoc_language.iter().map(|l| (P_ASSOC_LANGUAGE, l)));
+            for (param, language) in languages {
+                if !language::is_well_formed_language_tag(language) {
+                    errors.push(ValidationError::InvalidLanguageTag {
+                        param,
+                        value: language.clone(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Minimum `#EXT-X-VERSION` the playlist must declare for the tags and attributes it uses,
+    /// per the protocol version compatibility rules of the HLS spec (RFC 8216, section 7).
+    /// Covers the attributes of `min_version_for_attr`, `#EXT-X-DEFINE` (8, or 11 for `QUERYPARAM`),
+    /// and `SERVICE` values of a closed-caption `INSTREAM-ID` (7). Other tags kept as unknown tags,
+    /// e.g. `#EXT-X-SESSION-KEY`, aren't checked.
+    pub fn min_required_version(&self) -> usize {
+        let has_service_captions = self.unknown_tags.iter().any(|t| {
+            matches!(
+                parsers::closed_captions_instream_id(&t.line),
+                Some(InstreamId::Service(_))
+            )
+        });
+        serializers::attribute_names(self)
+            .into_iter()
+            .map(min_version_for_attr)
+            .chain(std::iter::once(min_version_for_definitions(
+                &self.definitions,
+            )))
+            .chain(has_service_captions.then_some(7))
+            .fold(1, usize::max)
+    }
+
+    /// Serialize the playlist to M3U8 text.
+    /// Known elements are written grouped by type. Unknown tags captured during parsing are re-emitted
+    /// verbatim at their original index among elements, which is only their original place in the
+    /// playlist if it was already grouped that way.
+    pub fn to_m3u8(&self) -> String {
+        serializers::serialize_hls_playlist(self)
+    }
+
+    /// Plain comment lines (not `#EXT` tags), without the leading `#`, in playlist order.
+    /// Some toolchains embed metadata such as build provenance in comments.
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Render audio, video and iframe streams as human-readable tables.
+    /// Same as `format_table` with default options.
+    pub fn to_table(&self) -> String {
+        self.format_table(&TableOptions::default())
+    }
+
+    /// Set of HDR formats offered across all video and iframe streams in the playlist.
+    pub fn hdr_formats(&self) -> HashSet<HdrFormat> {
+        self.all_stream_commons()
+            .flat_map(|common| common.hdr_formats())
+            .collect()
+    }
+
+    /// Set of codecs (`CODECS` entries, verbatim) used across all video and iframe streams in the playlist.
+    pub fn all_codecs(&self) -> BTreeSet<String> {
+        self.all_stream_commons()
+            .flat_map(|common| common.codecs.iter().cloned())
+            .collect()
+    }
+
+    /// Set of codecs used across all video and iframe streams in the playlist, in structured form.
+    /// Codecs differing only in profile or level are counted once.
+    pub fn all_codecs_typed(&self) -> BTreeSet<Codec> {
+        self.all_stream_commons()
+            .flat_map(|common| common.parsed_codecs())
+            .collect()
+    }
+
+    /// URIs of all media playlists the playlist references, in the order of its sections:
+    /// audio, video renditions (those with a URI), subtitles, video streams, then iframe streams.
+    pub fn uris(&self) -> impl Iterator<Item = &str> {
+        self.audio_streams
+            .inner
+            .iter()
+            .map(|a| a.uri.as_str())
+            .chain(
+                self.video_renditions
+                    .inner
+                    .iter()
+                    .filter_map(|v| v.uri.as_deref()),
+            )
+            .chain(self.subtitles.iter().map(|s| s.uri.as_str()))
+            .chain(self.all_stream_commons().map(|common| common.uri.as_str()))
+    }
+
+    /// Common data of all video streams, followed by that of all iframe streams.
+    fn all_stream_commons(&self) -> impl Iterator<Item = &types::stream_info::StreamInfoCommon> {
+        self.streams
+            .inner
+            .iter()
+            .map(|s| &s.common)
+            .chain(self.iframe_streams.inner.iter().map(|s| &s.common))
+    }
+
+    /// Video streams grouped by resolution, smallest first. Streams keep playlist order within a group.
+    pub fn group_by_resolution(&self) -> BTreeMap<Resolution, Vec<&StreamInfo>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for s in self.streams.inner.iter() {
+            groups
+                .entry(s.common.resolution.clone())
+                .or_default()
+                .push(s);
+        }
+        groups
+    }
+
+    /// Video streams grouped by each codec in their `CODECS`, so a stream with
+    /// audio and video codecs appears under both. Streams keep playlist order within a group.
+    pub fn group_by_codec(&self) -> BTreeMap<String, Vec<&StreamInfo>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for s in self.streams.inner.iter() {
+            for codec in s.common.codecs.iter() {
+                groups.entry(codec.clone()).or_default().push(s);
+            }
+        }
+        groups
+    }
+
+    /// Estimated size in bytes of all video streams, per `estimate_variant_size`.
+    /// `media_playlists` maps stream URIs to their fetched media playlists;
+    /// streams without a media playlist there are left out of the estimate.
+    pub fn estimate_total_size(&self, media_playlists: &HashMap<String, MediaPlaylist>) -> u64 {
+        self.streams
+            .inner
+            .iter()
+            .filter_map(|s| {
+                let media = media_playlists.get(&s.common.uri)?;
+                Some(estimate_variant_size(s, media))
+            })
+            .sum()
+    }
+
+    /// Whether any audio rendition is Dolby Atmos: its `CHANNELS` signals Joint Object Coding
+    /// (e.g. `16/JOC`), or its `GROUP-ID` names Atmos.
+    /// E-AC-3 (`ec-3`) alone is Dolby Digital Plus, so isn't treated as Atmos.
+    pub fn has_atmos(&self) -> bool {
+        self.audio_streams
+            .inner
+            .iter()
+            .any(|a| a.channel_info.joc || a.group_id.to_ascii_lowercase().contains("atmos"))
+    }
+
+    /// Whether any video or iframe stream is Dolby Vision, by its codecs or supplemental codecs.
+    pub fn has_dolby_vision(&self) -> bool {
+        self.hdr_formats().contains(&HdrFormat::DolbyVision)
+    }
+
+    /// Summarize what the playlist contains: stream counts, notable audio and video formats,
+    /// and the protocol versions its attributes require.
+    pub fn feature_summary(&self) -> FeatureSummary {
+        let hdr_formats = self.hdr_formats();
+        FeatureSummary {
+            audio_count: self.audio_streams.inner.len(),
+            video_count: self.streams.inner.len(),
+            iframe_count: self.iframe_streams.inner.len(),
+            has_atmos: self.has_atmos(),
+            has_dolby_vision: hdr_formats.contains(&HdrFormat::DolbyVision),
+            has_hdr: hdr_formats.contains(&HdrFormat::Pq) || hdr_formats.contains(&HdrFormat::Hlg),
+            has_subtitles: !self.subtitles.is_empty(),
+            declared_version: self.version.max(1),
+            required_version: self.min_required_version(),
+        }
+    }
+
+    /// Whether two playlists describe the same content, ignoring cosmetic differences:
+    /// the order of audio, video and iframe streams, the order of params within tags,
+    /// comments, and where unknown tags appear.
+    /// More robust than comparing the ordered stream vectors, e.g. against a golden file.
+    pub fn semantically_equal(&self, other: &HlsPlaylist) -> bool {
+        let (a, b) = (self.normalized(), other.normalized());
+        let unknown_lines = |p: &HlsPlaylist| {
+            p.unknown_tags
+                .iter()
+                .map(|t| t.line.clone())
+                .collect::<Vec<_>>()
+        };
+        a.version == b.version
+            && a.independent_segments == b.independent_segments
+            && a.content_steering == b.content_steering
+            && a.definitions == b.definitions
+            && same_elements(&a.audio_streams.inner, &b.audio_streams.inner)
+            && same_elements(&a.video_renditions.inner, &b.video_renditions.inner)
+            && same_elements(&a.subtitles, &b.subtitles)
+            && same_elements(&a.streams.inner, &b.streams.inner)
+            && same_elements(&a.iframe_streams.inner, &b.iframe_streams.inner)
+            && same_elements(&unknown_lines(&a), &unknown_lines(&b))
+    }
+
+    /// Copy of the playlist without param order, which is only used for serialization,
+    /// or source text kept by `ParseOptions::keep_raw`.
+    fn normalized(&self) -> HlsPlaylist {
+        let mut res = self.clone();
+        for audio in res.audio_streams.inner.iter_mut() {
+            audio.attribute_order.clear();
+            audio.raw = None;
+        }
+        for video in res.video_renditions.inner.iter_mut() {
+            video.attribute_order.clear();
+            video.raw = None;
+        }
+        for subtitles in res.subtitles.iter_mut() {
+            subtitles.attribute_order.clear();
+            subtitles.raw = None;
+        }
+        for stream in res.streams.inner.iter_mut() {
+            stream.common.attribute_order.clear();
+            stream.common.raw = None;
+        }
+        for stream in res.iframe_streams.inner.iter_mut() {
+            stream.common.attribute_order.clear();
+            stream.common.raw = None;
+        }
+        if let Some(steering) = res.content_steering.as_mut() {
+            steering.attribute_order.clear();
+        }
+        res
+    }
+
+    /// Merge `other` into this playlist, e.g. an audio-only master into a video-only one.
+    /// Audio, video renditions, subtitles, streams and iframe streams of `other` follow those of `self`,
+    /// and the higher version of the two is kept. Call `dedup_streams` afterwards
+    /// to drop variants present in both.
+    ///
+    /// Where single-valued fields conflict, `self` is preferred: its content steering,
+    /// `#EXT-X-START` and variable definitions override those of `other`.
+    /// `#EXT-X-INDEPENDENT-SEGMENTS` applies to all media, so is kept only if both declare it.
+    /// Unknown tags and comments of `other` are placed after those of `self`.
+    pub fn merge(&mut self, other: HlsPlaylist) {
+        self.version = self.version.max(other.version);
+        self.independent_segments &= other.independent_segments;
+        if self.content_steering.is_none() {
+            self.content_steering = other.content_steering;
+        }
+        for definition in other.definitions {
+            if !self
+                .definitions
+                .iter()
+                .any(|d| d.name() == definition.name())
+            {
+                self.definitions.push(definition);
+            }
+        }
+
+        self.audio_streams.extend(other.audio_streams.inner);
+        self.video_renditions.extend(other.video_renditions.inner);
+        self.subtitles.extend(other.subtitles);
+        self.streams.extend(other.streams.inner);
+        self.iframe_streams.extend(other.iframe_streams.inner);
+
+        let has_start = self.unknown_tags.iter().any(|t| t.name() == "#EXT-X-START");
+        self.unknown_tags.extend(
+            other
+                .unknown_tags
+                .into_iter()
+                .filter(|t| !(has_start && t.name() == "#EXT-X-START"))
+                // Positions refer to elements of `other`, so serialize these after all elements
+                .map(|t| types::unknown::UnknownTag {
+                    position: usize::MAX,
+                    ..t
+                }),
+        );
+        self.comments.extend(other.comments);
+    }
+
+    /// Remove video streams that share a URI with an earlier stream, keeping the first occurrence.
+    pub fn dedup_streams(&mut self) {
+        let mut seen_uris = HashSet::new();
+        self.streams
+            .inner
+            .retain(|s| seen_uris.insert(s.common.uri.clone()));
+    }
+
+    /// Audio `GROUP-ID`s that contain conflicting renditions,
+    /// i.e. more than one rendition with the same `NAME` in the group.
+    /// Each colliding group ID is reported once, in order of first appearance.
+    pub fn duplicate_audio_group_ids(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for audio in self.audio_streams.inner.iter() {
+            if !seen.insert((&audio.group_id, &audio.name)) && !duplicates.contains(&audio.group_id)
+            {
+                duplicates.push(audio.group_id.clone());
+            }
+        }
+        duplicates
+    }
+
+    /// Whether this playlist's video streams form the same ABR ladder as `other`'s.
+    /// Streams are matched by resolution and codecs, and matched bandwidths may differ
+    /// by up to `bandwidth_tolerance`, a fraction of the larger bandwidth (e.g. 0.05 for 5%).
+    pub fn ladders_equivalent(&self, other: &HlsPlaylist, bandwidth_tolerance: f32) -> bool {
+        if self.streams.inner.len() != other.streams.inner.len() {
+            return false;
+        }
+        // Sort both ladders the same way so that equivalent rungs line up
+        fn sorted_rungs(playlist: &HlsPlaylist) -> Vec<((usize, usize), &Vec<String>, u64)> {
+            let mut rungs = playlist
+                .streams
+                .inner
+                .iter()
+                .map(|s| {
+                    let r = &s.common.resolution;
+                    ((r.width, r.height), &s.common.codecs, s.common.bandwidth)
+                })
+                .collect::<Vec<_>>();
+            rungs.sort();
+            rungs
+        }
+        sorted_rungs(self).into_iter().zip(sorted_rungs(other)).all(
+            |((res_a, codecs_a, bw_a), (res_b, codecs_b, bw_b))| {
+                let allowed_diff = bw_a.max(bw_b) as f32 * bandwidth_tolerance;
+                res_a == res_b
+                    && codecs_a == codecs_b
+                    && (bw_a.abs_diff(bw_b) as f32) <= allowed_diff
+            },
+        )
+    }
+
+    /// Statistics over the video streams' bandwidths, resolutions, video ranges and codecs.
+    /// Returns `None` if there are no video streams.
+    pub fn bitrate_summary(&self) -> Option<BitrateSummary> {
+        let streams = &self.streams.inner;
+        let bandwidths = streams.iter().map(|s| s.common.bandwidth);
+        let resolutions = streams.iter().map(|s| &s.common.resolution);
+        Some(BitrateSummary {
+            min_bandwidth: bandwidths.clone().min()?,
+            max_bandwidth: bandwidths.clone().max()?,
+            mean_bandwidth: bandwidths.map(|b| b as f64).sum::<f64>() / streams.len() as f64,
+            min_resolution: resolutions.clone().min()?.clone(),
+            max_resolution: resolutions.max()?.clone(),
+            video_range_count: streams
+                .iter()
+                .map(|s| &s.common.video_range)
+                .collect::<HashSet<_>>()
+                .len(),
+            codec_count: streams
+                .iter()
+                .flat_map(|s| s.common.codecs.iter())
+                .collect::<HashSet<_>>()
+                .len(),
+        })
+    }
+
+    /// The preferred video variant: the highest `SCORE` if any variant is scored,
+    /// otherwise the highest `BANDWIDTH`. Returns `None` if there are no variants.
+    pub fn best_variant(&self) -> Option<&StreamInfo> {
+        let streams = self.streams.inner.iter();
+        if streams.clone().any(|s| s.score.is_some()) {
+            // Scores are never negative, so unscored variants rank below all scored ones
+            streams.max_by(|x, y| {
+                let x_score = x.score.unwrap_or(-1.0);
+                let y_score = y.score.unwrap_or(-1.0);
+                x_score.total_cmp(&y_score)
+            })
+        } else {
+            streams.max_by_key(|s| s.common.bandwidth)
+        }
+    }
+
+    /// The video variant whose `BANDWIDTH` is nearest `target`, preferring the lower one on ties.
+    /// Returns `None` if there are no variants.
+    pub fn variant_closest_to_bandwidth(&self, target: u64) -> Option<&StreamInfo> {
+        self.streams
+            .inner
+            .iter()
+            .min_by_key(|s| (s.common.bandwidth.abs_diff(target), s.common.bandwidth))
+    }
+
+    /// The highest-`BANDWIDTH` video variant not exceeding `cap`, first in playlist order on ties.
+    /// Returns `None` if every variant exceeds `cap`.
+    pub fn variant_at_or_below(&self, cap: u64) -> Option<&StreamInfo> {
+        self.streams
+            .inner
+            .iter()
+            .filter(|s| s.common.bandwidth <= cap)
+            .min_by_key(|s| std::cmp::Reverse(s.common.bandwidth))
+    }
+
+    /// Render the playlist as an indented tree, for interactive debugging:
+    /// audio groups with their renditions, then video variants with the groups they reference,
+    /// then iframe variants.
+    /// Unlike `to_table` and `to_m3u8` (HLS text), this shows how elements relate.
+    pub fn tree_view(&self) -> String {
+        let mut res = format!("Master playlist (version {})\n", self.version);
+
+        // Audio groups, in order of first appearance
+        let mut group_ids: Vec<&str> = Vec::new();
+        for audio in self.audio_streams.inner.iter() {
+            if !group_ids.contains(&audio.group_id.as_str()) {
+                group_ids.push(&audio.group_id);
+            }
+        }
+        res.push_str("  Audio groups\n");
+        for group_id in group_ids {
+            res.push_str(&format!("    {group_id}\n"));
+            for audio in self
+                .audio_streams
+                .inner
+                .iter()
+                .filter(|a| a.group_id == group_id)
+            {
+                res.push_str(&format!(
+                    "      {} ({}, {} channels) -> {}\n",
+                    audio.name,
+                    audio.language,
+                    audio.channel_info.param_value(),
+                    audio.uri
+                ));
+            }
+        }
+
+        res.push_str("  Variants\n");
+        for stream in self.streams.inner.iter() {
+            let common = &stream.common;
+            res.push_str(&format!(
+                "    {} bps, {}x{}, {} -> {}\n",
+                common.bandwidth,
+                common.resolution.width,
+                common.resolution.height,
+                common.codecs.join(","),
+                common.uri
+            ));
+            if let Some(group_id) = &stream.audio_group {
+                let group_found = self
+                    .audio_streams
+                    .inner
+                    .iter()
+                    .any(|a| &a.group_id == group_id);
+                res.push_str(&format!(
+                    "      audio group: {}{}\n",
+                    group_id,
+                    if group_found { "" } else { " (missing)" }
+                ));
+            }
+        }
+
+        res.push_str("  I-frame variants\n");
+        for stream in self.iframe_streams.inner.iter() {
+            let common = &stream.common;
+            res.push_str(&format!(
+                "    {} bps, {}x{}, {} -> {}\n",
+                common.bandwidth,
+                common.resolution.width,
+                common.resolution.height,
+                common.codecs.join(","),
+                common.uri
+            ));
+        }
+        res
+    }
+}
+
+/// Builds an `HlsPlaylist` programmatically, e.g. for packagers generating playlists to serialize.
+#[derive(Default, Debug)]
+pub struct HlsPlaylistBuilder {
+    playlist: HlsPlaylist,
+}
+
+impl HlsPlaylistBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the declared playlist protocol version. See `auto_version` to compute it instead.
+    pub fn version(mut self, version: usize) -> Self {
+        self.playlist.version = version;
+        self
+    }
+
+    pub fn independent_segments(mut self, independent_segments: bool) -> Self {
+        self.playlist.independent_segments = independent_segments;
+        self
+    }
+
+    pub fn content_steering(mut self, content_steering: ContentSteering) -> Self {
+        self.playlist.content_steering = Some(content_steering);
+        self
+    }
+
+    pub fn audio(mut self, audio: Audio) -> Self {
+        self.playlist.audio_streams.inner.push(audio);
+        self
+    }
+
+    pub fn video_rendition(mut self, video: VideoRendition) -> Self {
+        self.playlist.video_renditions.inner.push(video);
+        self
+    }
+
+    pub fn subtitles(mut self, subtitles: SubtitleRendition) -> Self {
+        self.playlist.subtitles.push(subtitles);
+        self
+    }
+
+    pub fn stream(mut self, stream: StreamInfo) -> Self {
+        self.playlist.streams.inner.push(stream);
+        self
+    }
+
+    pub fn iframe_stream(mut self, stream: IframeStreamInfo) -> Self {
+        self.playlist.iframe_streams.inner.push(stream);
+        self
+    }
+
+    /// Raise the version to the minimum required by all elements added so far,
+    /// according to `HlsPlaylist::min_required_version`. A higher version set by `version`
+    /// is kept. Call after adding elements.
+    pub fn auto_version(mut self) -> Self {
+        self.playlist.version = self
+            .playlist
+            .version
+            .max(self.playlist.min_required_version());
+        self
+    }
+
+    pub fn build(self) -> HlsPlaylist {
+        self.playlist
+    }
+}
+
+/// Whether `a` and `b` hold the same elements, with the same multiplicity, in any order.
+fn same_elements<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut matched = vec![false; b.len()];
+    a.iter().all(|x| {
+        let found = (0..b.len()).find(|&i| !matched[i] && b[i] == *x);
+        if let Some(i) = found {
+            matched[i] = true;
+        }
+        found.is_some()
+    })
 }
 
 impl Display for HlsPlaylist {
+    /// Writes the playlist as M3U8 text, same as `to_m3u8`.
+    /// Use `to_table` for a human-readable view.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}\n{}\n{}",
-            self.audio_streams, self.streams, self.iframe_streams
-        )
+        write!(f, "{}", self.to_m3u8())
     }
 }
 
 impl FromStr for HlsPlaylist {
-    // Make the return type of from_str equivalent to
-    // anyhow::Result to avoid conversion.
+    // Make the return type of from_str equivalent to anyhow::Result for ergonomics.
+    // See the `TryFrom` impl for the typed error.
+    type Err = anyhow::Error;
+
+    fn from_str(data: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(parsers::parse_hls_playlist(data, &ParseOptions::default())?)
+    }
+}
+
+impl TryFrom<&str> for HlsPlaylist {
+    // Alternative to `FromStr` for callers that want the concrete error type.
+    type Error = HlsParseError;
+
+    fn try_from(data: &str) -> std::result::Result<Self, Self::Error> {
+        parse_hls_playlist_typed(data)
+    }
+}
+
+impl MediaPlaylist {
+    /// Total duration of the playlist's segments, in seconds.
+    /// Segment durations that aren't finite and non-negative (e.g. `NaN`) are skipped.
+    pub fn total_duration(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|s| f64::from(s.duration))
+            .filter(|d| d.is_finite() && *d >= 0.0)
+            .sum()
+    }
+
+    /// Segments paired with their media sequence numbers,
+    /// which count from `media_sequence`, including segments omitted by `#EXT-X-SKIP`.
+    fn sequenced_segments(&self) -> impl Iterator<Item = (usize, &types::segment::Segment)> {
+        let first = self.media_sequence + self.skip.as_ref().map_or(0, |s| s.skipped_segments);
+        (first..).zip(self.segments.iter())
+    }
+
+    /// Compare this (old) playlist with a newer version, `other`, e.g. after a live playlist reload.
+    /// Segments are matched by media sequence number and URI, so a segment whose URI changed
+    /// is reported as removed and added.
+    pub fn diff<'a>(&'a self, other: &'a MediaPlaylist) -> PlaylistDiff<'a> {
+        let old: HashMap<_, _> = self
+            .sequenced_segments()
+            .map(|(seq, s)| ((seq, s.uri.as_str()), s))
+            .collect();
+        let new: HashMap<_, _> = other
+            .sequenced_segments()
+            .map(|(seq, s)| ((seq, s.uri.as_str()), s))
+            .collect();
+
+        let mut diff = PlaylistDiff::default();
+        for (seq, segment) in other.sequenced_segments() {
+            match old.get(&(seq, segment.uri.as_str())) {
+                Some(&old_segment) if old_segment != segment => {
+                    diff.changed.push((seq, old_segment, segment))
+                }
+                Some(_) => (),
+                None => diff.added.push((seq, segment)),
+            }
+        }
+        diff.removed = self
+            .sequenced_segments()
+            .filter(|(seq, s)| !new.contains_key(&(*seq, s.uri.as_str())))
+            .collect();
+        diff
+    }
+
+    /// Parse a media playlist, failing if it contains master playlist tags
+    /// (e.g. `#EXT-X-STREAM-INF`), rather than ignoring them as `from_str` does.
+    pub fn parse(data: &str) -> Result<Self, HlsParseError> {
+        Self::parse_with_options(data, &ParseOptions::default())
+    }
+
+    /// Parse a media playlist as `parse` does, with parsing behavior controlled by `options`.
+    pub fn parse_with_options(data: &str, options: &ParseOptions) -> Result<Self, HlsParseError> {
+        parsers::parse_media_playlist_only(data, options)
+    }
+
+    /// Check the playlist for problems that don't prevent parsing, returning all that are found.
+    /// This includes a declared version lower than `min_required_version`.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        check_version(self.version, self.min_required_version(), &mut errors);
+        errors
+    }
+
+    /// Minimum `#EXT-X-VERSION` the playlist must declare for the tags and attributes it uses,
+    /// per the protocol version compatibility rules of the HLS spec (RFC 8216, section 7).
+    /// Covers `#EXT-X-I-FRAMES-ONLY` (4), `#EXT-X-SKIP` (9, or 10 with `RECENTLY-REMOVED-DATERANGES`),
+    /// floating-point durations (3), key attributes, `SAMPLE-AES` (5), `#EXT-X-MAP` (5 or 6) and `#EXT-X-DEFINE`.
+    pub fn min_required_version(&self) -> usize {
+        let mut version = min_version_for_definitions(&self.definitions);
+        let mut require = |v: usize| version = version.max(v);
+        if self.iframes_only {
+            require(4);
+        }
+        if let Some(skip) = &self.skip {
+            require(if skip.recently_removed_dateranges.is_some() {
+                10
+            } else {
+                9
+            });
+        }
+        for segment in self.segments.iter() {
+            // Durations with a fractional part can only be written as floating-point
+            if segment.duration.fract() != 0.0 {
+                require(3);
+            }
+            for key in segment.keys.iter() {
+                if key.iv.is_some() {
+                    require(min_version_for_attr(P_IV));
+                }
+                if key.key_format.is_some() {
+                    require(min_version_for_attr(P_KEYFORMAT));
+                }
+                if key.key_format_versions.is_some() {
+                    require(min_version_for_attr(P_KEYFORMATVERSIONS));
+                }
+                if key.method == KeyMethod::SampleAes {
+                    require(5);
+                }
+            }
+            // `#EXT-X-MAP` needs version 5 in I-frame playlists, and 6 in others
+            if segment.map.is_some() {
+                require(if self.iframes_only { 5 } else { 6 });
+            }
+        }
+        version
+    }
+}
+
+impl FromStr for MediaPlaylist {
     type Err = anyhow::Error;
 
     fn from_str(data: &str) -> std::result::Result<Self, Self::Err> {
-        parsers::parse_hls_playlist(data)
+        Ok(parsers::parse_media_playlist(
+            data,
+            &ParseOptions::default(),
+        )?)
     }
 }
 
@@ -46,21 +1099,40 @@ impl FromStr for HlsPlaylist {
 mod test {
     use std::path::Path;
 
-    use crate::types::media::{Audio, AudioChannelInfo};
-    use crate::types::stream_info::{IframeStreamInfo, Resolution, StreamInfo, StreamInfoCommon};
+    use crate::types::define::Definition;
+    use crate::types::media::{Audio, AudioChannelInfo, AudioStreams, InstreamId};
+    use crate::types::segment::{
+        ByteRange, EncryptionKey, KeyMethod, MediaInitialization, Part, PreloadHint,
+        PreloadHintType, RenditionReport, Segment, Skip,
+    };
+    use crate::types::stream_info::{
+        Codec, CpcEntry, IframeStreamInfo, IframeStreams, Resolution, StreamInfoCommon, Streams,
+    };
 
     use super::*;
 
-    /// Just parse the sample input from a file, without checking parsed values for correctness.
-    #[test]
-    fn test_parse_sample_input() {
-        // Get contents of sample input file
+    /// Get contents of the sample input file.
+    fn sample_input() -> String {
         let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
             .expect("CARGO_MANIFEST_DIR environment variable is not set");
         let file_path =
             Path::new(manifest_dir.as_str()).join(Path::new("test-fixtures/sample-input.txt"));
-        let data = std::fs::read_to_string(file_path).expect("failed to read sample input file");
+        std::fs::read_to_string(file_path).expect("failed to read sample input file")
+    }
+
+    /// Get contents of the sample media playlist input file.
+    fn sample_media_input() -> String {
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+            .expect("CARGO_MANIFEST_DIR environment variable is not set");
+        let file_path = Path::new(manifest_dir.as_str())
+            .join(Path::new("test-fixtures/sample-media-input.txt"));
+        std::fs::read_to_string(file_path).expect("failed to read sample media input file")
+    }
 
+    /// Just parse the sample input from a file, without checking parsed values for correctness.
+    #[test]
+    fn test_parse_sample_input() {
+        let data = sample_input();
         let playlist = HlsPlaylist::from_str(data.as_str()).unwrap();
         assert_eq!(playlist.audio_streams.inner.len(), 4);
         assert_eq!(playlist.streams.inner.len(), 36);
@@ -80,7 +1152,8 @@ mod test {
     /// Parse basic elements that don't return structured data.
     #[test]
     fn test_parse_version() {
-        let data = "#EXT-X-VERSION:5
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
 ";
         let playlist = HlsPlaylist::from_str(data).unwrap();
         assert_eq!(playlist.version, 5);
@@ -89,7 +1162,8 @@ mod test {
     /// Parse audio media data only.
     #[test]
     fn test_parse_audio() {
-        let data = "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
 
 #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-64k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_64k/vod.m3u8\"
 
@@ -103,13 +1177,32 @@ mod test {
                 group_id: "aac-128k".to_owned(),
                 name: "English".to_owned(),
                 language: "en".to_owned(),
+                assoc_language: None,
                 default: true,
                 auto_select: true,
                 channel_info: AudioChannelInfo {
                     channels: 2,
                     joc: false,
+                    identifiers: vec![],
                 },
+                characteristics: None,
+                stable_rendition_id: None,
+                sample_rate: None,
+                bit_depth: None,
                 uri: "audio/unenc/aac_128k/vod.m3u8".to_owned(),
+                attribute_order: [
+                    "TYPE",
+                    "GROUP-ID",
+                    "NAME",
+                    "LANGUAGE",
+                    "DEFAULT",
+                    "AUTOSELECT",
+                    "CHANNELS",
+                    "URI"
+                ]
+                .map(String::from)
+                .to_vec(),
+                raw: None,
             }
         );
         assert!(playlist.audio_streams.inner[2].channel_info.joc);
@@ -118,7 +1211,8 @@ mod test {
     /// Parse stream data only.
     #[test]
     fn test_parse_stream() {
-        let data = "#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
 hdr10/unenc/1650k/vod.m3u8
 #EXT-X-STREAM-INF:BANDWIDTH=15811232,AVERAGE-BANDWIDTH=10058085,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
 hdr10/unenc/10000k/vod.m3u8
@@ -132,17 +1226,37 @@ hdr10/unenc/10000k/vod.m3u8
                 common: StreamInfoCommon {
                     bandwidth: 2483789,
                     codecs: vec!["mp4a.40.2".to_owned(), "hvc1.2.4.L90.90".to_owned()],
+                    supplemental_codecs: None,
                     resolution: Resolution {
                         width: 960,
                         height: 540,
                     },
                     video_range: "PQ".to_owned(),
+                    stable_variant_id: None,
+                    allowed_cpc: None,
                     uri: "hdr10/unenc/1650k/vod.m3u8".to_owned(),
+                    attribute_order: [
+                        "BANDWIDTH",
+                        "AVERAGE-BANDWIDTH",
+                        "CODECS",
+                        "RESOLUTION",
+                        "FRAME-RATE",
+                        "VIDEO-RANGE",
+                        "AUDIO",
+                        "CLOSED-CAPTIONS"
+                    ]
+                    .map(String::from)
+                    .to_vec(),
+                    raw: None,
                 },
-                average_bandwidth: 1762745,
-                frame_rate: 23.97,
-                audio_codec: "aac-128k".to_owned(),
+                average_bandwidth: Some(1762745),
+                frame_rate: Some(23.97),
+                audio_group: Some("aac-128k".to_owned()),
+                video_group: None,
                 closed_captions: "NONE".to_owned(),
+                pathway_id: None,
+                program_id: None,
+                score: None,
             }
         );
     }
@@ -150,7 +1264,8 @@ hdr10/unenc/10000k/vod.m3u8
     /// Parse iframe stream data only.
     #[test]
     fn test_parse_iframe() {
-        let data = "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=222552,CODECS=\"hvc1.2.4.L93.90\",RESOLUTION=1280x720,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/3300k/vod-iframe.m3u8\"
+        let data = "#EXTM3U
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=222552,CODECS=\"hvc1.2.4.L93.90\",RESOLUTION=1280x720,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/3300k/vod-iframe.m3u8\"
 
 #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\"
 ";
@@ -163,13 +1278,21 @@ hdr10/unenc/10000k/vod.m3u8
                 common: StreamInfoCommon {
                     bandwidth: 77758,
                     codecs: vec!["hvc1.2.4.L63.90".to_owned()],
+                    supplemental_codecs: None,
                     resolution: Resolution {
                         width: 640,
                         height: 360,
                     },
                     video_range: "PQ".to_owned(),
+                    stable_variant_id: None,
+                    allowed_cpc: None,
                     uri: "hdr10/unenc/900k/vod-iframe.m3u8".to_owned(),
+                    attribute_order: ["BANDWIDTH", "CODECS", "RESOLUTION", "VIDEO-RANGE", "URI"]
+                        .map(String::from)
+                        .to_vec(),
+                    raw: None,
                 },
+                average_bandwidth: None,
             }
         );
     }
@@ -180,4 +1303,3227 @@ hdr10/unenc/10000k/vod.m3u8
         let data = "this line should never exist in an HLS playlist!";
         assert!(HlsPlaylist::from_str(data).is_err());
     }
+
+    /// Detect HDR formats from video range and Dolby Vision codecs.
+    #[test]
+    fn test_hdr_formats() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert_eq!(playlist.hdr_formats(), HashSet::from([HdrFormat::Pq]));
+
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,dvh1.08.07\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+dv/unenc/1650k/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"avc1.640020\",RESOLUTION=640x360,VIDEO-RANGE=SDR,URI=\"sdr/unenc/900k/vod-iframe.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.hdr_formats(),
+            HashSet::from([HdrFormat::Pq, HdrFormat::DolbyVision, HdrFormat::Sdr])
+        );
+    }
+
+    /// Lowercase param names and YES/NO values are only accepted in lenient mode.
+    #[test]
+    fn test_parse_lenient() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\" English \",LANGUAGE=\"en\",default=yes,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+";
+        assert!(HlsPlaylist::from_str(data).is_err());
+        assert!(HlsPlaylist::from_str_with_options(data, &ParseOptions::default()).is_err());
+
+        let playlist = HlsPlaylist::from_str_with_options(
+            data,
+            &ParseOptions {
+                lenient: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(playlist.audio_streams.inner[0].default);
+        assert_eq!(playlist.audio_streams.inner[0].name, "English");
+    }
+
+    /// Remove repeated video streams, keeping the first of each URI.
+    #[test]
+    fn test_dedup_streams() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=15811232,AVERAGE-BANDWIDTH=10058085,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/10000k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let mut playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 3);
+        playlist.dedup_streams();
+        assert_eq!(playlist.streams.inner.len(), 2);
+        assert_eq!(
+            playlist.streams.inner[0].common.uri,
+            "hdr10/unenc/1650k/vod.m3u8"
+        );
+        assert_eq!(
+            playlist.streams.inner[1].common.uri,
+            "hdr10/unenc/10000k/vod.m3u8"
+        );
+    }
+
+    /// Report audio groups containing more than one rendition of the same name.
+    #[test]
+    fn test_duplicate_audio_group_ids() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert!(playlist.duplicate_audio_group_ids().is_empty());
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"Deutsch\",LANGUAGE=\"de\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k_de/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"6\",URI=\"audio/unenc/aac_128k_51/vod.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.duplicate_audio_group_ids(),
+            vec!["aac-128k".to_owned()]
+        );
+    }
+
+    /// Unknown tags survive a parse/serialize round trip, byte-for-byte and in place.
+    #[test]
+    fn test_round_trip_unknown_tags() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"16/JOC\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+#EXT-X-FUTURE-TAG:SOME-ATTR=\"some value\",OTHER-ATTR=42
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.unknown_tags.len(), 1);
+        assert_eq!(playlist.to_m3u8(), data);
+    }
+
+    /// `TryFrom` parses like `FromStr`, but surfaces the typed error.
+    #[test]
+    fn test_try_from() {
+        let playlist = HlsPlaylist::try_from(sample_input().as_str()).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 36);
+
+        let data = "#EXTM3U
+this line should never exist in an HLS playlist!";
+        assert!(matches!(
+            HlsPlaylist::try_from(data),
+            Err(HlsParseError::Syntax(_))
+        ));
+
+        // Recognized audio tag that's missing its GROUP-ID
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+";
+        assert!(matches!(
+            HlsPlaylist::try_from(data),
+            Err(HlsParseError::MissingGroupId { .. })
+        ));
+    }
+
+    /// Parse raw codec strings from the sample input into structured codecs.
+    #[test]
+    fn test_parsed_codecs() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        for stream in playlist.streams.inner.iter() {
+            let codecs = stream.common.parsed_codecs();
+            assert_eq!(codecs.len(), 2);
+            assert!(codecs[0] == Codec::Aac || codecs[0] == Codec::Ec3);
+            assert!(codecs[0].is_audio());
+            assert_eq!(codecs[1], Codec::Hevc);
+            assert!(codecs[1].is_video());
+        }
+        for stream in playlist.iframe_streams.inner.iter() {
+            assert_eq!(stream.common.parsed_codecs(), vec![Codec::Hevc]);
+        }
+
+        assert_eq!(Codec::from("avc1.640028"), Codec::H264);
+        assert_eq!(Codec::from("ac-3"), Codec::Ac3);
+        assert_eq!(
+            Codec::from("dvh1.08.07"),
+            Codec::Other("dvh1.08.07".to_owned())
+        );
+    }
+
+    /// Parse a media playlist, resolving each segment's key through key rotation.
+    #[test]
+    fn test_parse_media_playlist_keys() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert_eq!(playlist.version, 5);
+        assert_eq!(playlist.target_duration, 7);
+        assert_eq!(playlist.segments.len(), 5);
+        assert_eq!(playlist.segments[0].duration, 6.006);
+        assert_eq!(playlist.segments[0].uri, "hdr10/unenc/1650k/seg-0.ts");
+        assert_eq!(playlist.segments[4].title.as_deref(), Some("credits"));
+
+        // Segments before the first key, and after METHOD=NONE, aren't encrypted
+        assert!(playlist.segments[0].keys.is_empty());
+        assert!(playlist.segments[4].keys.is_empty());
+        // Each key applies until overridden
+        let key_uris = playlist.segments[1..4]
+            .iter()
+            .map(|s| s.keys[0].uri.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            key_uris,
+            vec![
+                "https://keys.example.com/key-1",
+                "https://keys.example.com/key-1",
+                "https://keys.example.com/key-2"
+            ]
+        );
+        let key = &playlist.segments[3].keys[0];
+        assert_eq!(key.method, KeyMethod::Aes128);
+        assert_eq!(
+            key.iv.as_deref(),
+            Some("0x00000000000000000000000000000002")
+        );
+    }
+
+    /// Expect failure on a media playlist without a target duration.
+    #[test]
+    fn test_parse_media_playlist_fail() {
+        let data = "#EXTM3U
+#EXTINF:6.006,
+seg-0.ts
+";
+        assert!(MediaPlaylist::from_str(data).is_err());
+    }
+
+    /// Typed parse errors identify what went wrong, and which param caused it.
+    #[test]
+    fn test_parse_typed_errors() {
+        assert!(parse_hls_playlist_typed(sample_input().as_str()).is_ok());
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+";
+        assert_eq!(
+            parse_hls_playlist_typed(data).unwrap_err(),
+            HlsParseError::MissingGroupId {
+                name: Some("English".to_owned()),
+                language: Some("en".to_owned()),
+                line: 2,
+            }
+        );
+
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=lots,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        assert_eq!(
+            parse_hls_playlist_typed(data).unwrap_err(),
+            HlsParseError::InvalidInt {
+                param: "BANDWIDTH",
+                value: "lots".to_owned()
+            }
+        );
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=MAYBE,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+";
+        assert!(matches!(
+            parse_hls_playlist_typed(data),
+            Err(HlsParseError::InvalidValue {
+                param: "DEFAULT",
+                ..
+            })
+        ));
+
+        // Errors still convert to `anyhow::Error` through `FromStr`
+        let e = HlsPlaylist::from_str(data).unwrap_err();
+        assert_eq!(e.to_string(), "invalid value for HLS param DEFAULT: MAYBE");
+    }
+
+    /// Ladders match despite small bandwidth drift, but not beyond the tolerance.
+    #[test]
+    fn test_ladders_equivalent() {
+        let golden = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let mut encoded = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert!(golden.ladders_equivalent(&encoded, 0.0));
+
+        // Drift every rung's bandwidth by 2%, and reorder the ladder
+        for stream in encoded.streams.inner.iter_mut() {
+            stream.common.bandwidth += stream.common.bandwidth / 50;
+        }
+        encoded.streams.inner.reverse();
+        assert!(golden.ladders_equivalent(&encoded, 0.05));
+        assert!(!golden.ladders_equivalent(&encoded, 0.01));
+
+        // A changed rung resolution is never equivalent
+        encoded.streams.inner[0].common.resolution.height += 2;
+        assert!(!golden.ladders_equivalent(&encoded, 0.05));
+    }
+
+    /// Parse Dolby Vision supplemental codecs, keeping codec/brand pairs verbatim.
+    #[test]
+    fn test_parse_supplemental_codecs() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",SUPPLEMENTAL-CODECS=\"dvh1.08.07/db4h\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=HLG,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+dv/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let common = &playlist.streams.inner[0].common;
+        assert_eq!(common.codecs.len(), 2);
+        assert_eq!(
+            common.supplemental_codecs,
+            Some(vec!["dvh1.08.07/db4h".to_owned()])
+        );
+        assert_eq!(
+            playlist.hdr_formats(),
+            HashSet::from([HdrFormat::Hlg, HdrFormat::DolbyVision])
+        );
+        assert!(
+            playlist
+                .to_m3u8()
+                .contains("SUPPLEMENTAL-CODECS=\"dvh1.08.07/db4h\"")
+        );
+        assert_eq!(
+            HlsPlaylist::from_str(sample_input().as_str())
+                .unwrap()
+                .streams
+                .inner[0]
+                .common
+                .supplemental_codecs,
+            None
+        );
+    }
+
+    /// Parse a content steering tag, and streams associated with its pathways.
+    #[test]
+    fn test_parse_content_steering() {
+        let data = "#EXTM3U
+#EXT-X-CONTENT-STEERING:SERVER-URI=\"steering.json\",PATHWAY-ID=\"cdn-a\"
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE,PATHWAY-ID=\"cdn-a\"
+https://cdn-a.example.com/hdr10/unenc/1650k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE,PATHWAY-ID=\"cdn-b\"
+https://cdn-b.example.com/hdr10/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let steering = playlist.content_steering.as_ref().unwrap();
+        assert_eq!(steering.server_uri, "steering.json");
+        assert_eq!(steering.pathway_id.as_deref(), Some("cdn-a"));
+        let pathways = playlist
+            .streams
+            .inner
+            .iter()
+            .map(|s| s.pathway_id.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(pathways, vec![Some("cdn-a"), Some("cdn-b")]);
+        assert_eq!(playlist.to_m3u8(), data);
+
+        // PATHWAY-ID is optional, SERVER-URI is not
+        let playlist = HlsPlaylist::from_str(
+            "#EXTM3U\n#EXT-X-CONTENT-STEERING:SERVER-URI=\"steering.json\"\n",
+        )
+        .unwrap();
+        assert_eq!(playlist.content_steering.unwrap().pathway_id, None);
+        assert_eq!(
+            parse_hls_playlist_typed("#EXTM3U\n#EXT-X-CONTENT-STEERING:PATHWAY-ID=\"cdn-a\"\n")
+                .unwrap_err(),
+            HlsParseError::MissingField("SERVER-URI")
+        );
+    }
+
+    /// Tree view nests renditions under their groups, and groups under the variants using them.
+    #[test]
+    fn test_tree_view() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"Deutsch\",LANGUAGE=\"de\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"16/JOC\",URI=\"audio/de/vod.m3u8\"
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+video/1650k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=705826,AVERAGE-BANDWIDTH=514769,CODECS=\"mp4a.40.2,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-64k\",CLOSED-CAPTIONS=NONE
+video/450k/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"video/900k/vod-iframe.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.tree_view(),
+            "Master playlist (version 5)
+  Audio groups
+    aac-128k
+      English (en, 2 channels) -> audio/en/vod.m3u8
+      Deutsch (de, 16/JOC channels) -> audio/de/vod.m3u8
+  Variants
+    2483789 bps, 960x540, mp4a.40.2,hvc1.2.4.L90.90 -> video/1650k/vod.m3u8
+      audio group: aac-128k
+    705826 bps, 640x360, mp4a.40.2,hvc1.2.4.L63.90 -> video/450k/vod.m3u8
+      audio group: aac-64k (missing)
+  I-frame variants
+    77758 bps, 640x360, hvc1.2.4.L63.90 -> video/900k/vod-iframe.m3u8
+"
+        );
+    }
+
+    /// Pick the best variant by score, or by bandwidth if no variant is scored.
+    #[test]
+    fn test_best_variant() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let best = playlist.best_variant().unwrap();
+        assert!(best.score.is_none());
+        assert_eq!(best.common.bandwidth, 26598940);
+        assert!(HlsPlaylist::default().best_variant().is_none());
+
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE,SCORE=2.5
+hdr10/unenc/1650k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=15811232,AVERAGE-BANDWIDTH=10058085,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE,SCORE=1.0
+hdr10/unenc/10000k/vod.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=25702333,AVERAGE-BANDWIDTH=16502314,CODECS=\"mp4a.40.2,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/16500k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let best = playlist.best_variant().unwrap();
+        assert_eq!(best.score, Some(2.5));
+        assert_eq!(best.common.uri, "hdr10/unenc/1650k/vod.m3u8");
+        assert_eq!(playlist.to_m3u8().matches("SCORE=").count(), 2);
+
+        let data = data.replace("SCORE=1.0", "SCORE=-1.0");
+        assert_eq!(
+            parse_hls_playlist_typed(&data).unwrap_err(),
+            HlsParseError::InvalidValue {
+                param: "SCORE",
+                value: "-1.0".to_owned()
+            }
+        );
+
+        // A negative zero score is the same as zero, under both `==` and `cmp`
+        let score = |score: &str| {
+            let data = data.replace("SCORE=-1.0", &format!("SCORE={score}"));
+            HlsPlaylist::from_str(&data).unwrap().streams.inner[1].clone()
+        };
+        let (negative, positive) = (score("-0"), score("0"));
+        assert!(negative.score.unwrap().is_sign_positive());
+        assert_eq!(negative, positive);
+        assert_eq!(negative.cmp(&positive), std::cmp::Ordering::Equal);
+
+        // Floats set by hand compare under `==` as under `cmp`
+        let mut nan = positive.clone();
+        nan.frame_rate = Some(f32::NAN);
+        assert_eq!(nan, nan.clone());
+        let mut negative = positive.clone();
+        negative.score = Some(-0.0);
+        assert_ne!(negative, positive);
+        assert_eq!(negative.cmp(&positive), std::cmp::Ordering::Less);
+    }
+
+    /// A missing `GROUP-ID` error identifies the rendition and the line it's on.
+    #[test]
+    fn test_missing_group_id() {
+        let data = "#EXTM3U
+#EXT-X-INDEPENDENT-SEGMENTS
+
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en/vod.m3u8\"
+# Deutsch rendition
+#EXT-X-MEDIA:TYPE=AUDIO,NAME=\"Deutsch\",LANGUAGE=\"de\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/de/vod.m3u8\"
+";
+        let e = parse_hls_playlist_typed(data).unwrap_err();
+        assert_eq!(
+            e,
+            HlsParseError::MissingGroupId {
+                name: Some("Deutsch".to_owned()),
+                language: Some("de".to_owned()),
+                line: 6,
+            }
+        );
+        assert_eq!(
+            e.to_string(),
+            "missing HLS param GROUP-ID on #EXT-X-MEDIA at line 6 (NAME=\"Deutsch\", LANGUAGE=\"de\")"
+        );
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/de/vod.m3u8\"
+";
+        assert_eq!(
+            HlsPlaylist::from_str(data).unwrap_err().to_string(),
+            "missing HLS param GROUP-ID on #EXT-X-MEDIA at line 2"
+        );
+    }
+
+    /// Build a playlist programmatically, computing the version from the attributes used.
+    #[test]
+    fn test_builder_auto_version() {
+        assert_eq!(min_version_for_attr("IV"), 2);
+        assert_eq!(min_version_for_attr("KEYFORMAT"), 5);
+        assert_eq!(min_version_for_attr("CHANNELS"), 1);
+        assert_eq!(min_version_for_attr("REQ-VIDEO-LAYOUT"), 12);
+
+        let parsed = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let mut builder = HlsPlaylistBuilder::new()
+            .version(4)
+            .independent_segments(true);
+        for audio in parsed.audio_streams.inner {
+            builder = builder.audio(audio);
+        }
+        for stream in parsed.streams.inner {
+            builder = builder.stream(stream);
+        }
+        let playlist = builder.auto_version().build();
+        // An explicit version higher than required is kept
+        assert_eq!(playlist.version, 4);
+        assert_eq!(playlist.audio_streams.inner.len(), 4);
+        assert!(playlist.to_m3u8().starts_with(
+            "#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-INDEPENDENT-SEGMENTS
+"
+        ));
+        let playlist = HlsPlaylistBuilder::new()
+            .stream(playlist.streams.inner[0].clone())
+            .auto_version()
+            .build();
+        assert_eq!(playlist.version, 1);
+
+        // `SERVICE` closed captions need version 7, unlike `CC` ones
+        let captions = |instream_id: &str| {
+            HlsPlaylist::from_str(&format!(
+                "#EXTM3U
+#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"English\",INSTREAM-ID=\"{instream_id}\"
+"
+            ))
+            .unwrap()
+        };
+        assert_eq!(captions("CC1").min_required_version(), 1);
+        assert_eq!(captions("SERVICE1").min_required_version(), 7);
+    }
+
+    /// Params are serialized in the order they were parsed in, or in canonical order if none was recorded.
+    #[test]
+    fn test_round_trip_attribute_order() {
+        // Every element type, already grouped in serialization order, with unknown tags between them
+        let data = "#EXTM3U
+#EXT-X-VERSION:8
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-DEFINE:NAME=\"cdn\",VALUE=\"https://cdn.example.com\"
+#EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"Example\"
+#EXT-X-CONTENT-STEERING:SERVER-URI=\"steering.json\",PATHWAY-ID=\"cdn-a\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=VIDEO,GROUP-ID=\"cam\",NAME=\"Angle 1\",DEFAULT=YES,URI=\"cam1/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"English\",LANGUAGE=\"en\",URI=\"subs/en/vod.m3u8\"
+#EXT-X-SESSION-KEY:METHOD=SAMPLE-AES,URI=\"skd://key\",KEYFORMAT=\"com.apple.streamingkeydelivery\"
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",VIDEO=\"cam\",CLOSED-CAPTIONS=NONE
+video/1650k/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"video/900k/vod-iframe.m3u8\"
+#EXT-X-START:TIME-OFFSET=10
+";
+        assert_eq!(HlsPlaylist::from_str(data).unwrap().to_m3u8(), data);
+
+        let data = "#EXTM3U
+#EXT-X-CONTENT-STEERING:PATHWAY-ID=\"cdn-a\",SERVER-URI=\"steering.json\"
+#EXT-X-MEDIA:TYPE=AUDIO,URI=\"audio/en/vod.m3u8\",GROUP-ID=\"aac-128k\",LANGUAGE=\"en\",NAME=\"English\",CHANNELS=\"2\",AUTOSELECT=YES,DEFAULT=YES
+#EXT-X-STREAM-INF:RESOLUTION=960x540,BANDWIDTH=2483789,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",AUDIO=\"aac-128k\",AVERAGE-BANDWIDTH=1762745,FRAME-RATE=23.97,SCORE=2,CLOSED-CAPTIONS=NONE,VIDEO-RANGE=PQ
+video/1650k/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:URI=\"video/900k/vod-iframe.m3u8\",BANDWIDTH=77758,VIDEO-RANGE=PQ,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360
+";
+        let mut playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.to_m3u8(), data);
+
+        // Without a recorded order, params fall back to canonical order
+        playlist.audio_streams.inner[0].attribute_order.clear();
+        assert!(playlist.to_m3u8().contains(
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en/vod.m3u8\"\n"
+        ));
+    }
+
+    /// CRLF line endings and a missing final newline don't leak into parsed values.
+    #[test]
+    fn test_parse_crlf() {
+        let data = "#EXTM3U\r\n# comment\r\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"\r\n#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\r\nhdr10/unenc/1650k/vod.m3u8\r\n#EXT-X-STREAM-INF:BANDWIDTH=15811232,AVERAGE-BANDWIDTH=10058085,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\r\nhdr10/unenc/10000k/vod.m3u8";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.audio_streams.inner[0].uri,
+            "audio/unenc/aac_128k/vod.m3u8"
+        );
+        let uris = playlist
+            .streams
+            .inner
+            .iter()
+            .map(|s| s.common.uri.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            uris,
+            vec!["hdr10/unenc/1650k/vod.m3u8", "hdr10/unenc/10000k/vod.m3u8"]
+        );
+        assert_eq!(playlist.streams.inner[1].closed_captions, "NONE");
+
+        // Comment on the final line, without a line ending
+        assert!(
+            HlsPlaylist::from_str("#EXTM3U\r\n#EXT-X-INDEPENDENT-SEGMENTS\r\n# comment").is_ok()
+        );
+
+        let data = sample_media_input().replace('\n', "\r\n");
+        let playlist = MediaPlaylist::from_str(data.trim_end()).unwrap();
+        assert_eq!(playlist.segments.len(), 5);
+        assert!(playlist.segments.iter().all(|s| !s.uri.contains('\r')));
+        assert_eq!(playlist.segments[4].title.as_deref(), Some("credits"));
+    }
+
+    /// Parse the `#EXT-X-I-FRAMES-ONLY` flag.
+    #[test]
+    fn test_parse_iframes_only() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(!playlist.iframes_only);
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-TARGETDURATION:7
+#EXT-X-I-FRAMES-ONLY
+#EXTINF:6.006,
+hdr10/unenc/1650k/iframe-0.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert!(playlist.iframes_only);
+        assert_eq!(playlist.segments.len(), 1);
+    }
+
+    /// Parse the `#EXT-X-ENDLIST` flag. Segments can't follow it, unless parsing leniently.
+    #[test]
+    fn test_parse_end_list() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(playlist.end_list);
+        assert!(playlist.validate().is_empty());
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:7
+#EXTINF:6.006,
+seg-0.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert!(!playlist.end_list);
+        assert!(playlist.validate().is_empty());
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:7
+#EXTINF:6.006,
+seg-0.ts
+#EXT-X-ENDLIST
+#EXTINF:6.006,
+seg-1.ts
+";
+        assert_eq!(
+            MediaPlaylist::parse(data).unwrap_err(),
+            HlsParseError::SegmentAfterEndList { line: 8 }
+        );
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let playlist = MediaPlaylist::parse_with_options(data, &options).unwrap();
+        assert!(playlist.end_list);
+        assert_eq!(playlist.segments.len(), 2);
+    }
+
+    /// Parse accessibility params on a described-video audio rendition.
+    #[test]
+    fn test_parse_audio_accessibility() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English (Described)\",LANGUAGE=\"en\",ASSOC-LANGUAGE=\"en-US\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",CHARACTERISTICS=\"public.accessibility.describes-video,public.easy-to-read\",URI=\"audio/en-ad/vod.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let audio = &playlist.audio_streams.inner[0];
+        assert_eq!(audio.assoc_language.as_deref(), Some("en-US"));
+        assert_eq!(
+            audio.characteristics,
+            Some(vec![
+                "public.accessibility.describes-video".to_owned(),
+                "public.easy-to-read".to_owned()
+            ])
+        );
+        assert_eq!(playlist.to_m3u8(), data);
+
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert!(playlist.audio_streams.inner[0].characteristics.is_none());
+    }
+
+    /// Malformed language tags are reported by validation, but don't prevent parsing.
+    #[cfg(feature = "bcp47")]
+    #[test]
+    fn test_validate_language() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert!(playlist.validate().is_empty());
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en-US\",ASSOC-LANGUAGE=\"english\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"Chinese\",LANGUAGE=\"zh-cmn-Hans-CN-x-private\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/zh/vod.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"Unknown\",LANGUAGE=\"en--US\",DEFAULT=NO,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/xx/vod.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.validate(),
+            vec![
+                ValidationError::InvalidLanguageTag {
+                    param: "ASSOC-LANGUAGE",
+                    value: "english".to_owned()
+                },
+                ValidationError::InvalidLanguageTag {
+                    param: "LANGUAGE",
+                    value: "en--US".to_owned()
+                },
+            ]
+        );
+
+        for valid in [
+            "en",
+            "sl-rozaj-biske",
+            "de-CH-1901",
+            "es-419",
+            "en-a-bbb-x-a-ccc",
+            "x-whatever",
+        ] {
+            assert!(language::is_well_formed_language_tag(valid), "{valid}");
+        }
+        for invalid in ["english", "e", "en-", "en-a", "en-US-x", "12", "en_US"] {
+            assert!(!language::is_well_formed_language_tag(invalid), "{invalid}");
+        }
+    }
+
+    /// Parse date ranges marking ad breaks, with and without SCTE-35 data.
+    #[test]
+    fn test_parse_date_range() {
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:7
+#EXT-X-DATERANGE:ID=\"splice-6FFFFFF0\",START-DATE=\"2014-03-05T11:15:00Z\"
+#EXTINF:6.006,
+seg-0.ts
+#EXT-X-DATERANGE:ID=\"ad1\",CLASS=\"com.example.ad\",START-DATE=\"2024-01-01T00:00:06.006Z\",DURATION=30.0,PLANNED-DURATION=30,SCTE35-OUT=0xFC002F0000,X-AD-ID=\"abc-123\",X-AD-SLOT=2
+#EXTINF:6.006,
+seg-1.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.date_ranges.len(), 2);
+
+        let date_range = &playlist.date_ranges[0];
+        assert_eq!(date_range.id, "splice-6FFFFFF0");
+        assert_eq!(date_range.start_date, "2014-03-05T11:15:00Z");
+        assert_eq!(date_range.class, None);
+        assert_eq!(date_range.duration, None);
+        assert!(date_range.client_attributes.is_empty());
+        assert_eq!(date_range.scte35_out, None);
+
+        let ad = &playlist.date_ranges[1];
+        assert_eq!(ad.id, "ad1");
+        assert_eq!(ad.class.as_deref(), Some("com.example.ad"));
+        assert_eq!(ad.duration, Some(30.0));
+        assert_eq!(ad.planned_duration, Some(30.0));
+        assert_eq!(ad.end_date, None);
+        assert_eq!(ad.scte35_out, Some(vec![0xFC, 0x00, 0x2F, 0x00, 0x00]));
+        assert_eq!(ad.scte35_in, None);
+        assert_eq!(ad.client_attributes["X-AD-ID"], "abc-123");
+        assert_eq!(ad.client_attributes["X-AD-SLOT"], "2");
+
+        let data = data.replace("0xFC002F0000", "0xFC0");
+        assert!(matches!(
+            MediaPlaylist::from_str(&data)
+                .unwrap_err()
+                .downcast::<HlsParseError>(),
+            Ok(HlsParseError::InvalidValue {
+                param: "SCTE35-OUT",
+                ..
+            })
+        ));
+    }
+
+    /// Parse initialization sections, with and without a byte range, applying each to subsequent segments.
+    #[test]
+    fn test_parse_map() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(playlist.map.is_none());
+        assert!(playlist.segments.iter().all(|s| s.map.is_none()));
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:6
+#EXT-X-TARGETDURATION:7
+#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"
+#EXTINF:6.006,
+seg-0.m4s
+#EXTINF:6.006,
+seg-1.m4s
+#EXT-X-MAP:URI=\"init-2.mp4\"
+#EXTINF:6.006,
+seg-2.m4s
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        let first = MediaInitialization {
+            uri: "init.mp4".to_owned(),
+            byte_range: Some(ByteRange {
+                length: 720,
+                offset: Some(0),
+            }),
+        };
+        assert_eq!(playlist.map.as_ref(), Some(&first));
+        assert_eq!(playlist.segments[0].map.as_ref(), Some(&first));
+        assert_eq!(playlist.segments[1].map.as_ref(), Some(&first));
+        assert_eq!(
+            playlist.segments[2].map,
+            Some(MediaInitialization {
+                uri: "init-2.mp4".to_owned(),
+                byte_range: None,
+            })
+        );
+
+        assert_eq!(
+            ByteRange::from_str("1024").unwrap(),
+            ByteRange {
+                length: 1024,
+                offset: None
+            }
+        );
+        assert_eq!(ByteRange::from_str("720@0").unwrap().to_string(), "720@0");
+        assert!(ByteRange::from_str("720@").is_err());
+    }
+
+    /// Best-effort parsing skips bad lines and elements, warning about each.
+    #[test]
+    fn test_parse_lenient_warnings() {
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(sample_input().as_str());
+        assert!(warnings.is_empty());
+        assert_eq!(playlist.streams.inner.len(), 36);
+
+        // Corrupt one stream's bandwidth
+        let data = sample_input().replacen("BANDWIDTH=15811232", "BANDWIDTH=lots", 1);
+        let corrupt_line = data.lines().position(|l| l.contains("=lots")).unwrap() + 1;
+        assert!(HlsPlaylist::from_str(&data).is_err());
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(&data);
+        assert_eq!(playlist.streams.inner.len(), 35);
+        assert_eq!(playlist.audio_streams.inner.len(), 4);
+        assert_eq!(playlist.iframe_streams.inner.len(), 2);
+        assert_eq!(
+            warnings,
+            vec![ParseWarning {
+                line: corrupt_line,
+                message: "failed to parse HLS param BANDWIDTH as int: lots".to_owned(),
+            }]
+        );
+
+        // Lines that don't parse at all are skipped
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
+this line should never exist in an HLS playlist!
+
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(data);
+        assert_eq!(playlist.version, 5);
+        assert_eq!(playlist.streams.inner.len(), 1);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "line 3: failed to parse line: this line should never exist in an HLS playlist!"
+        );
+
+        // Params that don't parse are skipped, with one warning each, keeping the stream
+        let unknown = data.replace("FRAME-RATE=23.97,", "FRAME-RATE=23.97,FOO=\"a,b\",");
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(&unknown);
+        assert_eq!(playlist.streams.inner.len(), 1);
+        assert_eq!(
+            playlist.streams.inner[0].common.uri,
+            "hdr10/unenc/1650k/vod.m3u8"
+        );
+        assert_eq!(playlist.streams.inner[0].frame_rate, Some(23.97));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[1].to_string(),
+            "line 5: skipped param FOO=\"a,b\" of #EXT-X-STREAM-INF"
+        );
+        let unknown = unknown.replace(",CLOSED-CAPTIONS=NONE", ",CLOSED-CAPTIONS=NONE,BAR=1");
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(&unknown);
+        assert_eq!(playlist.streams.inner.len(), 1);
+        assert_eq!(warnings.len(), 3);
+
+        // A stream tag that doesn't parse at all is skipped along with its URI, with one warning
+        let broken = data.replace("STREAM-INF:", "STREAM-INF:not params,");
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(&broken);
+        assert!(playlist.streams.inner.is_empty());
+        assert!(playlist.unknown_tags.is_empty());
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[1].line, 5);
+    }
+
+    /// Summarize the sample ladder, and an empty one.
+    #[test]
+    fn test_bitrate_summary() {
+        assert_eq!(HlsPlaylist::default().bitrate_summary(), None);
+
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let summary = playlist.bitrate_summary().unwrap();
+        assert_eq!(summary.min_bandwidth, 705826);
+        assert_eq!(summary.max_bandwidth, 26598940);
+        assert!((summary.mean_bandwidth - 366955727.0 / 36.0).abs() < 1e-6);
+        assert_eq!(
+            summary.min_resolution,
+            Resolution {
+                width: 640,
+                height: 360
+            }
+        );
+        assert_eq!(
+            summary.max_resolution,
+            Resolution {
+                width: 3840,
+                height: 2160
+            }
+        );
+        assert_eq!(summary.video_range_count, 1);
+        // mp4a.40.2, ec-3, and 7 HEVC profile/levels
+        assert_eq!(summary.codec_count, 9);
+    }
+
+    /// Streams sort ascending by bandwidth, then resolution.
+    #[test]
+    fn test_sort_streams() {
+        let mut playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        playlist.streams.inner.sort();
+        assert!(
+            playlist
+                .streams
+                .inner
+                .windows(2)
+                .all(|w| w[0].common.bandwidth <= w[1].common.bandwidth)
+        );
+        assert_eq!(playlist.streams.inner[0].common.bandwidth, 705826);
+        assert_eq!(playlist.streams.inner[35].common.bandwidth, 26598940);
+
+        playlist.iframe_streams.inner.sort();
+        assert_eq!(playlist.iframe_streams.inner[0].common.bandwidth, 77758);
+
+        // Equal bandwidths fall back to resolution
+        let mut low = StreamInfo::default();
+        low.common.resolution = Resolution {
+            width: 1920,
+            height: 800,
+        };
+        let mut high = StreamInfo::default();
+        high.common.resolution = Resolution {
+            width: 1920,
+            height: 1080,
+        };
+        assert!(low < high);
+        high.common.bandwidth = 1;
+        low.common.bandwidth = 2;
+        assert!(high < low);
+
+        // Otherwise equal streams without a frame rate sort after those with one
+        let with_rate = |rate| StreamInfo {
+            frame_rate: rate,
+            ..Default::default()
+        };
+        let mut streams = [
+            with_rate(None),
+            with_rate(Some(60.0)),
+            with_rate(Some(25.0)),
+        ];
+        streams.sort();
+        let rates = streams.iter().map(|s| s.frame_rate).collect::<Vec<_>>();
+        assert_eq!(rates, [Some(25.0), Some(60.0), None]);
+    }
+
+    /// Cloned playlists hold the same data as the original.
+    #[test]
+    fn test_clone_playlist() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let snapshot = playlist.clone();
+        assert_eq!(snapshot.audio_streams.inner, playlist.audio_streams.inner);
+        assert_eq!(snapshot.streams.inner, playlist.streams.inner);
+        assert_eq!(snapshot.iframe_streams.inner, playlist.iframe_streams.inner);
+        assert_eq!(snapshot.to_m3u8(), playlist.to_m3u8());
+
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert_eq!(playlist.clone().segments, playlist.segments);
+    }
+
+    /// Keep every identifier following the channel count, not just `JOC`.
+    #[test]
+    fn test_parse_channel_identifiers() {
+        let info = AudioChannelInfo::from_str("2").unwrap();
+        assert_eq!(info.channels, 2);
+        assert!(!info.joc);
+        assert!(info.identifiers.is_empty());
+
+        let info = AudioChannelInfo::from_str("16/JOC").unwrap();
+        assert_eq!(info.channels, 16);
+        assert!(info.joc);
+        assert_eq!(info.identifiers, vec!["JOC".to_owned()]);
+
+        let info = AudioChannelInfo::from_str("12/JOC/BED").unwrap();
+        assert_eq!(info.channels, 12);
+        assert!(info.joc);
+        assert_eq!(info.identifiers, vec!["JOC".to_owned(), "BED".to_owned()]);
+        assert_eq!(info.to_string().trim(), "12/JOC/BED");
+
+        let info = AudioChannelInfo::from_str("2/-/BINAURAL").unwrap();
+        assert!(!info.joc);
+        assert_eq!(
+            info.identifiers,
+            vec!["-".to_owned(), "BINAURAL".to_owned()]
+        );
+
+        // Order by channels, then joc
+        let mut infos = ["16/JOC", "6", "16", "2/-/BINAURAL"]
+            .map(|s| AudioChannelInfo::from_str(s).unwrap())
+            .to_vec();
+        infos.sort();
+        let sorted = infos.iter().map(|i| i.param_value()).collect::<Vec<_>>();
+        assert_eq!(sorted, vec!["2/-/BINAURAL", "6", "16", "16/JOC"]);
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"atmos\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"12/JOC/BED\",URI=\"audio/atmos/vod.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert!(playlist.to_m3u8().contains("CHANNELS=\"12/JOC/BED\""));
+    }
+
+    /// A repeated single-valued tag is an error only if its value conflicts, unless disabled.
+    #[test]
+    fn test_duplicate_version() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-VERSION:3
+";
+        assert_eq!(HlsPlaylist::from_str(data).unwrap().version, 3);
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-VERSION:6
+";
+        let err = parsers::parse_hls_playlist(data, &ParseOptions::default()).unwrap_err();
+        assert_eq!(
+            err,
+            HlsParseError::DuplicateTag {
+                tag: "#EXT-X-VERSION",
+                first: "3".to_owned(),
+                second: "6".to_owned(),
+                line: 3,
+            }
+        );
+        let options = ParseOptions {
+            strict_duplicates: false,
+            ..Default::default()
+        };
+        let playlist = HlsPlaylist::from_str_with_options(data, &options).unwrap();
+        assert_eq!(playlist.version, 6);
+
+        let data = "#EXTM3U
+#EXT-X-START:TIME-OFFSET=10
+#EXT-X-START:TIME-OFFSET=20
+";
+        assert!(HlsPlaylist::from_str(data).is_err());
+        assert!(HlsPlaylist::from_str_with_options(data, &options).is_ok());
+
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXT-X-TARGETDURATION:10
+#EXTINF:6.0,
+seg0.ts
+";
+        assert!(MediaPlaylist::from_str(data).is_err());
+    }
+
+    /// `Display` of a playlist is the playlist itself.
+    #[test]
+    fn test_display_m3u8() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let displayed = playlist.to_string();
+        assert!(displayed.starts_with("#EXTM3U"));
+        assert_eq!(displayed, playlist.to_m3u8());
+        assert!(!playlist.to_table().starts_with("#EXTM3U"));
+    }
+
+    /// Plain comments are captured, while the header and extension tags are not.
+    #[test]
+    fn test_parse_comments() {
+        let data = "#EXTM3U
+# built by encoder v1.2.3
+#EXT-X-VERSION:3
+#EXT-X-START:TIME-OFFSET=10
+#source: origin-7
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.comments(),
+            [
+                " built by encoder v1.2.3".to_owned(),
+                "source: origin-7".to_owned()
+            ]
+        );
+        assert_eq!(playlist.unknown_tags[0].position, 1);
+    }
+
+    /// `AVERAGE-BANDWIDTH` is optional, and omitted when serializing a stream without it.
+    #[test]
+    fn test_parse_stream_without_average_bandwidth() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,FRAME-RATE=23.976,VIDEO-RANGE=SDR,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let stream = &playlist.streams.inner[0];
+        assert_eq!(stream.average_bandwidth, None);
+        assert_eq!(stream.common.bandwidth, 2483789);
+        assert!(!playlist.to_m3u8().contains("AVERAGE-BANDWIDTH"));
+    }
+
+    /// `FRAME-RATE` is optional, and omitted when serializing a stream without it.
+    #[test]
+    fn test_parse_stream_without_frame_rate() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.streams.inner[0].frame_rate, None);
+        assert!(!playlist.to_m3u8().contains("FRAME-RATE"));
+    }
+
+    /// `AUDIO` is optional, e.g. for video-only playlists without audio groups.
+    #[test]
+    fn test_parse_stream_without_audio() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"avc1.640029\",RESOLUTION=1280x720,FRAME-RATE=23.976,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.streams.inner[0].audio_group, None);
+        #[allow(deprecated)]
+        let audio_codec = playlist.streams.inner[0].audio_codec();
+        assert_eq!(audio_codec, None);
+        assert!(!playlist.to_m3u8().contains("AUDIO="));
+        assert!(!playlist.tree_view().contains("audio group"));
+    }
+
+    /// A `BANDWIDTH` too large for its integer type is a parse error, not a panic.
+    #[test]
+    fn test_parse_bandwidth_overflow() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1234567890123456789012345678901234567890,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,FRAME-RATE=23.976,VIDEO-RANGE=SDR,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        assert_eq!(
+            parse_hls_playlist_typed(data).unwrap_err(),
+            HlsParseError::InvalidInt {
+                param: P_BANDWIDTH,
+                value: "1234567890123456789012345678901234567890".to_owned(),
+            }
+        );
+
+        // Fits in 64 bits, regardless of platform
+        let data = data.replace("1234567890123456789012345678901234567890", "8000000000");
+        let playlist = HlsPlaylist::from_str(&data).unwrap();
+        assert_eq!(playlist.streams.inner[0].common.bandwidth, 8_000_000_000);
+    }
+
+    /// Explicit parsing fails on tags belonging to the other playlist type.
+    #[test]
+    fn test_parse_explicit_playlist_type() {
+        let err = HlsPlaylist::parse_master(sample_media_input().as_str()).unwrap_err();
+        assert!(matches!(err, HlsParseError::InvalidElement(_)));
+        assert!(HlsPlaylist::parse_master(sample_input().as_str()).is_ok());
+
+        // Media playlist without segments, which otherwise parses as a master playlist
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXT-X-ENDLIST
+";
+        assert!(HlsPlaylist::from_str(data).is_ok());
+        assert_eq!(
+            HlsPlaylist::parse_master(data).unwrap_err().to_string(),
+            "#EXT-X-TARGETDURATION at line 2 is not allowed in a master playlist"
+        );
+
+        assert!(MediaPlaylist::parse(sample_media_input().as_str()).is_ok());
+        assert!(matches!(
+            MediaPlaylist::parse(sample_input().as_str()).unwrap_err(),
+            HlsParseError::InvalidElement(_)
+        ));
+    }
+
+    /// Unrecognized `#EXT-X-*` tags are recorded rather than dropped as comments.
+    #[test]
+    fn test_unknown_tag_recorded() {
+        let data = "#EXTM3U
+#EXT-X-FOO:bar
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.unknown_tags.len(), 1);
+        assert_eq!(playlist.unknown_tags[0].name(), "#EXT-X-FOO");
+        assert_eq!(playlist.unknown_tags[0].line, "#EXT-X-FOO:bar");
+        assert!(playlist.comments.is_empty());
+
+        let (_, warnings) = HlsPlaylist::parse_lenient(data);
+        assert_eq!(
+            warnings,
+            vec![ParseWarning {
+                line: 2,
+                message: "unknown tag #EXT-X-FOO is not interpreted".to_owned(),
+            }]
+        );
+
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXT-X-FOO:bar
+#EXTINF:6.0,
+seg0.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.unknown_tags.len(), 1);
+        assert_eq!(playlist.unknown_tags[0].name(), "#EXT-X-FOO");
+        assert_eq!(playlist.unknown_tags[0].position, 1);
+    }
+
+    /// Commas inside quoted values are part of the value, not param separators.
+    #[test]
+    fn test_parse_quoted_commas() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"grp,1\",NAME=\"English, UK\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,FRAME-RATE=23.976,VIDEO-RANGE=SDR,AUDIO=\"grp,1\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let audio = &playlist.audio_streams.inner[0];
+        assert_eq!(audio.group_id, "grp,1");
+        assert_eq!(audio.name, "English, UK");
+        assert_eq!(audio.language, "en");
+        let stream = &playlist.streams.inner[0];
+        assert_eq!(stream.audio_group.as_deref(), Some("grp,1"));
+        assert_eq!(stream.common.codecs, vec!["mp4a.40.2", "avc1.640029"]);
+        assert_eq!(
+            HlsPlaylist::from_str(&playlist.to_m3u8())
+                .unwrap()
+                .to_m3u8(),
+            playlist.to_m3u8()
+        );
+
+        // A quoted value can't run past the end of its line
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac,NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"a.m3u8
+\"
+";
+        assert!(HlsPlaylist::from_str(data).is_err());
+    }
+
+    /// All missing params of a tag are reported together.
+    #[test]
+    fn test_missing_fields_listed() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\"
+";
+        let err = parse_hls_playlist_typed(data).unwrap_err();
+        assert_eq!(
+            err,
+            HlsParseError::MissingFields {
+                kind: "audio",
+                params: vec![P_NAME, P_URI],
+            }
+        );
+        assert_eq!(err.to_string(), "missing HLS audio params: NAME, URI");
+
+        // A single missing param is reported as before
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"a.m3u8\"
+";
+        assert_eq!(
+            parse_hls_playlist_typed(data).unwrap_err(),
+            HlsParseError::MissingField(P_NAME)
+        );
+    }
+
+    /// Detect Dolby Atmos audio and Dolby Vision video.
+    #[test]
+    fn test_dolby_detection() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert!(playlist.has_atmos());
+        // Sample streams are HEVC with PQ, without Dolby Vision codecs
+        assert!(!playlist.has_dolby_vision());
+
+        let mut playlist = playlist;
+        playlist
+            .audio_streams
+            .inner
+            .retain(|a| a.group_id != "atmos");
+        // E-AC-3 5.1 isn't Atmos
+        assert!(!playlist.has_atmos());
+
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"ec-3,hvc1.2.4.L93.90\",SUPPLEMENTAL-CODECS=\"dvh1.08.07/db4h\",RESOLUTION=1280x720,VIDEO-RANGE=PQ,CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        assert!(HlsPlaylist::from_str(data).unwrap().has_dolby_vision());
+    }
+
+    /// Parse hi-res audio `SAMPLE-RATE` and `BIT-DEPTH`.
+    #[test]
+    fn test_parse_audio_sample_rate_bit_depth() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"alac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",SAMPLE-RATE=96000,BIT-DEPTH=24,URI=\"audio/alac/vod.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let audio = &playlist.audio_streams.inner[0];
+        assert_eq!(audio.sample_rate, Some(96000));
+        assert_eq!(audio.bit_depth, Some(24));
+        assert!(playlist.to_m3u8().contains(data.trim_end()));
+
+        let data = data.replace("BIT-DEPTH=24", "BIT-DEPTH=300");
+        assert_eq!(
+            parse_hls_playlist_typed(&data).unwrap_err(),
+            HlsParseError::InvalidInt {
+                param: P_BIT_DEPTH,
+                value: "300".to_owned(),
+            }
+        );
+    }
+
+    /// Parse stable IDs used to match variants and renditions across steering pathways.
+    #[test]
+    fn test_parse_stable_ids() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",STABLE-RENDITION-ID=\"en-aac\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE,STABLE-VARIANT-ID=\"720p\"
+sdr/720p/vod.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=222552,CODECS=\"avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,STABLE-VARIANT-ID=\"720p-iframe\",URI=\"sdr/720p/iframe_index.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.audio_streams.inner[0]
+                .stable_rendition_id
+                .as_deref(),
+            Some("en-aac")
+        );
+        assert_eq!(
+            playlist.streams.inner[0]
+                .common
+                .stable_variant_id
+                .as_deref(),
+            Some("720p")
+        );
+        assert_eq!(
+            playlist.iframe_streams.inner[0]
+                .common
+                .stable_variant_id
+                .as_deref(),
+            Some("720p-iframe")
+        );
+        assert!(playlist.to_m3u8().contains(data));
+    }
+
+    /// Playlists differing only in stream and param order are semantically equal.
+    #[test]
+    fn test_semantically_equal() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let mut reordered = playlist.clone();
+        reordered.streams.inner.reverse();
+        reordered.audio_streams.inner.rotate_left(1);
+        reordered.iframe_streams.inner.reverse();
+        assert!(playlist.semantically_equal(&reordered));
+        assert!(reordered.semantically_equal(&playlist));
+
+        // Param order and comments are cosmetic
+        let a = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+720p.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS=\"avc1.640029\",RESOLUTION=640x360,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+360p.m3u8
+";
+        let b = "#EXTM3U
+# generated
+#EXT-X-STREAM-INF:CODECS=\"avc1.640029\",BANDWIDTH=1000000,RESOLUTION=640x360,CLOSED-CAPTIONS=NONE,VIDEO-RANGE=SDR
+360p.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+720p.m3u8
+";
+        let (a, b) = (
+            HlsPlaylist::from_str(a).unwrap(),
+            HlsPlaylist::from_str(b).unwrap(),
+        );
+        assert!(a.semantically_equal(&b));
+
+        // Content differences aren't ignored
+        let mut changed = playlist.clone();
+        changed.streams.inner[0].common.bandwidth += 1;
+        assert!(!playlist.semantically_equal(&changed));
+        let mut duplicated = playlist.clone();
+        duplicated.streams.inner[1] = duplicated.streams.inner[0].clone();
+        assert!(!playlist.semantically_equal(&duplicated));
+    }
+
+    /// Legacy `PROGRAM-ID` is accepted on streams.
+    #[test]
+    fn test_parse_program_id() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=2483789,CODECS=\"mp4a.40.2,avc1.640029\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+sdr/720p/vod.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let stream = &playlist.streams.inner[0];
+        assert_eq!(stream.program_id, Some(1));
+        assert_eq!(stream.common.bandwidth, 2483789);
+        assert!(playlist.to_m3u8().contains(data));
+    }
+
+    /// Parse single tags into their types, without a surrounding playlist.
+    #[test]
+    fn test_parse_single_tags() {
+        let audio = Audio::from_str("#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"").unwrap();
+        assert_eq!(audio.group_id, "aac-128k");
+        assert_eq!(audio.channel_info.channels, 2);
+        assert_eq!(audio.uri, "audio/unenc/aac_128k/vod.m3u8");
+
+        let data = "#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE
+hdr10/unenc/1650k/vod.m3u8
+";
+        let stream = StreamInfo::from_str(data).unwrap();
+        let playlist = HlsPlaylist::from_str(&format!("#EXTM3U\n{data}")).unwrap();
+        assert_eq!(stream, playlist.streams.inner[0]);
+        assert_eq!(stream.common.uri, "hdr10/unenc/1650k/vod.m3u8");
+
+        let data = "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\"";
+        let iframe = IframeStreamInfo::from_str(data).unwrap();
+        assert_eq!(
+            iframe.common.resolution,
+            Resolution {
+                width: 640,
+                height: 360,
+            }
+        );
+        assert_eq!(iframe.common.bandwidth, 77758);
+
+        // Exactly one tag of the matching type
+        assert!(Audio::from_str(data).is_err());
+        assert!(IframeStreamInfo::from_str(&format!("{data}\n{data}")).is_err());
+    }
+
+    /// Estimate variant sizes from bandwidth and total segment duration.
+    #[test]
+    fn test_estimate_variant_size() {
+        let stream = StreamInfo::from_str("#EXT-X-STREAM-INF:BANDWIDTH=8000000,CODECS=\"avc1.640029\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+1080p.m3u8
+").unwrap();
+        let media = MediaPlaylist::from_str(
+            "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXTINF:6.0,
+seg0.ts
+#EXTINF:4.0,
+seg1.ts
+#EXT-X-ENDLIST
+",
+        )
+        .unwrap();
+        assert_eq!(media.total_duration(), 10.0);
+        // 1 MB/s for 10 seconds
+        assert_eq!(estimate_variant_size(&stream, &media), 10_000_000);
+
+        let empty = MediaPlaylist::from_str("#EXTM3U\n#EXT-X-TARGETDURATION:6\n").unwrap();
+        assert_eq!(estimate_variant_size(&stream, &empty), 0);
+
+        let mut playlist = HlsPlaylistBuilder::new().stream(stream.clone()).build();
+        let mut other = stream;
+        other.common.uri = "720p.m3u8".to_owned();
+        playlist.streams.inner.push(other);
+        // Only streams with a fetched media playlist count
+        let media_playlists = HashMap::from([("1080p.m3u8".to_owned(), media)]);
+        assert_eq!(playlist.estimate_total_size(&media_playlists), 10_000_000);
+    }
+
+    /// A byte order mark and blank lines before `#EXTM3U` are skipped.
+    #[test]
+    fn test_parse_leading_bom_and_whitespace() {
+        let data = format!("\u{feff}{}", sample_input());
+        let playlist = HlsPlaylist::from_str(&data).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 36);
+
+        let data = "\n\n  \n#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-FOO:bar\n";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.version, 3);
+        // Line numbers still count the skipped lines
+        let (_, warnings) = HlsPlaylist::parse_lenient(data);
+        assert_eq!(warnings[0].line, 6);
+
+        let data = format!("\u{feff}\n{}", sample_media_input());
+        assert!(MediaPlaylist::from_str(&data).is_ok());
+
+        // The first meaningful line must still be an HLS element
+        assert!(HlsPlaylist::from_str("\u{feff}\nnot a playlist").is_err());
+    }
+
+    /// `#EXTM3U` must be the first line, unless parsing leniently.
+    #[test]
+    fn test_require_header() {
+        let data = "#EXT-X-VERSION:3
+#EXT-X-INDEPENDENT-SEGMENTS
+";
+        let err = parse_hls_playlist_typed(data).unwrap_err();
+        assert_eq!(err, HlsParseError::MissingHeader);
+        assert_eq!(err.to_string(), "playlist must begin with #EXTM3U");
+
+        // Header present, but not first
+        let data_late_header = format!("{data}#EXTM3U\n");
+        assert_eq!(
+            parse_hls_playlist_typed(&data_late_header).unwrap_err(),
+            HlsParseError::MissingHeader
+        );
+
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let playlist = HlsPlaylist::from_str_with_options(data, &options).unwrap();
+        assert_eq!(playlist.version, 3);
+
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(data);
+        assert!(playlist.independent_segments);
+        assert_eq!(
+            warnings,
+            vec![ParseWarning {
+                line: 1,
+                message: "playlist must begin with #EXTM3U".to_owned(),
+            }]
+        );
+    }
+
+    /// Video renditions are parsed into their own collection, and streams reference them by group.
+    #[test]
+    fn test_video_renditions() {
+        let data = r#"#EXTM3U
+#EXT-X-MEDIA:TYPE=VIDEO,GROUP-ID="cam",NAME="Main",DEFAULT=YES,AUTOSELECT=YES
+#EXT-X-MEDIA:TYPE=VIDEO,GROUP-ID="cam",NAME="Sideline",LANGUAGE="en",URI="sideline/prog.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS="avc1.640020",RESOLUTION=1280x720,VIDEO-RANGE=SDR,VIDEO="cam",CLOSED-CAPTIONS=NONE
+main/prog.m3u8
+"#;
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        let renditions = &playlist.video_renditions.inner;
+        assert_eq!(renditions.len(), 2);
+        assert_eq!(renditions[0].group_id, "cam");
+        assert!(renditions[0].default);
+        assert_eq!(renditions[0].uri, None);
+        assert_eq!(renditions[1].language.as_deref(), Some("en"));
+        assert!(!renditions[1].auto_select);
+        assert_eq!(renditions[1].uri.as_deref(), Some("sideline/prog.m3u8"));
+        assert_eq!(
+            playlist.streams.inner[0].video_group.as_deref(),
+            Some("cam")
+        );
+        assert!(playlist.audio_streams.inner.is_empty());
+
+        assert_eq!(playlist.to_m3u8(), data);
+    }
+
+    /// `#EXT-X-BITRATE` applies to subsequent segments until the next bitrate tag.
+    #[test]
+    fn test_parse_bitrate() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(playlist.segments.iter().all(|s| s.bitrate.is_none()));
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:7
+#EXTINF:6.006,
+seg-0.ts
+#EXT-X-BITRATE:2500
+#EXTINF:6.006,
+seg-1.ts
+#EXTINF:6.006,
+seg-2.ts
+#EXT-X-BITRATE:1200
+#EXTINF:6.006,
+seg-3.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        let bitrates = playlist
+            .segments
+            .iter()
+            .map(|s| s.bitrate)
+            .collect::<Vec<_>>();
+        assert_eq!(bitrates, [None, Some(2500), Some(2500), Some(1200)]);
+        assert!(playlist.unknown_tags.is_empty());
+    }
+
+    /// Public enums are `#[non_exhaustive]`, so variants can be added without a breaking change.
+    #[test]
+    fn test_non_exhaustive_match() {
+        let err = parse_hls_playlist_typed("not a playlist").unwrap_err();
+        // Matches in other crates must include a wildcard arm to compile,
+        // as more variants may be added in future.
+        let described = match err {
+            HlsParseError::MissingHeader => "missing header",
+            HlsParseError::Syntax(_) => "syntax",
+            _ => "other",
+        };
+        assert_eq!(described, "missing header");
+    }
+
+    /// The borrowed form holds slices of the input, and converts to the same owned playlist.
+    #[test]
+    fn test_parse_borrowed() {
+        let data = sample_input();
+        let borrowed = HlsPlaylistRef::parse(&data).unwrap();
+        assert_eq!(borrowed.streams.len(), 36);
+
+        let input = data.as_bytes().as_ptr_range();
+        let stream = &borrowed.streams[0];
+        let bandwidth = stream.get("BANDWIDTH").unwrap();
+        assert_eq!(bandwidth, "2483789");
+        assert!(input.contains(&bandwidth.as_ptr()));
+        assert!(input.contains(&stream.uri.unwrap().as_ptr()));
+        let group_id = borrowed.audio_streams[0].get("GROUP-ID").unwrap();
+        assert!(input.contains(&group_id.as_ptr()));
+
+        let owned = borrowed.into_owned().unwrap();
+        assert!(owned.semantically_equal(&HlsPlaylist::from_str(&data).unwrap()));
+
+        // Conversion failures surface from `into_owned`, rather than from parsing
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=abc,CODECS=\"avc1.640020\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+main/prog.m3u8
+";
+        let borrowed = HlsPlaylistRef::parse(data).unwrap();
+        assert!(borrowed.into_owned().is_err());
+    }
+
+    /// Syntax errors locate the first line that doesn't parse, without exposing `nom`.
+    #[test]
+    fn test_syntax_error_location() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+  not a tag
+#EXT-X-INDEPENDENT-SEGMENTS
+";
+        let Err(HlsParseError::Syntax(e)) = parse_hls_playlist_typed(data) else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(e.kind, ParseErrorKind::UnrecognizedElement);
+        assert_eq!(e.line, 3);
+        assert_eq!(e.column, 3);
+        assert_eq!(
+            e.to_string(),
+            "unrecognized HLS element: not a tag at line 3, column 3"
+        );
+
+        let data = format!("{}\noops\n", sample_input().trim_end());
+        let Err(HlsParseError::Syntax(e)) = parse_hls_playlist_typed(&data) else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(e.line, data.lines().count());
+        assert_eq!(e.column, 1);
+
+        let e = parsers::parse_audio(
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"a.m3u8\"\n#EXT-X-VERSION:3",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            e,
+            HlsParseError::Syntax(ParseError {
+                kind: ParseErrorKind::TrailingInput,
+                line: 2,
+                ..
+            })
+        ));
+    }
+
+    /// An audio group has at most one default rendition, which must also be autoselected.
+    #[test]
+    fn test_validate_default_renditions() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"French\",LANGUAGE=\"fr\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/fr.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"German\",LANGUAGE=\"de\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/de.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"ac3\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=NO,CHANNELS=\"6\",URI=\"audio/en-ac3.m3u8\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"ac3\",NAME=\"French\",LANGUAGE=\"fr\",DEFAULT=NO,AUTOSELECT=NO,CHANNELS=\"6\",URI=\"audio/fr-ac3.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.validate(),
+            vec![
+                ValidationError::MultipleDefaultsInGroup("aac".to_owned()),
+                ValidationError::DefaultNotAutoselect {
+                    group_id: "ac3".to_owned(),
+                    name: "English".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            playlist.validate()[1].to_string(),
+            "rendition \"English\" in group \"ac3\" is DEFAULT=YES but not AUTOSELECT=YES"
+        );
+    }
+
+    /// Partial segments attach to the segment that follows them; later parts remain trailing.
+    #[test]
+    fn test_parse_parts() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(playlist.part_target.is_none());
+        assert!(playlist.segments.iter().all(|s| s.parts.is_empty()));
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:9
+#EXT-X-TARGETDURATION:4
+#EXT-X-PART-INF:PART-TARGET=1.002
+#EXT-X-MAP:URI=\"init.mp4\"
+#EXT-X-PART:DURATION=1.002,URI=\"seg-0.0.mp4\",INDEPENDENT=YES
+#EXT-X-PART:DURATION=1.002,URI=\"seg-0.1.mp4\"
+#EXT-X-PART:DURATION=1.002,URI=\"seg-0.mp4\",BYTERANGE=\"2048@4096\",GAP=YES
+#EXTINF:3.006,
+seg-0.mp4
+#EXT-X-PART:DURATION=1.002,URI=\"seg-1.0.mp4\",INDEPENDENT=YES
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(playlist.part_target, Some(1.002));
+        let parts = &playlist.segments[0].parts;
+        assert_eq!(parts.len(), 3);
+        assert_eq!(
+            parts[0],
+            Part {
+                duration: 1.002,
+                uri: "seg-0.0.mp4".to_owned(),
+                independent: true,
+                byte_range: None,
+                gap: false,
+            }
+        );
+        assert!(!parts[1].independent);
+        assert_eq!(
+            parts[2].byte_range,
+            Some(ByteRange {
+                length: 2048,
+                offset: Some(4096),
+            })
+        );
+        assert!(parts[2].gap);
+
+        assert_eq!(playlist.trailing_parts.len(), 1);
+        assert_eq!(playlist.trailing_parts[0].uri, "seg-1.0.mp4");
+        assert!(playlist.unknown_tags.is_empty());
+
+        // DURATION is required
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:4
+#EXT-X-PART:URI=\"seg-0.0.mp4\"
+";
+        assert_eq!(
+            MediaPlaylist::parse(data).unwrap_err(),
+            HlsParseError::MissingField(P_DURATION)
+        );
+    }
+
+    /// Preload hints and rendition reports of a low-latency playlist are collected in order.
+    #[test]
+    fn test_parse_preload_hints() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:9
+#EXT-X-TARGETDURATION:4
+#EXT-X-PART-INF:PART-TARGET=1.002
+#EXTINF:3.006,
+seg-0.mp4
+#EXT-X-PART:DURATION=1.002,URI=\"seg-1.0.mp4\",INDEPENDENT=YES
+#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"seg-1.1.mp4\"
+#EXT-X-PRELOAD-HINT:TYPE=MAP,URI=\"init.mp4\",BYTERANGE-START=0,BYTERANGE-LENGTH=720
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.preload_hints,
+            vec![
+                PreloadHint {
+                    hint_type: PreloadHintType::Part,
+                    uri: "seg-1.1.mp4".to_owned(),
+                    byte_range_start: None,
+                    byte_range_length: None,
+                },
+                PreloadHint {
+                    hint_type: PreloadHintType::Map,
+                    uri: "init.mp4".to_owned(),
+                    byte_range_start: Some(0),
+                    byte_range_length: Some(720),
+                },
+            ]
+        );
+
+        let data = "#EXTM3U
+#EXT-X-TARGETDURATION:4
+#EXTINF:3.006,
+seg-0.mp4
+#EXT-X-PRELOAD-HINT:TYPE=DATA,URI=\"seg-1.mp4\"
+";
+        assert!(matches!(
+            MediaPlaylist::parse(data),
+            Err(HlsParseError::InvalidValue { param: "TYPE", .. })
+        ));
+    }
+
+    /// Rendition reports are collected in order, with optional sequence and part numbers.
+    #[test]
+    fn test_parse_rendition_reports() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:9
+#EXT-X-TARGETDURATION:4
+#EXTINF:3.006,
+seg-0.mp4
+#EXT-X-RENDITION-REPORT:URI=\"../720p/prog.m3u8\",LAST-MSN=273,LAST-PART=2
+#EXT-X-RENDITION-REPORT:URI=\"../480p/prog.m3u8\"
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.rendition_reports,
+            vec![
+                RenditionReport {
+                    uri: "../720p/prog.m3u8".to_owned(),
+                    last_msn: Some(273),
+                    last_part: Some(2),
+                },
+                RenditionReport {
+                    uri: "../480p/prog.m3u8".to_owned(),
+                    last_msn: None,
+                    last_part: None,
+                },
+            ]
+        );
+        assert!(playlist.unknown_tags.is_empty());
+    }
+
+    /// Delta playlist updates record how many segments were skipped, and removed date ranges.
+    #[test]
+    fn test_parse_skip() {
+        let playlist = MediaPlaylist::from_str(sample_media_input().as_str()).unwrap();
+        assert!(playlist.skip.is_none());
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:9
+#EXT-X-TARGETDURATION:4
+#EXT-X-MEDIA-SEQUENCE:100
+#EXT-X-SKIP:SKIPPED-SEGMENTS=20
+#EXTINF:4.0,
+seg-120.mp4
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.skip,
+            Some(Skip {
+                skipped_segments: 20,
+                recently_removed_dateranges: None,
+            })
+        );
+        assert_eq!(playlist.segments.len(), 1);
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:10
+#EXT-X-TARGETDURATION:4
+#EXT-X-SKIP:SKIPPED-SEGMENTS=3,RECENTLY-REMOVED-DATERANGES=\"ad-1\tad-2\"
+#EXTINF:4.0,
+seg-3.mp4
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        let skip = playlist.skip.unwrap();
+        assert_eq!(skip.skipped_segments, 3);
+        assert_eq!(
+            skip.recently_removed_dateranges,
+            Some(vec!["ad-1".to_owned(), "ad-2".to_owned()])
+        );
+    }
+
+    /// Parsing from bytes reports where invalid UTF-8 starts, unless decoding leniently.
+    #[test]
+    fn test_from_bytes() {
+        let playlist = HlsPlaylist::from_bytes(sample_input().as_bytes()).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 36);
+
+        // Latin-1 encoded "é" in a rendition name
+        let mut data = b"#EXTM3U\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Fran".to_vec();
+        let offset = data.len();
+        data.extend(b"\xe7ais\",LANGUAGE=\"fr\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"fr.m3u8\"\n");
+        let err = HlsPlaylist::from_bytes(&data).unwrap_err();
+        assert_eq!(err, HlsParseError::InvalidUtf8 { offset });
+        assert_eq!(
+            err.to_string(),
+            format!("playlist is not valid UTF-8 at byte offset {offset}")
+        );
+
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let playlist = HlsPlaylist::from_bytes_with_options(&data, &options).unwrap();
+        assert_eq!(playlist.audio_streams.inner[0].name, "Fran\u{fffd}ais");
+    }
+
+    /// `#EXT-X-GAP` marks only the segment that follows it.
+    #[test]
+    fn test_parse_gap() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:8
+#EXT-X-TARGETDURATION:7
+#EXT-X-GAP
+#EXTINF:6.006,
+seg-0.ts
+#EXTINF:6.006,
+seg-1.ts
+";
+        let playlist = MediaPlaylist::from_str(data).unwrap();
+        let gaps = playlist.segments.iter().map(|s| s.gap).collect::<Vec<_>>();
+        assert_eq!(gaps, [true, false]);
+        assert!(playlist.unknown_tags.is_empty());
+    }
+
+    /// Tables size columns to their contents, so long URIs aren't cut unless a maximum width is set.
+    #[test]
+    fn test_format_table_widths() {
+        let uri = format!(
+            "https://cdn.example.com/{}/index.m3u8",
+            "very-long-path".repeat(5)
+        );
+        let data = format!(
+            "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f,mp4a.40.2\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+{uri}
+"
+        );
+        let playlist = HlsPlaylist::from_str(&data).unwrap();
+
+        let table = playlist.format_table(&TableOptions::default());
+        assert!(table.contains(&uri));
+        let stream_lines = table
+            .lines()
+            .skip_while(|l| *l != "Video Streams")
+            .skip(2)
+            .take(2)
+            .collect::<Vec<_>>();
+        assert_eq!(stream_lines[0].len(), stream_lines[1].len());
+
+        let compact = playlist.format_table(&TableOptions {
+            compact: true,
+            ..Default::default()
+        });
+        assert!(compact.contains(&uri));
+        assert!(!compact.contains('|'));
+
+        let truncated = playlist.format_table(&TableOptions {
+            max_width: Some(20),
+            ..Default::default()
+        });
+        assert!(!truncated.contains(&uri));
+        assert!(truncated.contains(&format!("{}…", &uri[..19])));
+    }
+
+    /// Group the sample's streams by resolution and by codec.
+    #[test]
+    fn test_group_streams() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+
+        let by_resolution = playlist.group_by_resolution();
+        let resolutions = by_resolution
+            .iter()
+            .map(|(r, streams)| (r.width, r.height, streams.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolutions,
+            [
+                (640, 360, 6),
+                (960, 540, 3),
+                (1280, 720, 6),
+                (1920, 1080, 9),
+                (2560, 1440, 6),
+                (3840, 2160, 6),
+            ]
+        );
+
+        let by_codec = playlist.group_by_codec();
+        assert_eq!(by_codec["ec-3"].len(), 24);
+        assert_eq!(by_codec["mp4a.40.2"].len(), 12);
+        assert_eq!(by_codec["hvc1.2.4.L63.90"].len(), 6);
+        assert!(
+            by_codec["hvc1.2.4.L63.90"]
+                .iter()
+                .all(|s| s.common.resolution.width == 640)
+        );
+    }
+
+    /// Stream collections can be collected into and extended from iterators.
+    #[test]
+    fn test_collect_streams() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let (low, high): (Vec<StreamInfo>, Vec<StreamInfo>) = playlist
+            .streams
+            .inner
+            .iter()
+            .cloned()
+            .partition(|s| s.common.resolution.width < 1920);
+
+        let mut streams: Streams = low.clone().into_iter().collect();
+        assert_eq!(streams.inner, low);
+        streams.extend(high.clone());
+        assert_eq!(streams.inner.len(), playlist.streams.inner.len());
+        assert_eq!(streams.inner[low.len()..], high);
+        assert_eq!(
+            streams.to_string().lines().count(),
+            playlist.streams.to_string().lines().count()
+        );
+
+        let audio: AudioStreams = playlist.audio_streams.inner.iter().cloned().collect();
+        assert_eq!(audio.inner, playlist.audio_streams.inner);
+        let mut iframes = IframeStreams::default();
+        iframes.extend(playlist.iframe_streams.inner.iter().cloned());
+        assert_eq!(iframes.inner, playlist.iframe_streams.inner);
+    }
+
+    /// Parsing a prefix stops at trailing data that isn't a playlist element, and returns it.
+    #[test]
+    fn test_parse_prefix() {
+        let sample = sample_input();
+        let data = format!("{sample}\n\n\nGET /master.m3u8 200 OK\n#EXTM3U\n");
+        assert!(HlsPlaylist::from_str(&data).is_err());
+
+        let (playlist, rest) = HlsPlaylist::from_str_prefix(&data).unwrap();
+        assert_eq!(rest, "GET /master.m3u8 200 OK\n#EXTM3U\n");
+        assert!(playlist.semantically_equal(&HlsPlaylist::from_str(&sample).unwrap()));
+
+        let (_, rest) = HlsPlaylist::from_str_prefix(&sample).unwrap();
+        assert!(rest.is_empty());
+
+        // Concatenated playlists split at the second header
+        let second = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low/index.m3u8
+";
+        let data = format!("{sample}\n{second}");
+        let (playlist, rest) = HlsPlaylist::from_str_prefix(&data).unwrap();
+        assert!(playlist.semantically_equal(&HlsPlaylist::from_str(&sample).unwrap()));
+        assert_eq!(rest, second);
+        let (playlist, rest) = HlsPlaylist::from_str_prefix(rest).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 1);
+        assert!(rest.is_empty());
+    }
+
+    /// Malformed params found by fuzzing fail to parse, rather than panicking.
+    #[test]
+    fn test_fuzz_regressions() {
+        assert!(Resolution::from_str("1920").is_err());
+        assert!(Resolution::from_str("").is_err());
+        assert!(AudioChannelInfo::from_str("").is_err());
+        assert!(AudioChannelInfo::from_str("/JOC").is_err());
+
+        for data in [
+            "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1920,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low/index.m3u8
+",
+            "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=,URI=\"audio/en.m3u8\"
+",
+        ] {
+            assert!(HlsPlaylist::from_str(data).is_err());
+            let (_, warnings) = HlsPlaylist::parse_lenient(data);
+            assert!(!warnings.is_empty());
+        }
+    }
+
+    /// Malformed resolutions fail with an error saying what's wrong.
+    #[test]
+    fn test_resolution_from_str_malformed() {
+        let err = |s: &str| Resolution::from_str(s).unwrap_err().to_string();
+        assert_eq!(err("1920"), "resolution is not of the form WxH: 1920");
+        assert_eq!(
+            err("1920x"),
+            "resolution is missing its width or height: 1920x"
+        );
+        assert_eq!(
+            err("x1080"),
+            "resolution is missing its width or height: x1080"
+        );
+        assert_eq!(
+            err("1920x1080x2"),
+            "resolution has more than two components: 1920x1080x2"
+        );
+        assert_eq!(err("wide x1080"), "failed to parse pixed width: wide ");
+        assert_eq!(
+            Resolution::from_str("1920x1080").unwrap(),
+            Resolution {
+                width: 1920,
+                height: 1080
+            }
+        );
+    }
+
+    /// Empty, non-numeric or missing channel counts fail with a clear error.
+    #[test]
+    fn test_channels_from_str_malformed() {
+        let err = |s: &str| AudioChannelInfo::from_str(s).unwrap_err().to_string();
+        assert_eq!(err(""), "channel count is missing: \"\"");
+        assert_eq!(err("/JOC"), "channel count is missing: \"/JOC\"");
+        assert_eq!(err("two"), "failed to parse channel count: two");
+        assert!(AudioChannelInfo::from_str("16/JOC").unwrap().joc);
+
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"\",URI=\"audio/en.m3u8\"
+";
+        assert!(HlsPlaylist::from_str(data).is_err());
+    }
+
+    /// Public tag parsers return `types` values, and fail as `nom` errors on tags that don't convert.
+    #[test]
+    fn test_public_tag_parsers() {
+        let data = "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=150000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,URI=\"iframe.m3u8\"
+#EXT-X-MEDIA:TYPE=VIDEO,GROUP-ID=\"cam\",NAME=\"Angle 2\",URI=\"angle2.m3u8\"
+";
+        let (rest, iframe) = parse::parse_iframe_stream_info(data).unwrap();
+        assert_eq!(iframe.common.uri, "iframe.m3u8");
+        let (rest, video) = parse::parse_video_rendition(rest).unwrap();
+        assert_eq!(video.name, "Angle 2");
+        assert!(rest.is_empty());
+
+        // Parses as a tag, but lacks required params
+        let data = "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=150000\n";
+        let Err(nom::Err::Error(e)) = parse::parse_iframe_stream_info(data) else {
+            panic!("expected a recoverable error");
+        };
+        assert_eq!(e.input, data);
+        assert_eq!(e.code, nom::error::ErrorKind::MapRes);
+    }
+
+    /// Fetch and parse a playlist from a server, failing on error statuses.
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn test_from_url() {
+        let mut server = mockito::Server::new();
+        let playlist_mock = server
+            .mock("GET", "/master.m3u8")
+            .with_header("content-type", "application/vnd.apple.mpegurl")
+            .with_body(sample_input())
+            .create();
+        let missing_mock = server
+            .mock("GET", "/missing.m3u8")
+            .with_status(404)
+            .create();
+
+        let playlist = HlsPlaylist::from_url(&format!("{}/master.m3u8", server.url())).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 36);
+        playlist_mock.assert();
+
+        let err = HlsPlaylist::from_url(&format!("{}/missing.m3u8", server.url())).unwrap_err();
+        assert!(err.to_string().contains("failed to GET playlist"));
+        missing_mock.assert();
+    }
+
+    /// Select variants by target and maximum bandwidth.
+    #[test]
+    fn test_variant_selection() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let closest = |target| {
+            playlist
+                .variant_closest_to_bandwidth(target)
+                .map(|s| s.common.bandwidth)
+        };
+        assert_eq!(closest(2_400_000), Some(2483789));
+        assert_eq!(closest(0), Some(705826));
+        assert_eq!(closest(u64::MAX), Some(26598940));
+        // Midway between 3380396 and 3790212
+        assert_eq!(closest(3585304), Some(3380396));
+
+        let at_or_below = |cap| {
+            playlist
+                .variant_at_or_below(cap)
+                .map(|s| s.common.bandwidth)
+        };
+        assert_eq!(at_or_below(5_000_000), Some(4686819));
+        assert_eq!(at_or_below(705826), Some(705826));
+        assert_eq!(at_or_below(700_000), None);
+
+        assert!(
+            HlsPlaylist::default()
+                .variant_closest_to_bandwidth(1_000_000)
+                .is_none()
+        );
+    }
+
+    /// With `keep_raw`, parsed elements record the source text they were parsed from.
+    #[test]
+    fn test_keep_raw() {
+        let data = sample_input();
+        let options = ParseOptions {
+            keep_raw: true,
+            ..Default::default()
+        };
+        let playlist = HlsPlaylist::from_str_with_options(&data, &options).unwrap();
+        let lines = data.lines().collect::<Vec<_>>();
+
+        let audio = &playlist.audio_streams.inner[0];
+        let audio_line = lines.iter().find(|l| l.contains(&audio.uri)).unwrap();
+        assert_eq!(audio.raw.as_deref(), Some(*audio_line));
+
+        let stream = &playlist.streams.inner[0];
+        let uri_index = lines.iter().position(|l| *l == stream.common.uri).unwrap();
+        let stream_raw = format!("{}\n{}", lines[uri_index - 1], lines[uri_index]);
+        assert_eq!(stream.common.raw.as_deref(), Some(stream_raw.as_str()));
+
+        let iframe = &playlist.iframe_streams.inner[0];
+        assert!(
+            iframe
+                .common
+                .raw
+                .as_deref()
+                .unwrap()
+                .ends_with(&format!("URI=\"{}\"", iframe.common.uri))
+        );
+
+        // Not kept by default, and ignored when comparing playlists
+        let default = HlsPlaylist::from_str(&data).unwrap();
+        assert!(default.audio_streams.inner[0].raw.is_none());
+        assert!(default.semantically_equal(&playlist));
+        // Nor when comparing or ordering streams
+        assert_eq!(default.streams.inner, playlist.streams.inner);
+        assert_eq!(
+            default.iframe_streams.inner[0].cmp(&playlist.iframe_streams.inner[0]),
+            std::cmp::Ordering::Equal
+        );
+
+        // Source text is recorded before variable substitution
+        let data = r#"#EXTM3U
+#EXT-X-DEFINE:NAME="lang",VALUE="en"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="English",LANGUAGE="{$lang}",DEFAULT=YES,AUTOSELECT=YES,CHANNELS="2",URI="audio/{$lang}.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS="avc1.4d401f,mp4a.40.2",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO="aac",CLOSED-CAPTIONS=NONE
+low/{$lang}.m3u8
+"#;
+        let lines = data.lines().collect::<Vec<_>>();
+        for playlist in [
+            HlsPlaylist::from_str_with_options(data, &options).unwrap(),
+            parsers::parse_hls_playlist_lenient(data, &options).0,
+        ] {
+            let audio = &playlist.audio_streams.inner[0];
+            assert_eq!(audio.uri, "audio/en.m3u8");
+            assert_eq!(audio.raw.as_deref(), Some(lines[2]));
+            let stream = &playlist.streams.inner[0];
+            assert_eq!(stream.common.uri, "low/en.m3u8");
+            let stream_raw = format!("{}\n{}", lines[3], lines[4]);
+            assert_eq!(stream.common.raw.as_deref(), Some(stream_raw.as_str()));
+        }
+    }
+
+    /// Resolutions, channel layouts and codecs can be deduplicated in hashed collections.
+    #[test]
+    fn test_hash_stream_properties() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+
+        let resolutions = playlist
+            .streams
+            .inner
+            .iter()
+            .map(|s| s.common.resolution.clone())
+            .collect::<HashSet<_>>();
+        assert_eq!(resolutions.len(), 6);
+        assert!(resolutions.contains(&Resolution {
+            width: 3840,
+            height: 2160
+        }));
+
+        let channels = playlist
+            .audio_streams
+            .inner
+            .iter()
+            .map(|a| a.channel_info.clone())
+            .collect::<HashSet<_>>();
+        assert_eq!(channels.len(), 3);
+
+        let codecs = playlist
+            .streams
+            .inner
+            .iter()
+            .flat_map(|s| s.common.parsed_codecs())
+            .collect::<HashSet<_>>();
+        assert_eq!(codecs.len(), 3);
+    }
+
+    /// `#EXT-X-DEFINE` variables are substituted into URIs; undefined variables are an error.
+    #[test]
+    fn test_variable_substitution() {
+        let data = "#EXTM3U
+#EXT-X-DEFINE:NAME=\"host\",VALUE=\"cdn.example.com\"
+#EXT-X-DEFINE:IMPORT=\"token\"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+https://{$host}/low/index.m3u8?token={$token}
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.definitions,
+            vec![
+                Definition::Value {
+                    name: "host".to_owned(),
+                    value: "cdn.example.com".to_owned()
+                },
+                Definition::Import("token".to_owned()),
+            ]
+        );
+        // Imported values come from outside the playlist, so their references are kept
+        assert_eq!(
+            playlist.streams.inner[0].common.uri,
+            "https://cdn.example.com/low/index.m3u8?token={$token}"
+        );
+        assert!(
+            playlist
+                .to_m3u8()
+                .contains("#EXT-X-DEFINE:IMPORT=\"token\"\n")
+        );
+
+        let options = ParseOptions {
+            keep_variable_references: true,
+            ..Default::default()
+        };
+        let kept = HlsPlaylist::from_str_with_options(data, &options).unwrap();
+        assert_eq!(
+            kept.streams.inner[0].common.uri,
+            "https://{$host}/low/index.m3u8?token={$token}"
+        );
+
+        let undefined = data.replace("{$host}", "{$origin}");
+        assert_eq!(
+            parse_hls_playlist_typed(&undefined).unwrap_err(),
+            HlsParseError::UndefinedVariable {
+                name: "origin".to_owned(),
+                line: 5
+            }
+        );
+        let (_, warnings) = HlsPlaylist::parse_lenient(&undefined);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, 5);
+    }
+
+    /// The feature summary of the sample counts its streams and detects its HDR (PQ) variants.
+    #[test]
+    fn test_feature_summary() {
+        let playlist = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let summary = playlist.feature_summary();
+        assert_eq!(summary.audio_count, playlist.audio_streams.inner.len());
+        assert_eq!(summary.video_count, playlist.streams.inner.len());
+        assert_eq!(summary.iframe_count, playlist.iframe_streams.inner.len());
+        assert!(summary.has_hdr);
+        assert!(summary.has_atmos);
+        assert!(!summary.has_subtitles);
+        assert_eq!((summary.declared_version, summary.required_version), (1, 1));
+
+        let subtitles = "#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"English\",URI=\"subs/en.m3u8\"
+";
+        let summary = HlsPlaylist::from_str(subtitles).unwrap().feature_summary();
+        assert!(summary.has_subtitles);
+        assert!(!summary.has_hdr);
+        assert_eq!(summary.video_count, 0);
+        assert_eq!((summary.declared_version, summary.required_version), (4, 1));
+
+        // A quoted value that looks like a subtitles type doesn't count
+        let captions = "#EXTM3U
+#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"a,TYPE=SUBTITLES,b\",INSTREAM-ID=\"CC1\"
+";
+        let summary = HlsPlaylist::from_str(captions).unwrap().feature_summary();
+        assert!(!summary.has_subtitles);
+    }
+
+    /// Declaring a version lower than the playlist's tags and attributes require is reported.
+    #[test]
+    fn test_validate_version() {
+        let master = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        assert_eq!(master.min_required_version(), 1);
+        assert!(master.validate().is_empty());
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-DEFINE:NAME=\"host\",VALUE=\"cdn.example.com\"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+https://{$host}/low/index.m3u8
+";
+        let master = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(master.min_required_version(), 8);
+        assert_eq!(
+            master.validate(),
+            vec![ValidationError::VersionTooLow {
+                declared: 3,
+                required: 8
+            }]
+        );
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
+#EXT-X-TARGETDURATION:4
+#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\",IV=0x0123456789abcdef0123456789abcdef
+#EXT-X-MAP:URI=\"init.mp4\"
+#EXTINF:4,
+seg-0.mp4
+";
+        let media = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(media.min_required_version(), 6);
+        assert_eq!(
+            media.validate(),
+            vec![ValidationError::VersionTooLow {
+                declared: 5,
+                required: 6
+            }]
+        );
+        let media =
+            MediaPlaylist::from_str(&data.replace("#EXT-X-MAP:URI=\"init.mp4\"\n", "")).unwrap();
+        assert_eq!(media.min_required_version(), 2);
+        assert!(media.validate().is_empty());
+        // A missing version tag declares version 1
+        let media = MediaPlaylist::from_str(&data.replace("#EXT-X-VERSION:5\n", "")).unwrap();
+        assert_eq!(
+            media.validate(),
+            vec![ValidationError::VersionTooLow {
+                declared: 1,
+                required: 6
+            }]
+        );
+
+        // Removed date ranges in a delta update need version 10, one more than skipping alone
+        let data = "#EXTM3U
+#EXT-X-VERSION:9
+#EXT-X-TARGETDURATION:4
+#EXT-X-SKIP:SKIPPED-SEGMENTS=3,RECENTLY-REMOVED-DATERANGES=\"ad-1\"
+#EXTINF:4,
+seg-3.mp4
+";
+        let media = MediaPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            media.validate(),
+            vec![ValidationError::VersionTooLow {
+                declared: 9,
+                required: 10
+            }]
+        );
+        let media =
+            MediaPlaylist::from_str(&data.replace(",RECENTLY-REMOVED-DATERANGES=\"ad-1\"", ""))
+                .unwrap();
+        assert!(media.validate().is_empty());
+
+        // Master-only: `SERVICE` closed captions need version 7
+        let data = "#EXTM3U
+#EXT-X-VERSION:6
+#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"English\",INSTREAM-ID=\"SERVICE1\"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=\"cc\"
+low/index.m3u8
+";
+        let master = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            master.validate(),
+            vec![ValidationError::VersionTooLow {
+                declared: 6,
+                required: 7
+            }]
+        );
+    }
+
+    /// Lenient mode discards a `;` comment after a tag's attributes; strict mode rejects it.
+    #[test]
+    fn test_lenient_trailing_comment() {
+        let data = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en.m3u8\"; note
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE; note
+low/index.m3u8
+#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=200000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,URI=\"low/iframe.m3u8\" ; note, with a comma
+";
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let playlist = HlsPlaylist::from_str_with_options(data, &options).unwrap();
+        assert_eq!(playlist.audio_streams.inner[0].uri, "audio/en.m3u8");
+        let stream = &playlist.streams.inner[0];
+        assert_eq!(stream.closed_captions, "NONE");
+        assert_eq!(stream.common.uri, "low/index.m3u8");
+        assert_eq!(
+            playlist.iframe_streams.inner[0].common.uri,
+            "low/iframe.m3u8"
+        );
+
+        assert!(HlsPlaylist::from_str(data).is_err());
+        // Same as the playlist without comments
+        let without_comments = data
+            .replace(" ; note, with a comma", "")
+            .replace("; note", "");
+        let strict = HlsPlaylist::from_str(&without_comments).unwrap();
+        assert!(strict.semantically_equal(&playlist));
+    }
+
+    /// Segments and streams written one at a time parse back into the same playlist.
+    #[test]
+    fn test_playlist_writer() {
+        let key = |n: usize| EncryptionKey {
+            method: KeyMethod::Aes128,
+            uri: format!("https://keys.example.com/key-{n}"),
+            iv: Some(format!("0x{n:032x}")),
+            key_format: None,
+            key_format_versions: None,
+        };
+        let segments = (0..1000)
+            .map(|i| Segment {
+                duration: if i == 999 { 4.171 } else { 6.006 },
+                title: (i == 999).then(|| "credits".to_owned()),
+                uri: format!("seg-{i}.m4s"),
+                // Segments switch keys every 100, and the last 100 are unencrypted
+                keys: (i < 900).then(|| key(i / 100)).into_iter().collect(),
+                map: Some(MediaInitialization {
+                    uri: format!("init-{}.mp4", i / 500),
+                    byte_range: None,
+                }),
+                bitrate: Some(1650),
+                parts: Vec::new(),
+                gap: i == 500,
+            })
+            .collect::<Vec<_>>();
+
+        let mut writer = PlaylistWriter::new(Vec::new());
+        writer.write_header(6).unwrap();
+        writer.write_target_duration(7).unwrap();
+        for segment in segments.iter() {
+            writer.write_segment(segment).unwrap();
+        }
+        writer.write_end_list().unwrap();
+        let data = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        // Tags are only written when their value changes
+        assert_eq!(data.matches("#EXT-X-KEY:").count(), 10);
+        assert_eq!(data.matches("#EXT-X-MAP:").count(), 2);
+        assert_eq!(data.matches("#EXT-X-BITRATE:").count(), 1);
+        let playlist = MediaPlaylist::parse(&data).unwrap();
+        assert_eq!(playlist.version, 6);
+        assert_eq!(playlist.target_duration, 7);
+        assert!(playlist.end_list);
+        assert_eq!(playlist.segments, segments);
+        assert!(playlist.validate().is_empty());
+
+        let master = HlsPlaylist::from_str(sample_input().as_str()).unwrap();
+        let mut writer = PlaylistWriter::new(Vec::new());
+        writer.write_header(master.version).unwrap();
+        for stream in master.streams.inner.iter() {
+            writer.write_stream(stream).unwrap();
+        }
+        let data = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let parsed = HlsPlaylist::parse_master(&data).unwrap();
+        assert_eq!(parsed.streams.inner, master.streams.inner);
+    }
+
+    /// `INSTREAM-ID` values parse into their channel or service number, within the allowed range.
+    #[test]
+    fn test_instream_id() {
+        assert_eq!(InstreamId::from_str("CC2").unwrap(), InstreamId::Cc(2));
+        assert_eq!(
+            InstreamId::from_str("SERVICE63").unwrap(),
+            InstreamId::Service(63)
+        );
+        assert_eq!(InstreamId::Cc(2).to_string(), "CC2");
+        assert_eq!(InstreamId::Service(63).to_string(), "SERVICE63");
+
+        for invalid in [
+            "CC0",
+            "CC5",
+            "SERVICE0",
+            "SERVICE64",
+            "SERVICE",
+            "CC-1",
+            "CC+2",
+            "cc1",
+            "DVB1",
+        ] {
+            assert!(InstreamId::from_str(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(
+            InstreamId::from_str("SERVICE64").unwrap_err().to_string(),
+            "instream ID number must be 1-63: SERVICE64"
+        );
+    }
+
+    /// Merging an audio-only master into a video-only one keeps all renditions and variants,
+    /// preferring the first playlist's single-valued tags.
+    #[test]
+    fn test_merge() {
+        let video = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-START:TIME-OFFSET=10
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO=\"aac\",CLOSED-CAPTIONS=NONE
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2560000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,AUDIO=\"aac\",CLOSED-CAPTIONS=NONE
+high/index.m3u8
+";
+        let audio = "#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-START:TIME-OFFSET=0
+#EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"Example\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en.m3u8\"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO=\"aac\",CLOSED-CAPTIONS=NONE
+low/index.m3u8
+";
+        let mut merged = HlsPlaylist::from_str(video).unwrap();
+        merged.merge(HlsPlaylist::from_str(audio).unwrap());
+        assert_eq!(merged.version, 4);
+        assert!(!merged.independent_segments);
+        assert_eq!(merged.audio_streams.inner.len(), 1);
+        assert_eq!(merged.streams.inner.len(), 3);
+        assert!(merged.validate().is_empty());
+
+        // The first playlist's start wins, and the other's remaining unknown tags come last
+        let m3u8 = merged.to_m3u8();
+        assert!(m3u8.contains("#EXT-X-START:TIME-OFFSET=10\n"));
+        assert!(!m3u8.contains("TIME-OFFSET=0"));
+        assert!(
+            m3u8.trim_end()
+                .ends_with("#EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"Example\"")
+        );
+        assert!(
+            HlsPlaylist::from_str(&m3u8)
+                .unwrap()
+                .semantically_equal(&merged)
+        );
+
+        merged.dedup_streams();
+        assert_eq!(
+            merged
+                .streams
+                .inner
+                .iter()
+                .map(|s| s.common.uri.as_str())
+                .collect::<Vec<_>>(),
+            ["low/index.m3u8", "high/index.m3u8"]
+        );
+    }
+
+    /// Test that media tags of different types in one playlist are all parsed.
+    #[test]
+    fn test_parse_mixed_media() {
+        let data = r#"#EXTM3U
+#EXT-X-VERSION:6
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aud",NAME="English",LANGUAGE="en",DEFAULT=YES,AUTOSELECT=YES,CHANNELS="2",URI="audio/en.m3u8"
+#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID="subs",NAME="English",LANGUAGE="en",DEFAULT=YES,AUTOSELECT=YES,FORCED=NO,URI="subs/en.m3u8"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aud",NAME="French",LANGUAGE="fr",DEFAULT=NO,AUTOSELECT=YES,CHANNELS="2",URI="audio/fr.m3u8"
+#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID="subs",NAME="French",LANGUAGE="fr",DEFAULT=NO,AUTOSELECT=YES,FORCED=NO,URI="subs/fr.m3u8"
+#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID="cc",NAME="English",LANGUAGE="en",INSTREAM-ID="CC1"
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS="avc1.640028,mp4a.40.2",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO="aud",CLOSED-CAPTIONS=NONE
+video/index.m3u8
+"#;
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist
+                .audio_streams
+                .inner
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>(),
+            ["English", "French"]
+        );
+        assert_eq!(
+            playlist
+                .subtitles
+                .iter()
+                .map(|s| (s.language.as_deref(), s.default, s.uri.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (Some("en"), true, "subs/en.m3u8"),
+                (Some("fr"), false, "subs/fr.m3u8")
+            ]
+        );
+        assert!(playlist.subtitles.iter().all(|s| !s.forced));
+        // Closed captions aren't modeled yet
+        assert_eq!(playlist.unknown_tags.len(), 1);
+        assert!(playlist.feature_summary().has_subtitles);
+        assert_eq!(playlist.streams.inner.len(), 1);
+        // Renditions are serialized grouped by type, so only the content round-trips
+        let reparsed = HlsPlaylist::from_str(&playlist.to_m3u8()).unwrap();
+        assert!(reparsed.semantically_equal(&playlist));
+        assert_eq!(reparsed.subtitles, playlist.subtitles);
+
+        // Closed-caption tags are validated, though kept as unknown tags
+        for invalid in ["CC0", "SERVICE64"] {
+            let data = data.replace("INSTREAM-ID=\"CC1\"", &format!("INSTREAM-ID=\"{invalid}\""));
+            assert_eq!(
+                HlsPlaylist::from_str(&data)
+                    .unwrap_err()
+                    .downcast::<HlsParseError>()
+                    .unwrap(),
+                HlsParseError::InvalidValue {
+                    param: "INSTREAM-ID",
+                    value: invalid.to_owned(),
+                }
+            );
+        }
+
+        // Single-type parsers reject media of other types
+        assert!(
+            Audio::from_str(
+                r#"#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID="subs",NAME="English",URI="subs/en.m3u8""#
+            )
+            .is_err()
+        );
+    }
+
+    /// Test that tables stay aligned when an audio name contains wide characters.
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn test_table_wide_characters() {
+        use crate::table::display_width;
+
+        let data = r#"#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="日本語",LANGUAGE="ja",DEFAULT=YES,AUTOSELECT=YES,CHANNELS="2",URI="audio/ja.m3u8"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="English",LANGUAGE="en",DEFAULT=NO,AUTOSELECT=YES,CHANNELS="2",URI="audio/en.m3u8"
+"#;
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(display_width("日本語"), 6);
+
+        // Sized tables
+        let table = playlist.format_table(&TableOptions::default());
+        let audio_lines = table.lines().skip(2).take(3).collect::<Vec<_>>();
+        assert!(audio_lines[1].contains("日本語"));
+        assert!(
+            audio_lines
+                .iter()
+                .all(|l| display_width(l) == display_width(audio_lines[0]))
+        );
+
+        // Truncation counts wide characters as two columns
+        let truncated = playlist.format_table(&TableOptions {
+            max_width: Some(4),
+            ..Default::default()
+        });
+        assert!(truncated.contains("| 日…  |"));
+
+        // Fixed-width tables
+        let fixed = playlist.audio_streams.to_string();
+        let lines = fixed
+            .lines()
+            .filter(|l| l.starts_with('|'))
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines
+                .iter()
+                .all(|l| display_width(l) == display_width(lines[0]))
+        );
+    }
+
+    /// Test that a stream's last attribute may omit its comma, capturing the URI on the next line,
+    /// while attributes not separated by commas are rejected.
+    #[test]
+    fn test_parse_stream_single_attribute() {
+        for line in [
+            "BANDWIDTH=1000",
+            "BANDWIDTH=1000,",
+            "BANDWIDTH=1000 ",
+            "BANDWIDTH=1000\r",
+        ] {
+            let data = format!("#EXTM3U\n#EXT-X-STREAM-INF:{line}\nlow/index.m3u8\n");
+            let playlist = HlsPlaylistRef::parse(&data).unwrap();
+            assert_eq!(playlist.streams.len(), 1);
+            assert_eq!(playlist.streams[0].params, [(P_BANDWIDTH, "1000")]);
+            assert_eq!(playlist.streams[0].uri, Some("low/index.m3u8"));
+        }
+
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2560000,CODECS=\"avc1.4d401f\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+high/index.m3u8";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist
+                .streams
+                .inner
+                .iter()
+                .map(|s| s.common.uri.as_str())
+                .collect::<Vec<_>>(),
+            ["low/index.m3u8", "high/index.m3u8"]
+        );
+
+        assert!(
+            HlsPlaylistRef::parse(
+                "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1000 RESOLUTION=1280x720\nlow/index.m3u8\n"
+            )
+            .is_err()
+        );
+    }
+
+    /// Test that the playlist kind is detected from its tags, and a mix of both kinds is rejected.
+    #[test]
+    fn test_parse_playlist_kind() {
+        assert!(matches!(
+            Playlist::parse(&sample_input()).unwrap(),
+            Playlist::Master(_)
+        ));
+        assert!(matches!(
+            Playlist::parse(&sample_media_input()).unwrap(),
+            Playlist::Media(_)
+        ));
+
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:10
+#EXTINF:9.009,
+segment0.ts
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low/index.m3u8
+";
+        let err = Playlist::parse(data).unwrap_err();
+        assert_eq!(
+            err,
+            HlsParseError::MixedPlaylist {
+                master_tag: "#EXT-X-STREAM-INF".to_string(),
+                master_line: 6,
+                media_tag: "#EXT-X-TARGETDURATION".to_string(),
+                media_line: 3,
+            }
+        );
+        assert!(err.to_string().contains("#EXT-X-STREAM-INF at line 6"));
+    }
+
+    /// Test parsing an iframe stream that declares `AVERAGE-BANDWIDTH`, and its round trip.
+    #[test]
+    fn test_parse_iframe_average_bandwidth() {
+        let data = r#"#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,AVERAGE-BANDWIDTH=68000,CODECS="hvc1.2.4.L63.90",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI="iframe.m3u8""#;
+        let iframe = IframeStreamInfo::from_str(data).unwrap();
+        assert_eq!(iframe.average_bandwidth, Some(68000));
+        assert_eq!(iframe.common.bandwidth, 77758);
+
+        let playlist = HlsPlaylist::from_str(&format!("#EXTM3U\n{data}\n")).unwrap();
+        assert_eq!(playlist.iframe_streams.inner[0], iframe);
+        assert!(playlist.to_m3u8().contains(data));
+
+        let err = IframeStreamInfo::from_str(&data.replace("68000", "high")).unwrap_err();
+        assert!(err.to_string().contains("AVERAGE-BANDWIDTH"));
+    }
+
+    /// Test that the codecs of all video and iframe streams are aggregated, raw and typed.
+    #[test]
+    fn test_all_codecs() {
+        let playlist = HlsPlaylist::from_str(&sample_input()).unwrap();
+        let codecs = playlist.all_codecs();
+        assert_eq!(
+            codecs,
+            [
+                "ec-3",
+                "hvc1.2.4.H120.90",
+                "hvc1.2.4.H150.90",
+                "hvc1.2.4.L120.90",
+                "hvc1.2.4.L150.90",
+                "hvc1.2.4.L63.90",
+                "hvc1.2.4.L90.90",
+                "hvc1.2.4.L93.90",
+                "mp4a.40.2",
+            ]
+            .map(String::from)
+            .into()
+        );
+        assert_eq!(
+            playlist.all_codecs_typed(),
+            [Codec::Hevc, Codec::Aac, Codec::Ec3].into()
+        );
+    }
+
+    /// Test parsing a stream's `ALLOWED-CPC` entries, and their round trip.
+    #[test]
+    fn test_parse_allowed_cpc() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,ALLOWED-CPC=\"com.example.drm1:SMART-TV/PC,com.example.drm2:HW\",CLOSED-CAPTIONS=NONE
+low/index.m3u8
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        assert_eq!(
+            playlist.streams.inner[0].common.allowed_cpc,
+            Some(vec![
+                CpcEntry {
+                    key_format: "com.example.drm1".to_owned(),
+                    classes: vec!["SMART-TV".to_owned(), "PC".to_owned()],
+                },
+                CpcEntry {
+                    key_format: "com.example.drm2".to_owned(),
+                    classes: vec!["HW".to_owned()],
+                },
+            ])
+        );
+        assert_eq!(playlist.to_m3u8(), data);
+
+        let err = HlsPlaylist::from_str(&data.replace("drm2:HW", "drm2")).unwrap_err();
+        assert!(err.to_string().contains("ALLOWED-CPC"));
+    }
+
+    /// Test that a playlist equals its clone and its own round trip, and differs once modified.
+    #[test]
+    fn test_playlist_eq() {
+        let playlist = HlsPlaylist::from_str(&sample_input()).unwrap();
+        assert_eq!(playlist, playlist.clone());
+        assert_eq!(
+            HlsPlaylist::from_str(&playlist.to_m3u8()).unwrap(),
+            playlist
+        );
+
+        let mut other = playlist.clone();
+        other.streams.inner.swap(0, 1);
+        assert_ne!(other, playlist);
+        assert!(other.semantically_equal(&playlist));
+    }
+
+    /// Test that a URI line without a preceding `#EXT-X-STREAM-INF` gets a targeted error.
+    #[test]
+    fn test_parse_stray_uri() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:3
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+high/index.m3u8
+";
+        let HlsParseError::Syntax(e) =
+            parsers::parse_hls_playlist(data, &ParseOptions::default()).unwrap_err()
+        else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(e.kind, ParseErrorKind::UnexpectedUri);
+        assert_eq!(e.line, 3);
+        assert_eq!(
+            e.to_string(),
+            "unexpected URI line \"low/index.m3u8\" without preceding #EXT-X-STREAM-INF at line 3, column 1"
+        );
+
+        // Lines that don't look like URIs are still unrecognized elements
+        let HlsParseError::Syntax(e) =
+            parsers::parse_hls_playlist("#EXTM3U\nnot a uri\n", &ParseOptions::default())
+                .unwrap_err()
+        else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(e.kind, ParseErrorKind::UnrecognizedElement);
+    }
+
+    /// Test that `uris` yields the URI of every audio, video and iframe stream.
+    #[test]
+    fn test_uris() {
+        let playlist = HlsPlaylist::from_str(&sample_input()).unwrap();
+        let uris = playlist.uris().collect::<Vec<_>>();
+        assert_eq!(
+            uris.len(),
+            playlist.audio_streams.inner.len()
+                + playlist.streams.inner.len()
+                + playlist.iframe_streams.inner.len()
+        );
+        assert_eq!(uris[0], playlist.audio_streams.inner[0].uri);
+        assert_eq!(
+            uris.last().copied(),
+            playlist
+                .iframe_streams
+                .inner
+                .last()
+                .map(|s| s.common.uri.as_str())
+        );
+    }
+
+    /// Test `Resolution` presets, checked construction and derived measures.
+    #[test]
+    fn test_resolution_helpers() {
+        assert_eq!(Resolution::new(1920, 1080), Some(Resolution::HD_1080));
+        assert_eq!(
+            Resolution::from_str("3840x2160").unwrap(),
+            Resolution::UHD_4K
+        );
+        assert_eq!(Resolution::new(0, 720), None);
+        assert_eq!(Resolution::new(1280, 0), None);
+        assert!(Resolution::HD_720 < Resolution::HD_1080);
+
+        assert!(Resolution::from_str("1920x0").is_err());
+        assert!(Resolution::from_str("0x1080").is_err());
+
+        assert_eq!(Resolution::HD_1080.total_pixels(), Some(2_073_600));
+        let huge = Resolution::from_str(&format!("{}x2", usize::MAX)).unwrap();
+        assert_eq!(huge.total_pixels(), None);
+        for resolution in [Resolution::HD_720, Resolution::HD_1080, Resolution::UHD_4K] {
+            assert!((resolution.aspect_ratio() - 16.0 / 9.0).abs() < f32::EPSILON);
+        }
+    }
+
+    /// Test that `FRAME-RATE` values equal regardless of representation, and malformed ones are rejected.
+    #[test]
+    fn test_parse_frame_rate_representations() {
+        let stream = |frame_rate: &str| {
+            StreamInfo::from_str(&format!(
+                "#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,FRAME-RATE={frame_rate},VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE\nlow/index.m3u8"
+            ))
+        };
+        let whole = stream("30").unwrap();
+        let padded = stream("30.000").unwrap();
+        assert_eq!(whole.frame_rate, Some(30.0));
+        assert_eq!(whole.frame_rate, padded.frame_rate);
+        assert_eq!(whole.cmp(&padded), std::cmp::Ordering::Equal);
+        assert!(stream("59.94").unwrap() > stream("29.97").unwrap());
+
+        for frame_rate in ["NaN", "inf", "-30", "0", "3e1", "30.", ".5"] {
+            assert!(stream(frame_rate).is_err(), "accepted {frame_rate}");
+        }
+    }
+
+    /// Test that the top-level `parse` returns the kind of playlist it's given.
+    #[test]
+    fn test_parse_entry() {
+        let Playlist::Master(master) = parse(&sample_input()).unwrap() else {
+            panic!("expected a master playlist");
+        };
+        assert!(master.semantically_equal(&HlsPlaylist::from_str(&sample_input()).unwrap()));
+
+        let Playlist::Media(media) = parse(&sample_media_input()).unwrap() else {
+            panic!("expected a media playlist");
+        };
+        assert_eq!(
+            media.segments,
+            MediaPlaylist::from_str(&sample_media_input())
+                .unwrap()
+                .segments
+        );
+
+        assert!(parse("not a playlist").is_err());
+    }
+
+    /// Test that the object count is exposed only for Joint Object Coding audio.
+    #[test]
+    fn test_audio_object_count() {
+        let atmos = AudioChannelInfo::from_str("16/JOC").unwrap();
+        assert!(atmos.joc);
+        assert_eq!(atmos.channels, 16);
+        assert_eq!(atmos.object_count(), Some(16));
+
+        let stereo = AudioChannelInfo::from_str("2").unwrap();
+        assert!(!stereo.joc);
+        assert_eq!(stereo.object_count(), None);
+    }
+
+    /// Test that tags unknown to HLS fail parsing with `error_on_unknown_tags`, and are kept otherwise.
+    #[test]
+    fn test_error_on_unknown_tags() {
+        let data = format!(
+            "{}\n#EXT-X-START:TIME-OFFSET=10\n#EXT-X-CUSTOM:foo=bar\n",
+            sample_input().trim_end()
+        );
+        let strict = ParseOptions {
+            error_on_unknown_tags: true,
+            ..Default::default()
+        };
+        let err = parsers::parse_hls_playlist(&data, &strict).unwrap_err();
+        assert_eq!(
+            err,
+            HlsParseError::UnknownTag {
+                tag: "#EXT-X-CUSTOM".to_owned(),
+                line: data.lines().count(),
+            }
+        );
+        assert!(err.to_string().contains("unknown tag #EXT-X-CUSTOM"));
+
+        let playlist = HlsPlaylist::from_str(&data).unwrap();
+        assert_eq!(
+            playlist.unknown_tags.last().unwrap().name(),
+            "#EXT-X-CUSTOM"
+        );
+        assert!(
+            parsers::parse_hls_playlist(&data.replace("#EXT-X-CUSTOM:foo=bar\n", ""), &strict)
+                .is_ok()
+        );
+
+        let media = format!("{}#EXT-X-CUSTOM\n", sample_media_input());
+        assert!(matches!(
+            MediaPlaylist::parse_with_options(&media, &strict),
+            Err(HlsParseError::UnknownTag { .. })
+        ));
+        assert!(MediaPlaylist::from_str(&media).is_ok());
+
+        // Spec tags kept as unknown tags in media playlists are accepted
+        let media = sample_media_input().replace(
+            "#EXTM3U\n",
+            "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES\n",
+        );
+        let playlist = MediaPlaylist::parse_with_options(&media, &strict).unwrap();
+        assert_eq!(playlist.unknown_tags.len(), 2);
+    }
+
+    /// Test diffing a media playlist against a reload with one more segment, and after sliding forward.
+    #[test]
+    fn test_media_playlist_diff() {
+        let old = MediaPlaylist::from_str(&sample_media_input()).unwrap();
+        assert!(old.diff(&old).is_empty());
+
+        let data = sample_media_input().replace(
+            "#EXT-X-ENDLIST",
+            "#EXTINF:4.0,\nappended.ts\n#EXT-X-ENDLIST",
+        );
+        let new = MediaPlaylist::from_str(&data).unwrap();
+        assert!(new.validate().is_empty());
+        let diff = old.diff(&new);
+        let seq = old.media_sequence + old.segments.len();
+        assert_eq!(diff.added, [(seq, new.segments.last().unwrap())]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+
+        // A sliding window drops the first segment, and a changed duration is reported as such
+        let mut slid = new.clone();
+        slid.segments.remove(0);
+        slid.media_sequence += 1;
+        slid.segments[0].duration += 1.0;
+        let diff = new.diff(&slid);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed, [(new.media_sequence, &new.segments[0])]);
+        assert_eq!(
+            diff.changed,
+            [(new.media_sequence + 1, &new.segments[1], &slid.segments[0])]
+        );
+    }
+
+    /// Parsing time grows linearly with the playlist, so large playlists parse quickly.
+    #[test]
+    fn test_parse_large_playlists() {
+        let mut media = String::from("#EXTM3U\n#EXT-X-TARGETDURATION:4\n");
+        for i in 0..50_000 {
+            media.push_str(&format!("#EXTINF:4.000,\nseg-{i}.ts\n"));
+        }
+        let mut master = String::from("#EXTM3U\n");
+        for i in 0..20_000 {
+            master.push_str(&format!(
+                "#EXT-X-STREAM-INF:BANDWIDTH={i},CODECS=\"avc1.640028\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE\nvideo/{i}.m3u8\n"
+            ));
+        }
+
+        let start = std::time::Instant::now();
+        let playlist = MediaPlaylist::from_str(&media).unwrap();
+        assert_eq!(playlist.segments.len(), 50_000);
+        let playlist = HlsPlaylist::from_str(&master).unwrap();
+        assert_eq!(playlist.streams.inner.len(), 20_000);
+        let (_, warnings) = HlsPlaylist::parse_lenient(&master);
+        assert!(warnings.is_empty());
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(10),
+            "took {:?}",
+            start.elapsed()
+        );
+    }
+
+    /// Variables are only substituted into quoted-string and hexadecimal attribute values and URIs,
+    /// and each may only be declared once.
+    #[test]
+    fn test_variable_substitution_scope() {
+        let data = "#EXTM3U
+#EXT-X-DEFINE:NAME=\"res\",VALUE=\"1280x720,BANDWIDTH=1\"
+#EXT-X-DEFINE:NAME=\"lang\",VALUE=\"en\"
+#EXT-X-X-{$lang}:ID=\"{$lang}\"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"{$res}\",LANGUAGE=\"{$lang}\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/{$lang}.m3u8\"
+";
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+        // A value containing an attribute list stays within its quoted string
+        assert_eq!(playlist.audio_streams.inner[0].name, "1280x720,BANDWIDTH=1");
+        assert_eq!(playlist.audio_streams.inner[0].uri, "audio/en.m3u8");
+        // Tag names aren't substituted, unlike the tag's values
+        assert_eq!(playlist.unknown_tags[0].line, "#EXT-X-X-{$lang}:ID=\"en\"");
+
+        // Nor are other values, so a reference can't inject attributes
+        let injected = format!(
+            "{data}#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION={{$res}},VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE\nlow/index.m3u8\n"
+        );
+        assert!(HlsPlaylist::from_str(&injected).is_err());
+
+        // Hexadecimal values may only be substituted with hexadecimal digits
+        let media = "#EXTM3U
+#EXT-X-VERSION:8
+#EXT-X-TARGETDURATION:4
+#EXT-X-DEFINE:NAME=\"iv\",VALUE=\"0123456789abcdef0123456789ABCDEF\"
+#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\",IV=0x{$iv}
+#EXTINF:4,
+seg-0.ts
+";
+        let playlist = MediaPlaylist::from_str(media).unwrap();
+        assert_eq!(
+            playlist.segments[0].keys[0].iv.as_deref(),
+            Some("0x0123456789abcdef0123456789ABCDEF")
+        );
+        let not_hex = media.replace("0123456789abcdef", "0123456789abcdef,METHOD=NONE");
+        assert!(MediaPlaylist::from_str(&not_hex).is_err());
+
+        // Declaring a name twice is an error, even with the same value
+        let duplicate = data.replace(
+            "#EXT-X-DEFINE:NAME=\"lang\",VALUE=\"en\"\n",
+            "#EXT-X-DEFINE:NAME=\"lang\",VALUE=\"en\"\n#EXT-X-DEFINE:NAME=\"lang\",VALUE=\"en\"\n",
+        );
+        let error = HlsParseError::DuplicateVariable {
+            name: "lang".to_owned(),
+            line: 4,
+        };
+        assert_eq!(parse_hls_playlist_typed(&duplicate).unwrap_err(), error);
+        let (playlist, warnings) = HlsPlaylist::parse_lenient(&duplicate);
+        // The duplicate is skipped; the other warning is for the unknown tag
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            (warnings[0].line, &warnings[0].message),
+            (4, &error.to_string())
+        );
+        assert_eq!(playlist.definitions.len(), 2);
+        let duplicate = media.replace(
+            "#EXT-X-KEY",
+            "#EXT-X-DEFINE:NAME=\"iv\",VALUE=\"00\"\n#EXT-X-KEY",
+        );
+        assert_eq!(
+            parsers::parse_media_playlist(&duplicate, &ParseOptions::default()).unwrap_err(),
+            HlsParseError::DuplicateVariable {
+                name: "iv".to_owned(),
+                line: 5,
+            }
+        );
+    }
+
+    /// Keys of different `KEYFORMAT`s apply to the same segments, each until replaced by its own format.
+    #[test]
+    fn test_parse_media_playlist_key_formats() {
+        let data = "#EXTM3U
+#EXT-X-VERSION:5
+#EXT-X-TARGETDURATION:7
+#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://key-1\",KEYFORMAT=\"com.apple.streamingkeydelivery\"
+#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"data:text/plain;base64,AAAA\",KEYFORMAT=\"urn:uuid:edef8ba9-79d6-4ace-a3c8-27dcd51d21ed\"
+#EXTINF:6.006,
+seg-0.ts
+#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://key-2\",KEYFORMAT=\"com.apple.streamingkeydelivery\"
+#EXTINF:6.006,
+seg-1.ts
+#EXT-X-KEY:METHOD=NONE
+#EXTINF:6.006,
+seg-2.ts
+";
+        let playlist = MediaPlaylist::parse(data).unwrap();
+        let keys = |i: usize| {
+            playlist.segments[i]
+                .keys
+                .iter()
+                .map(|k| (k.format(), k.uri.as_str()))
+                .collect::<Vec<_>>()
+        };
+        let widevine = "urn:uuid:edef8ba9-79d6-4ace-a3c8-27dcd51d21ed";
+        let fairplay = "com.apple.streamingkeydelivery";
+        assert_eq!(
+            keys(0),
+            [
+                (fairplay, "skd://key-1"),
+                (widevine, "data:text/plain;base64,AAAA")
+            ]
+        );
+        assert_eq!(
+            keys(1),
+            [
+                (fairplay, "skd://key-2"),
+                (widevine, "data:text/plain;base64,AAAA")
+            ]
+        );
+        assert!(keys(2).is_empty());
+
+        // Written back, each segment keeps all of its keys
+        let mut writer = PlaylistWriter::new(Vec::new());
+        writer.write_header(playlist.version).unwrap();
+        writer
+            .write_target_duration(playlist.target_duration)
+            .unwrap();
+        for segment in playlist.segments[..2].iter().rev() {
+            writer.write_segment(segment).unwrap();
+        }
+        let written =
+            MediaPlaylist::parse(&String::from_utf8(writer.finish().unwrap()).unwrap()).unwrap();
+        assert_eq!(written.segments[0], playlist.segments[1]);
+        assert_eq!(written.segments[1], playlist.segments[0]);
+    }
 }
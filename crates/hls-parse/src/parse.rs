@@ -0,0 +1,141 @@
+//! Parsers for single HLS tags, as [`nom`] parsers returning types from [`crate::types`].
+//!
+//! Each parser consumes one tag (and, for `#EXT-X-STREAM-INF`, its URI line), followed by any
+//! newlines, so they compose with `nom` combinators to parse playlists that extend HLS.
+//! Tags that parse but don't convert (e.g. a missing required param) fail with
+//! [`ErrorKind::MapRes`], like [`nom::combinator::map_res`].
+//!
+//! These parsers use `nom` 8, re-exported as [`crate::nom`]. Their signatures, including the
+//! `nom` version, are part of this crate's public API: a `nom` major version bump is a breaking change.
+//!
+//! Parse every stream of a (master) playlist section:
+//! ```
+//! use hls_parse::nom::{Parser, multi::many1};
+//! use hls_parse::parse::parse_stream_info;
+//!
+//! let data = "#EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+//! low/index.m3u8
+//! #EXT-X-STREAM-INF:BANDWIDTH=2560000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+//! high/index.m3u8
+//! ";
+//! let (rest, streams) = many1(parse_stream_info).parse(data).unwrap();
+//! assert!(rest.is_empty());
+//! assert_eq!(streams[1].common.uri, "high/index.m3u8");
+//! ```
+//!
+//! Mix with parsers of custom tags:
+//! ```
+//! use hls_parse::nom::{
+//!     IResult, Parser,
+//!     branch::alt,
+//!     bytes::complete::tag,
+//!     character::complete::{multispace0, not_line_ending},
+//!     multi::many1,
+//!     sequence::delimited,
+//! };
+//! use hls_parse::parse::parse_audio;
+//! use hls_parse::types::media::Audio;
+//!
+//! enum Line {
+//!     Audio(Audio),
+//!     Region(String),
+//! }
+//!
+//! fn region(data: &str) -> IResult<&str, String> {
+//!     delimited(tag("#EXT-X-MY-REGION:"), not_line_ending, multispace0)
+//!         .map(str::to_owned)
+//!         .parse(data)
+//! }
+//!
+//! let data = "#EXT-X-MY-REGION:eu-west
+//! #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en.m3u8\"
+//! ";
+//! let (_, lines) = many1(alt((parse_audio.map(Line::Audio), region.map(Line::Region))))
+//!     .parse(data)
+//!     .unwrap();
+//! assert!(matches!(&lines[0], Line::Region(r) if r == "eu-west"));
+//! assert!(matches!(&lines[1], Line::Audio(a) if a.name == "English"));
+//! ```
+
+use nom::IResult;
+use nom::error::{Error, ErrorKind};
+
+use crate::ParseOptions;
+use crate::error::HlsParseError;
+use crate::parsers::{
+    HlsElement, consumed, hls_audio, hls_content_steering, hls_iframe_stream_info, hls_stream_info,
+    hls_video_rendition,
+};
+use crate::types::borrowed::TagRef;
+use crate::types::media::{Audio, VideoRendition};
+use crate::types::steering::ContentSteering;
+use crate::types::stream_info::{IframeStreamInfo, StreamInfo};
+
+/// Parse an `#EXT-X-MEDIA:TYPE=AUDIO` tag.
+pub fn parse_audio(data: &str) -> IResult<&str, Audio> {
+    let options = ParseOptions::default();
+    let (rest, elt) = hls_audio(data, &options)?;
+    convert(data, rest, elt, |elt, raw| match elt {
+        HlsElement::Audio(params) => TagRef::new(params, None, raw).to_audio(&options),
+        _ => unreachable!("audio parser returned another element"),
+    })
+}
+
+/// Parse an `#EXT-X-MEDIA:TYPE=VIDEO` tag.
+pub fn parse_video_rendition(data: &str) -> IResult<&str, VideoRendition> {
+    let options = ParseOptions::default();
+    let (rest, elt) = hls_video_rendition(data, &options)?;
+    convert(data, rest, elt, |elt, raw| match elt {
+        HlsElement::VideoRendition(params) => {
+            TagRef::new(params, None, raw).to_video_rendition(&options)
+        }
+        _ => unreachable!("video rendition parser returned another element"),
+    })
+}
+
+/// Parse an `#EXT-X-STREAM-INF` tag, followed by its URI line.
+pub fn parse_stream_info(data: &str) -> IResult<&str, StreamInfo> {
+    let options = ParseOptions::default();
+    let (rest, elt) = hls_stream_info(data, &options)?;
+    convert(data, rest, elt, |elt, raw| match elt {
+        HlsElement::StreamInfo(params, uri) => {
+            TagRef::new(params, Some(uri), raw).to_stream_info(&options)
+        }
+        _ => unreachable!("stream parser returned another element"),
+    })
+}
+
+/// Parse an `#EXT-X-I-FRAME-STREAM-INF` tag.
+pub fn parse_iframe_stream_info(data: &str) -> IResult<&str, IframeStreamInfo> {
+    let options = ParseOptions::default();
+    let (rest, elt) = hls_iframe_stream_info(data, &options)?;
+    convert(data, rest, elt, |elt, raw| match elt {
+        HlsElement::IframeStreamInfo(params) => {
+            TagRef::new(params, None, raw).to_iframe_stream_info(&options)
+        }
+        _ => unreachable!("iframe stream parser returned another element"),
+    })
+}
+
+/// Parse an `#EXT-X-CONTENT-STEERING` tag.
+pub fn parse_content_steering(data: &str) -> IResult<&str, ContentSteering> {
+    let (rest, elt) = hls_content_steering(data, &ParseOptions::default())?;
+    convert(data, rest, elt, |elt, raw| match elt {
+        HlsElement::ContentSteering(params) => TagRef::new(params, None, raw).to_content_steering(),
+        _ => unreachable!("content steering parser returned another element"),
+    })
+}
+
+/// Convert a parsed element with `f`, given the element and its source text,
+/// failing at `data` (the start of the tag) if it doesn't convert.
+fn convert<'a, T>(
+    data: &'a str,
+    rest: &'a str,
+    elt: HlsElement<'a>,
+    f: impl FnOnce(HlsElement<'a>, &'a str) -> Result<T, HlsParseError>,
+) -> IResult<&'a str, T> {
+    match f(elt, consumed(data, rest)) {
+        Ok(value) => Ok((rest, value)),
+        Err(_) => Err(nom::Err::Error(Error::new(data, ErrorKind::MapRes))),
+    }
+}
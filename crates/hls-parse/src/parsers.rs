@@ -5,80 +5,1151 @@
 //! As a rule of thumb, parsers in this module strip extra whitespace
 //! newlines to set up input for subsequent parsers.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use nom::branch::alt;
-use nom::bytes::complete::{take_till, take_until};
-use nom::character::complete::{digit1, newline, not_line_ending, space0};
-use nom::combinator::{all_consuming, eof, map_res, opt};
-use nom::multi::{fold_many1, many1};
+use nom::bytes::complete::{tag_no_case, take_till, take_till1, take_while1};
+use nom::character::complete::{digit1, line_ending, not_line_ending, space0};
+use nom::combinator::{all_consuming, eof, map_res, not, opt, peek, recognize, verify};
+use nom::multi::{fold_many1, many0, many1};
+use nom::sequence::delimited;
 use nom::{IResult, Parser};
 use nom::{bytes::complete::tag, character::complete::multispace0};
 
-use crate::HlsPlaylist;
-use crate::builders::{AudioBuilder, IframeStreamInfoBuilder, StreamInfoBuilder};
+use crate::builders::{
+    DateRangeBuilder, DefinitionBuilder, KeyBuilder, MapBuilder, PartBuilder, PreloadHintBuilder,
+    RenditionReportBuilder, SegmentBuilder, SkipBuilder, value_from_param_str,
+};
 use crate::constants::*;
+use crate::error::{HlsParseError, ParseError, ParseErrorKind, ParseWarning};
+use crate::types::borrowed::{TagRef, UnknownTagRef};
+use crate::types::define::Definition;
+use crate::types::media::{Audio, InstreamId};
+use crate::types::segment::{EncryptionKey, MediaInitialization};
+use crate::types::stream_info::{IframeStreamInfo, StreamInfo};
+use crate::types::unknown::UnknownTag;
+use crate::{HlsPlaylist, HlsPlaylistRef, MediaPlaylist, ParseOptions, Playlist};
 
 type NomStrError<'a> = nom::error::Error<&'a str>;
 
+/// (name, value) pairs of a tag's params in source order, borrowed from the input.
+type Params<'a> = Vec<(&'a str, &'a str)>;
+
 /// Holds possible HLS playlist elements for flexibility in parser return types.
 /// Outside of this module, use types from the `types` module directly instead.
 // OPTIMIZATION: Box contained types to reduce the size of this enum?
 #[derive(Debug)]
-enum HlsElement {
+pub(crate) enum HlsElement<'a> {
     NoData,
-    Audio(AudioBuilder),
-    StreamInfo(StreamInfoBuilder),
-    IframeStreamInfo(IframeStreamInfoBuilder),
+    /// Text of a plain (non-`#EXT`) comment line, without the leading `#`
+    Comment(&'a str),
+    Audio(Params<'a>),
+    VideoRendition(Params<'a>),
+    SubtitleRendition(Params<'a>),
+    /// Params of the tag, and the stream URI on the following line
+    StreamInfo(Params<'a>, &'a str),
+    IframeStreamInfo(Params<'a>),
     Version(usize),
+    IndependentSegments,
+    ContentSteering(Params<'a>),
+    Define(DefinitionBuilder),
+    /// Full line of an `#EXT-X-` tag not otherwise handled by this module
+    UnknownTag(&'a str),
+    /// Full line of an `#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS` tag, kept as an unknown tag,
+    /// and its `INSTREAM-ID` if present
+    ClosedCaptions(&'a str, Option<Result<InstreamId, HlsParseError>>),
+    // Media playlist elements
+    TargetDuration(usize),
+    MediaSequence(usize),
+    /// Approximate segment bitrate in kbit/s, from `#EXT-X-BITRATE`
+    Bitrate(u64),
+    IframesOnly,
+    EndList,
+    /// `#EXT-X-GAP`, marking the next segment as missing
+    Gap,
+    /// Duration and title from `#EXTINF`
+    SegmentInfo(f32, Option<String>),
+    Key(KeyBuilder),
+    DateRange(DateRangeBuilder),
+    Map(MapBuilder),
+    Part(PartBuilder),
+    /// Target duration of partial segments in seconds, from `#EXT-X-PART-INF`
+    PartInf(f32),
+    PreloadHint(PreloadHintBuilder),
+    RenditionReport(RenditionReportBuilder),
+    Skip(SkipBuilder),
+    /// URI line that ends a media segment
+    Uri(String),
 }
 
-impl HlsElement {
-    /// Consumes self, moving it into the HLS playlist matching its variant.
-    fn add_to_playlist(self, playlist: &mut HlsPlaylist) -> anyhow::Result<()> {
+impl<'a> HlsElement<'a> {
+    /// Whether this element is numbered among elements carrying data,
+    /// so that unknown tags can later be serialized in place.
+    fn is_positioned(&self) -> bool {
+        !matches!(self, HlsElement::NoData | HlsElement::Comment(_))
+    }
+
+    /// Consumes self, moving it into the borrowed HLS playlist matching its variant.
+    /// Tag params are kept as borrowed slices, to be converted by `HlsPlaylistRef::into_owned`.
+    /// `position` is the index of this element among all elements carrying data,
+    /// `line` is the line on which the element starts, and `raw` its source text.
+    fn add_to_playlist(
+        self,
+        playlist: &mut HlsPlaylistRef<'a>,
+        single_valued: &mut SingleValuedTags,
+        position: usize,
+        (line, raw): (usize, &'a str),
+    ) -> Result<(), HlsParseError> {
+        let tag = |params, uri| TagRef {
+            params,
+            uri,
+            line,
+            raw,
+        };
         match self {
             HlsElement::NoData => (),
-            HlsElement::Audio(x) => playlist.audio_streams.inner.push(x.build()?),
-            HlsElement::StreamInfo(x) => playlist.streams.inner.push(x.build()?),
-            HlsElement::IframeStreamInfo(x) => playlist.iframe_streams.inner.push(x.build()?),
-            HlsElement::Version(v) => playlist.version = v,
+            HlsElement::Comment(text) => playlist.comments.push(text),
+            HlsElement::Audio(params) => playlist.audio_streams.push(tag(params, None)),
+            HlsElement::VideoRendition(params) => playlist.video_renditions.push(tag(params, None)),
+            HlsElement::SubtitleRendition(params) => playlist.subtitles.push(tag(params, None)),
+            HlsElement::StreamInfo(params, uri) => playlist.streams.push(tag(params, Some(uri))),
+            HlsElement::IframeStreamInfo(params) => playlist.iframe_streams.push(tag(params, None)),
+            HlsElement::Version(v) => {
+                single_valued.check("#EXT-X-VERSION", v.to_string(), line)?;
+                playlist.version = v;
+            }
+            HlsElement::IndependentSegments => playlist.independent_segments = true,
+            HlsElement::ContentSteering(params) => {
+                playlist.content_steering = Some(tag(params, None))
+            }
+            HlsElement::Define(x) => add_definition(&mut playlist.definitions, x.build()?, line)?,
+            HlsElement::ClosedCaptions(tag_line, instream_id) => {
+                instream_id.transpose()?;
+                playlist.unknown_tags.push(UnknownTagRef {
+                    line: tag_line,
+                    position,
+                })
+            }
+            HlsElement::UnknownTag(tag_line) => {
+                if let Some(value) = tag_line.strip_prefix("#EXT-X-START:") {
+                    single_valued.check("#EXT-X-START", value.to_owned(), line)?;
+                }
+                playlist.unknown_tags.push(UnknownTagRef {
+                    line: tag_line,
+                    position,
+                })
+            }
+            HlsElement::TargetDuration(_)
+            | HlsElement::MediaSequence(_)
+            | HlsElement::Bitrate(_)
+            | HlsElement::IframesOnly
+            | HlsElement::EndList
+            | HlsElement::Gap
+            | HlsElement::SegmentInfo(..)
+            | HlsElement::Key(_)
+            | HlsElement::DateRange(_)
+            | HlsElement::Map(_)
+            | HlsElement::Part(_)
+            | HlsElement::PartInf(_)
+            | HlsElement::PreloadHint(_)
+            | HlsElement::RenditionReport(_)
+            | HlsElement::Skip(_)
+            | HlsElement::Uri(_) => {
+                unreachable!("media playlist element passed to master playlist")
+            }
+        }
+        Ok(())
+    }
+
+    /// Consumes self, moving it into the media playlist matching its variant.
+    /// Tags that apply to subsequent segments are held in `state` until a segment URI is reached.
+    fn add_to_media_playlist(
+        self,
+        playlist: &mut MediaPlaylist,
+        state: &mut MediaParseState,
+        line: usize,
+    ) -> Result<(), HlsParseError> {
+        match self {
+            HlsElement::NoData | HlsElement::Comment(_) => (),
+            HlsElement::Version(v) => {
+                state
+                    .single_valued
+                    .check("#EXT-X-VERSION", v.to_string(), line)?;
+                playlist.version = v;
+            }
+            HlsElement::TargetDuration(d) => {
+                state
+                    .single_valued
+                    .check("#EXT-X-TARGETDURATION", d.to_string(), line)?;
+                state.target_duration = Some(d);
+            }
+            HlsElement::MediaSequence(n) => {
+                state
+                    .single_valued
+                    .check("#EXT-X-MEDIA-SEQUENCE", n.to_string(), line)?;
+                playlist.media_sequence = n;
+            }
+            HlsElement::IframesOnly => playlist.iframes_only = true,
+            HlsElement::EndList => playlist.end_list = true,
+            HlsElement::SegmentInfo(duration, title) => {
+                state.segment.duration = Some(duration);
+                state.segment.title = title;
+            }
+            HlsElement::Gap => state.segment.gap = true,
+            HlsElement::Key(x) => match x.build()? {
+                // A key replaces the previous one of its format only, but `METHOD=NONE` clears all
+                Some(key) => match state.keys.iter_mut().find(|k| k.format() == key.format()) {
+                    Some(previous) => *previous = key,
+                    None => state.keys.push(key),
+                },
+                None => state.keys.clear(),
+            },
+            HlsElement::Bitrate(b) => state.bitrate = Some(b),
+            HlsElement::Part(x) => state.segment.parts.push(x.build()?),
+            HlsElement::PartInf(target) => playlist.part_target = Some(target),
+            HlsElement::PreloadHint(x) => playlist.preload_hints.push(x.build()?),
+            HlsElement::RenditionReport(x) => playlist.rendition_reports.push(x.build()?),
+            HlsElement::Skip(x) => playlist.skip = Some(x.build()?),
+            HlsElement::Define(x) => add_definition(&mut playlist.definitions, x.build()?, line)?,
+            HlsElement::DateRange(x) => playlist.date_ranges.push(x.build()?),
+            HlsElement::Map(x) => {
+                let map = x.build()?;
+                playlist.map.get_or_insert_with(|| map.clone());
+                state.map = Some(map);
+            }
+            HlsElement::Uri(uri) => {
+                // Resolve state carried from previous tags into the segment this URI completes
+                let mut segment = std::mem::take(&mut state.segment);
+                segment.uri = Some(uri);
+                segment.keys = state.keys.clone();
+                segment.map = state.map.clone();
+                segment.bitrate = state.bitrate;
+                playlist.segments.push(segment.build()?);
+            }
+            HlsElement::UnknownTag(line) => playlist.unknown_tags.push(UnknownTag {
+                line: line.to_owned(),
+                position: state.position,
+            }),
+            HlsElement::Audio(_)
+            | HlsElement::VideoRendition(_)
+            | HlsElement::SubtitleRendition(_)
+            | HlsElement::ClosedCaptions(..)
+            | HlsElement::StreamInfo(..)
+            | HlsElement::IframeStreamInfo(_)
+            | HlsElement::IndependentSegments
+            | HlsElement::ContentSteering(_) => {
+                unreachable!("master playlist element passed to media playlist")
+            }
         }
         Ok(())
     }
 }
 
+/// Values of tags that may appear at most once in a playlist, keyed by tag name.
+/// Used to detect a repeated tag whose value conflicts with its first occurrence.
+#[derive(Debug, Default)]
+struct SingleValuedTags {
+    /// Whether a conflicting repeat is an error, rather than overriding the earlier value
+    strict: bool,
+    seen: HashMap<&'static str, String>,
+}
+
+impl SingleValuedTags {
+    fn new(options: &ParseOptions) -> Self {
+        Self {
+            strict: options.strict_duplicates,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Record `value` for `tag`, found at `line`. Returns an error if strict and
+    /// `tag` was seen before with a different value.
+    fn check(
+        &mut self,
+        tag: &'static str,
+        value: String,
+        line: usize,
+    ) -> Result<(), HlsParseError> {
+        match self.seen.get(tag) {
+            Some(first) if self.strict && *first != value => Err(HlsParseError::DuplicateTag {
+                tag,
+                first: first.clone(),
+                second: value,
+                line,
+            }),
+            _ => {
+                self.seen.insert(tag, value);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// State carried across elements while parsing a media playlist.
+#[derive(Debug, Default)]
+struct MediaParseState {
+    single_valued: SingleValuedTags,
+    /// Number of elements carrying data so far, to locate unknown tags
+    position: usize,
+    target_duration: Option<usize>,
+    /// Segment tags seen since the last segment URI
+    segment: SegmentBuilder,
+    /// Most recent `#EXT-X-KEY` of each `KEYFORMAT`, which applies to all segments until
+    /// the next key tag of that format, or `METHOD=NONE`
+    keys: Vec<EncryptionKey>,
+    /// Most recent `#EXT-X-MAP`, which applies to all segments until the next map tag
+    map: Option<MediaInitialization>,
+    /// Most recent `#EXT-X-BITRATE`, which applies to all segments until the next bitrate tag
+    bitrate: Option<u64>,
+}
+
+/// Tags that may only appear in a media playlist.
+const MEDIA_PLAYLIST_TAGS: &[&str] = &[
+    "#EXTINF",
+    "#EXT-X-TARGETDURATION",
+    "#EXT-X-MEDIA-SEQUENCE",
+    "#EXT-X-DISCONTINUITY-SEQUENCE",
+    "#EXT-X-DISCONTINUITY",
+    "#EXT-X-ENDLIST",
+    "#EXT-X-PLAYLIST-TYPE",
+    "#EXT-X-I-FRAMES-ONLY",
+    "#EXT-X-BYTERANGE",
+    "#EXT-X-BITRATE",
+    "#EXT-X-KEY",
+    "#EXT-X-MAP",
+    "#EXT-X-PROGRAM-DATE-TIME",
+    "#EXT-X-DATERANGE",
+    "#EXT-X-GAP",
+    "#EXT-X-PART",
+    "#EXT-X-PART-INF",
+    "#EXT-X-SERVER-CONTROL",
+    "#EXT-X-PRELOAD-HINT",
+    "#EXT-X-RENDITION-REPORT",
+    "#EXT-X-SKIP",
+];
+
+/// Tags that may only appear in a master playlist.
+const MASTER_PLAYLIST_TAGS: &[&str] = &[
+    "#EXT-X-MEDIA",
+    "#EXT-X-STREAM-INF",
+    "#EXT-X-I-FRAME-STREAM-INF",
+    "#EXT-X-SESSION-DATA",
+    "#EXT-X-SESSION-KEY",
+    "#EXT-X-CONTENT-STEERING",
+];
+
+/// Tags that may appear in either kind of playlist.
+const SHARED_PLAYLIST_TAGS: &[&str] = &[
+    "#EXTM3U",
+    "#EXT-X-VERSION",
+    "#EXT-X-INDEPENDENT-SEGMENTS",
+    "#EXT-X-START",
+    "#EXT-X-DEFINE",
+];
+
+/// Return an error if `elt`, found on `line`, is a tag unknown to HLS (as far as this crate knows)
+/// and `options.error_on_unknown_tags` is set.
+fn check_known_tag(
+    elt: &HlsElement,
+    line: usize,
+    options: &ParseOptions,
+) -> Result<(), HlsParseError> {
+    if let HlsElement::UnknownTag(tag_line) = elt
+        && options.error_on_unknown_tags
+    {
+        let name = tag_name(tag_line).trim_end();
+        let known = [
+            MASTER_PLAYLIST_TAGS,
+            MEDIA_PLAYLIST_TAGS,
+            SHARED_PLAYLIST_TAGS,
+        ];
+        if !known.iter().any(|tags| tags.contains(&name)) {
+            return Err(HlsParseError::UnknownTag {
+                tag: name.to_owned(),
+                line,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Name of the tag on `line`, e.g. `#EXT-X-VERSION` for `#EXT-X-VERSION:3`.
+pub(crate) fn tag_name(line: &str) -> &str {
+    line.split_once(':').map_or(line, |(name, _)| name)
+}
+
+/// Return the first of `tags` found in `data`, with its 1-based line number.
+fn first_tag<'a>(data: &'a str, tags: &[&str]) -> Option<(&'a str, usize)> {
+    data.lines()
+        .map(|line| tag_name(line).trim_end())
+        .zip(1..)
+        .find(|(name, _)| tags.contains(name))
+}
+
+/// Return an error for the first line of `data` holding one of `tags`,
+/// which don't belong in a playlist of type `playlist_type`.
+fn reject_tags(data: &str, tags: &[&str], playlist_type: &str) -> Result<(), HlsParseError> {
+    match first_tag(data, tags) {
+        Some((name, line)) => Err(HlsParseError::InvalidElement(format!(
+            "{name} at line {line} is not allowed in a {playlist_type} playlist"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Parse a master or media playlist, according to which kind's tags `data` contains.
+/// A playlist with neither (e.g. just a header) is a master playlist.
+/// Returns an error if it contains tags of both kinds.
+pub(crate) fn parse_playlist(
+    data: &str,
+    options: &ParseOptions,
+) -> Result<Playlist, HlsParseError> {
+    match (
+        first_tag(data, MASTER_PLAYLIST_TAGS),
+        first_tag(data, MEDIA_PLAYLIST_TAGS),
+    ) {
+        (Some((master_tag, master_line)), Some((media_tag, media_line))) => {
+            Err(HlsParseError::MixedPlaylist {
+                master_tag: master_tag.to_owned(),
+                master_line,
+                media_tag: media_tag.to_owned(),
+                media_line,
+            })
+        }
+        (None, Some(_)) => Ok(Playlist::Media(parse_media_playlist(data, options)?)),
+        _ => Ok(Playlist::Master(parse_hls_playlist(data, options)?)),
+    }
+}
+
+/// Parse a master playlist, returning an error if it contains media playlist tags.
+pub(crate) fn parse_master_playlist_only(
+    data: &str,
+    options: &ParseOptions,
+) -> Result<HlsPlaylist, HlsParseError> {
+    reject_tags(data, MEDIA_PLAYLIST_TAGS, "master")?;
+    parse_hls_playlist(data, options)
+}
+
+/// Parse a media playlist, returning an error if it contains master playlist tags.
+pub(crate) fn parse_media_playlist_only(
+    data: &str,
+    options: &ParseOptions,
+) -> Result<MediaPlaylist, HlsParseError> {
+    reject_tags(data, MASTER_PLAYLIST_TAGS, "media")?;
+    parse_media_playlist(data, options)
+}
+
 // Parse the entire input stream, incorporating all components into the returned `HlsPlaylist`.
 // Returns an error if any line or component fails to parse.
-pub(crate) fn parse_hls_playlist<'a>(data: &'a str) -> anyhow::Result<HlsPlaylist> {
-    let mut res = HlsPlaylist::default();
+pub(crate) fn parse_hls_playlist(
+    data: &str,
+    options: &ParseOptions,
+) -> Result<HlsPlaylist, HlsParseError> {
+    let substituted = substitute_variables(data, options, |e, _| Err(e))?;
+    let mut res = parse_hls_playlist_ref(&substituted, options)?;
+    if options.keep_raw {
+        restore_raw(&mut res, &substituted, data);
+    }
+    res.into_owned()
+}
 
-    // TODO: split `data` into lines for easier error identification
+/// Point the source text of each tag in `playlist`, parsed from `data` with variables substituted,
+/// at the same text in `source`, as written before substitution.
+/// Substitution never adds or removes lines, so each tag spans the same lines in both.
+fn restore_raw<'a>(playlist: &mut HlsPlaylistRef<'a>, data: &str, source: &'a str) {
+    if data.as_ptr() == source.as_ptr() {
+        return;
+    }
+    // (start, end) byte offsets of each line, without its newline
+    let line_bounds = |s: &str| {
+        let mut start = 0;
+        s.split('\n')
+            .map(|line| {
+                let bounds = (start, start + line.len());
+                start += line.len() + 1;
+                bounds
+            })
+            .collect::<Vec<_>>()
+    };
+    let (data_lines, source_lines) = (line_bounds(data), line_bounds(source));
+    let tags = playlist
+        .audio_streams
+        .iter_mut()
+        .chain(&mut playlist.video_renditions)
+        .chain(&mut playlist.subtitles)
+        .chain(&mut playlist.streams)
+        .chain(&mut playlist.iframe_streams)
+        .chain(&mut playlist.content_steering);
+    for tag in tags {
+        let start = tag.raw.as_ptr() as usize - data.as_ptr() as usize;
+        let end = start + tag.raw.len();
+        let first = data[..start].matches('\n').count();
+        let last = first + tag.raw.matches('\n').count();
+        // Text before the tag on its first line, and after it on its last, isn't substituted
+        tag.raw = &source[source_lines[first].0 + (start - data_lines[first].0)
+            ..source_lines[last].1 - (data_lines[last].1 - end)];
+    }
+}
 
-    // Try using all available parsing functions below, collecting the `HlsElement`s returned by successful parsers.
+// Parse the entire input stream into an `HlsPlaylistRef`, borrowing tag params from `data`.
+// Returns an error if any line fails to parse, or a single-valued tag is repeated.
+pub(crate) fn parse_hls_playlist_ref<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> Result<HlsPlaylistRef<'a>, HlsParseError> {
+    let mut res = HlsPlaylistRef::new(options);
+    if !options.lenient && !has_header(data) {
+        return Err(HlsParseError::MissingHeader);
+    }
+
+    let components = match all_consuming(many1(with_line(data, master_playlist_element(*options))))
+        .parse(playlist_start(data))
+    {
+        Ok((_, components)) => components,
+        Err(e) => return Err(master_syntax_error(data, e)),
+    };
+    add_all_to_playlist(&mut res, components, options)?;
+    Ok(res)
+}
+
+// Parse as many leading elements of `data` as possible into an `HlsPlaylist`,
+// returning the playlist and the input that remains from the first line that isn't an element.
+pub(crate) fn parse_hls_playlist_prefix<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> Result<(HlsPlaylist, &'a str), HlsParseError> {
+    let mut res = HlsPlaylistRef::new(options);
+    if !options.lenient && !has_header(data) {
+        return Err(HlsParseError::MissingHeader);
+    }
+
+    // A later `#EXTM3U` begins another playlist, so ends the prefix
+    let start = playlist_start(data);
+    let start = hls_header(start).map_or(start, |(rest, _)| rest);
+    let element = (not(tag("#EXTM3U")), master_playlist_element(*options)).map(|(_, elt)| elt);
+    let (rest, components) = match many0(with_line(data, element)).parse(start) {
+        Ok(parsed) => parsed,
+        Err(e) => return Err(syntax_error(data, e, ParseErrorKind::UnrecognizedElement)),
+    };
+    // With variables to substitute, parse the prefix again from its substituted text
+    let prefix = &data[..data.len() - rest.len()];
+    if let Cow::Owned(_) = substitute_variables(prefix, options, |e, _| Err(e))? {
+        return Ok((parse_hls_playlist(prefix, options)?, rest));
+    }
+    add_all_to_playlist(&mut res, components, options)?;
+    Ok((res.into_owned()?, rest))
+}
+
+// Add parsed `(line, element)` pairs to `playlist`, in order.
+fn add_all_to_playlist<'a>(
+    playlist: &mut HlsPlaylistRef<'a>,
+    components: Vec<(usize, &'a str, HlsElement<'a>)>,
+    options: &ParseOptions,
+) -> Result<(), HlsParseError> {
+    // Number elements that carry data, so that unknown tags can later be serialized in place
+    let mut position = 0;
+    let mut single_valued = SingleValuedTags::new(options);
+    for (line, raw, elt) in components {
+        check_known_tag(&elt, line, options)?;
+        let positioned = elt.is_positioned();
+        elt.add_to_playlist(playlist, &mut single_valued, position, (line, raw))?;
+        position += usize::from(positioned);
+    }
+    Ok(())
+}
+
+// Parse the entire input stream on a best-effort basis, incorporating all components that parse
+// into the returned `HlsPlaylist`. Lines and components that fail to parse are skipped,
+// each producing a warning.
+pub(crate) fn parse_hls_playlist_lenient(
+    data: &str,
+    options: &ParseOptions,
+) -> (HlsPlaylist, Vec<ParseWarning>) {
+    let mut res = HlsPlaylistRef::new(options);
+    let mut warnings = Vec::new();
+    let substituted = substitute_variables(data, options, |e, line| {
+        warnings.push(ParseWarning {
+            line,
+            message: e.to_string(),
+        });
+        Ok(())
+    })
+    .expect("undefined variables are reported as warnings");
+    let source = data;
+    let data = &*substituted;
+    if !options.lenient && !has_header(data) {
+        warnings.push(ParseWarning {
+            line: line_number(data, playlist_start(data)),
+            message: HlsParseError::MissingHeader.to_string(),
+        });
+    }
+
+    let mut single_valued = SingleValuedTags::new(options);
+    let mut parser = with_line(data, master_playlist_element(*options));
+    let mut rest = playlist_start(data);
+    // Number elements that carry data, so that unknown tags can later be serialized in place
+    let mut position = 0;
+    while !rest.is_empty() {
+        match parser.parse(rest) {
+            Ok((mut next, (line, mut raw, mut elt))) => {
+                // A stream whose params don't all parse falls back to an unknown tag,
+                // so is parsed again without them
+                if let HlsElement::UnknownTag(tag_line) = &elt
+                    && tag_name(tag_line) == "#EXT-X-STREAM-INF"
+                {
+                    match hls_stream_info_skipping_params(rest, options) {
+                        Ok((after, (stream, skipped))) => {
+                            warnings.extend(skipped.into_iter().map(|param| ParseWarning {
+                                line,
+                                message: format!("skipped param {param} of #EXT-X-STREAM-INF"),
+                            }));
+                            (next, raw, elt) = (after, consumed(rest, after), stream);
+                        }
+                        Err(_) => {
+                            // Skip the stream's URI along with its tag, so it isn't reported again
+                            let (skipped, after) = rest.split_once('\n').unwrap_or((rest, ""));
+                            warnings.push(ParseWarning {
+                                line,
+                                message: format!("failed to parse line: {}", skipped.trim_end()),
+                            });
+                            rest = after.trim_start();
+                            let (uri, after_uri) = rest.split_once('\n').unwrap_or((rest, ""));
+                            if is_uri_line(uri.trim_end()) {
+                                rest = after_uri.trim_start();
+                            }
+                            continue;
+                        }
+                    }
+                }
+                rest = next;
+                if let HlsElement::UnknownTag(tag_line) | HlsElement::ClosedCaptions(tag_line, _) =
+                    &elt
+                {
+                    warnings.push(ParseWarning {
+                        line,
+                        message: format!("unknown tag {} is not interpreted", tag_name(tag_line)),
+                    });
+                }
+                let positioned = elt.is_positioned();
+                match elt.add_to_playlist(&mut res, &mut single_valued, position, (line, raw)) {
+                    Ok(()) => position += usize::from(positioned),
+                    Err(e) => warnings.push(ParseWarning {
+                        line,
+                        message: e.to_string(),
+                    }),
+                }
+            }
+            Err(_) => {
+                // Skip the offending line, along with any blank lines following it
+                let (skipped, next) = rest.split_once('\n').unwrap_or((rest, ""));
+                warnings.push(ParseWarning {
+                    line: line_number(data, rest),
+                    message: format!("failed to parse line: {}", skipped.trim_end()),
+                });
+                rest = next.trim_start();
+            }
+        }
+    }
+
+    if options.keep_raw {
+        restore_raw(&mut res, data, source);
+    }
+    // Tags whose params fail to convert are skipped too
+    let res = playlist_from_ref(res, |e, line| {
+        warnings.push(ParseWarning {
+            line,
+            message: e.to_string(),
+        });
+        Ok(())
+    })
+    .expect("conversion errors are reported as warnings");
+    // Conversion warnings are collected per tag type, so restore line order
+    warnings.sort_by_key(|w| w.line);
+
+    (res, warnings)
+}
+
+/// Convert the borrowed playlist into an owned one, passing each tag that fails to convert
+/// to `on_error` along with its line. The tag is skipped if `on_error` returns `Ok`.
+pub(crate) fn playlist_from_ref(
+    playlist: HlsPlaylistRef,
+    mut on_error: impl FnMut(HlsParseError, usize) -> Result<(), HlsParseError>,
+) -> Result<HlsPlaylist, HlsParseError> {
+    let options = &playlist.options;
+    let mut res = HlsPlaylist {
+        version: playlist.version,
+        independent_segments: playlist.independent_segments,
+        definitions: playlist.definitions.clone(),
+        comments: playlist.comments.iter().map(|&c| c.to_owned()).collect(),
+        unknown_tags: playlist
+            .unknown_tags
+            .iter()
+            .map(|t| UnknownTag {
+                line: t.line.to_owned(),
+                position: t.position,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    res.audio_streams.inner = convert_tags(&playlist.audio_streams, &mut on_error, |t| {
+        t.to_audio(options)
+    })?;
+    res.video_renditions.inner = convert_tags(&playlist.video_renditions, &mut on_error, |t| {
+        t.to_video_rendition(options)
+    })?;
+    res.subtitles = convert_tags(&playlist.subtitles, &mut on_error, |t| {
+        t.to_subtitle_rendition(options)
+    })?;
+    res.streams.inner = convert_tags(&playlist.streams, &mut on_error, |t| {
+        t.to_stream_info(options)
+    })?;
+    res.iframe_streams.inner = convert_tags(&playlist.iframe_streams, &mut on_error, |t| {
+        t.to_iframe_stream_info(options)
+    })?;
+    res.content_steering = convert_tags(
+        playlist.content_steering.as_slice(),
+        &mut on_error,
+        TagRef::to_content_steering,
+    )?
+    .pop();
+    Ok(res)
+}
+
+/// Convert each of `tags` using `convert`, keeping those that succeed.
+/// See `playlist_from_ref` for how failures are handled by `on_error`.
+fn convert_tags<'a, T>(
+    tags: &[TagRef<'a>],
+    on_error: &mut impl FnMut(HlsParseError, usize) -> Result<(), HlsParseError>,
+    convert: impl Fn(&TagRef<'a>) -> Result<T, HlsParseError>,
+) -> Result<Vec<T>, HlsParseError> {
+    let mut res = Vec::with_capacity(tags.len());
+    for tag in tags.iter() {
+        match convert(tag) {
+            Ok(x) => res.push(x),
+            Err(e) => on_error(e, tag.line)?,
+        }
+    }
+    Ok(res)
+}
+
+/// Parse `data` as exactly one element, using `parser`.
+fn parse_single_element<'a>(
+    data: &'a str,
+    mut parser: impl Parser<&'a str, Output = HlsElement<'a>, Error = NomStrError<'a>>,
+) -> Result<HlsElement<'a>, HlsParseError> {
+    let (rest, elt) = parser
+        .parse(data)
+        .map_err(|e| syntax_error(data, e, ParseErrorKind::UnrecognizedElement))?;
+    if !rest.is_empty() {
+        return Err(syntax_error(
+            data,
+            nom::Err::Error(NomStrError::new(rest, nom::error::ErrorKind::Eof)),
+            ParseErrorKind::TrailingInput,
+        ));
+    }
+    Ok(elt)
+}
+
+/// Convert a `nom` error from parsing `data` into a `ParseError`,
+/// located at the input that remained when parsing failed.
+fn syntax_error(data: &str, e: nom::Err<NomStrError>, kind: ParseErrorKind) -> HlsParseError {
+    let rest = match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+        nom::Err::Incomplete(_) => "",
+    };
+    let consumed = &data[..data.len() - rest.len()];
+    let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+    let text = rest.lines().next().unwrap_or_default().trim_end();
+    let message = match kind {
+        ParseErrorKind::UnrecognizedElement => format!("unrecognized HLS element: {text}"),
+        ParseErrorKind::TrailingInput => format!("unexpected input after element: {text}"),
+        ParseErrorKind::UnexpectedUri => {
+            format!("unexpected URI line {text:?} without preceding #EXT-X-STREAM-INF")
+        }
+    };
+    HlsParseError::Syntax(ParseError {
+        kind,
+        line: line_number(data, rest),
+        column: consumed[line_start..].chars().count() + 1,
+        message,
+    })
+}
+
+/// Convert a `nom` error from parsing master playlist `data` into a `ParseError`, as `syntax_error` does.
+/// A whole line that failed to parse is reported as a stray URI if it looks like one
+/// (no `#` prefix or whitespace), as only `#EXT-X-STREAM-INF` is followed by a line of its own.
+fn master_syntax_error(data: &str, e: nom::Err<NomStrError>) -> HlsParseError {
+    let kind = match &e {
+        nom::Err::Error(e) | nom::Err::Failure(e)
+            if (e.input.len() == data.len()
+                || data[..data.len() - e.input.len()].ends_with('\n'))
+                && is_uri_line(e.input.lines().next().unwrap_or_default()) =>
+        {
+            ParseErrorKind::UnexpectedUri
+        }
+        _ => ParseErrorKind::UnrecognizedElement,
+    };
+    syntax_error(data, e, kind)
+}
+
+/// Whether `line` looks like a URI: not a tag or comment, and without whitespace.
+fn is_uri_line(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with('#') && !line.contains(char::is_whitespace)
+}
+
+/// Parse a single `#EXT-X-MEDIA:TYPE=AUDIO` line.
+pub(crate) fn parse_audio(data: &str) -> Result<Audio, HlsParseError> {
+    let options = ParseOptions::default();
+    match parse_single_element(data, |d| hls_audio(d, &options))? {
+        HlsElement::Audio(params) => TagRef::new(params, None, data).to_audio(&options),
+        _ => unreachable!("audio parser returned another element"),
+    }
+}
+
+/// Parse a single `#EXT-X-STREAM-INF` line, followed by its URI line.
+pub(crate) fn parse_stream_info(data: &str) -> Result<StreamInfo, HlsParseError> {
+    let options = ParseOptions::default();
+    match parse_single_element(data, |d| hls_stream_info(d, &options))? {
+        HlsElement::StreamInfo(params, uri) => {
+            TagRef::new(params, Some(uri), data).to_stream_info(&options)
+        }
+        _ => unreachable!("stream parser returned another element"),
+    }
+}
+
+/// Parse a single `#EXT-X-I-FRAME-STREAM-INF` line.
+pub(crate) fn parse_iframe_stream_info(data: &str) -> Result<IframeStreamInfo, HlsParseError> {
+    let options = ParseOptions::default();
+    match parse_single_element(data, |d| hls_iframe_stream_info(d, &options))? {
+        HlsElement::IframeStreamInfo(params) => {
+            TagRef::new(params, None, data).to_iframe_stream_info(&options)
+        }
+        _ => unreachable!("iframe stream parser returned another element"),
+    }
+}
+
+/// Return a parser for any single element of a master playlist.
+fn master_playlist_element<'a>(
+    options: ParseOptions,
+) -> impl Parser<&'a str, Output = HlsElement<'a>, Error = NomStrError<'a>> {
+    // Try using all available parsing functions below, returning the `HlsElement` of the successful parser.
     // By design of the parsing functions, at most one will succeed.
-    let components = match all_consuming(many1(alt((
+    alt((
         // Small optimization: roughly ordered by expected frequency (descending)
-        hls_stream_info,
-        hls_iframe_stream_info,
-        hls_audio,
+        move |d| hls_stream_info(d, &options),
+        move |d| hls_iframe_stream_info(d, &options),
+        move |d| hls_media(d, &options),
         hls_version,
         hls_independent_segments,
+        move |d| hls_content_steering(d, &options),
+        move |d| hls_define(d, &options),
         hls_header,
+        // NOTE: must follow all parsers of specific #EXT-X-* tags
+        hls_unknown_tag,
         // NOTE: must be last, as HLS extensions (#EXT-X-*) are technically comments
         hls_comment,
-    ))))
-    .parse(data)
+    ))
+}
+
+// Parse an entire media playlist, incorporating all components into the returned `MediaPlaylist`.
+// Returns an error if any line or component fails to parse.
+pub(crate) fn parse_media_playlist(
+    data: &str,
+    options: &ParseOptions,
+) -> Result<MediaPlaylist, HlsParseError> {
+    let mut res = MediaPlaylist::default();
+    let substituted = substitute_variables(data, options, |e, _| Err(e))?;
+    let data = &*substituted;
+
+    // Try using all available parsing functions below, collecting the `HlsElement`s returned by successful parsers.
+    // By design of the parsing functions, at most one will succeed.
+    let components = match all_consuming(many1(with_line(
+        data,
+        alt((
+            // Small optimization: roughly ordered by expected frequency (descending)
+            hls_uri,
+            hls_segment_info,
+            |d| hls_key(d, options),
+            |d| hls_date_range(d, options),
+            |d| hls_map(d, options),
+            |d| hls_part(d, options),
+            |d| hls_part_inf(d, options),
+            |d| hls_preload_hint(d, options),
+            |d| hls_rendition_report(d, options),
+            |d| hls_skip(d, options),
+            hls_target_duration,
+            hls_media_sequence,
+            hls_bitrate,
+            hls_end_list,
+            hls_gap,
+            hls_iframes_only,
+            |d| hls_define(d, options),
+            hls_version,
+            hls_header,
+            // NOTE: must follow all parsers of specific #EXT-X-* tags
+            hls_unknown_tag,
+            // NOTE: must be last, as HLS extensions (#EXT-X-*) are technically comments
+            hls_comment,
+        )),
+    )))
+    .parse(playlist_start(data))
     {
         Ok((_, components)) => components,
-        Err(e) => anyhow::bail!("{e}"),
+        Err(e) => return Err(syntax_error(data, e, ParseErrorKind::UnrecognizedElement)),
     };
-    for elt in components {
-        elt.add_to_playlist(&mut res)?;
+
+    let mut state = MediaParseState {
+        single_valued: SingleValuedTags::new(options),
+        ..Default::default()
+    };
+    for (line, _, elt) in components {
+        check_known_tag(&elt, line, options)?;
+        if res.end_list && !options.lenient && matches!(elt, HlsElement::Uri(_)) {
+            return Err(HlsParseError::SegmentAfterEndList { line });
+        }
+        let positioned = elt.is_positioned();
+        elt.add_to_media_playlist(&mut res, &mut state, line)?;
+        state.position += usize::from(positioned);
+    }
+    if state.segment.duration.is_some() {
+        return Err(HlsParseError::InvalidElement(
+            "#EXTINF not followed by a segment URI".to_owned(),
+        ));
     }
+    res.trailing_parts = state.segment.parts;
+    res.target_duration = state
+        .target_duration
+        .ok_or(HlsParseError::MissingField("#EXT-X-TARGETDURATION"))?;
 
     Ok(res)
 }
 
+/// Append `definition`, declared at `line`, to `definitions`, unless a variable of the same name
+/// is already declared.
+fn add_definition(
+    definitions: &mut Vec<Definition>,
+    definition: Definition,
+    line: usize,
+) -> Result<(), HlsParseError> {
+    if definitions.iter().any(|d| d.name() == definition.name()) {
+        return Err(HlsParseError::DuplicateVariable {
+            name: definition.name().to_owned(),
+            line,
+        });
+    }
+    definitions.push(definition);
+    Ok(())
+}
+
+/// Substitute variable references (`{$name}`) in `data` with the values declared by preceding
+/// `#EXT-X-DEFINE` tags, unless `options.keep_variable_references` is set.
+/// As the HLS spec allows, only quoted-string and hexadecimal attribute values and URI lines
+/// are substituted, so a value can't change a tag's name, enumerated values or attribute list.
+/// References to variables declared by `IMPORT` or `QUERYPARAM` are left as they are,
+/// as their values come from outside the playlist. A reference to an undeclared variable,
+/// or to one whose value isn't hexadecimal within a hexadecimal value, is passed to `on_error`
+/// along with its line, and left as it is if `on_error` returns `Ok`.
+fn substitute_variables<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+    mut on_error: impl FnMut(HlsParseError, usize) -> Result<(), HlsParseError>,
+) -> Result<Cow<'a, str>, HlsParseError> {
+    if options.keep_variable_references || !data.contains("{$") {
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let mut definitions = HashMap::new();
+    let mut res = String::with_capacity(data.len());
+    for (i, line) in data.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_start();
+        // Definitions take effect from the next line; plain comments are left alone
+        if tag_name(trimmed).trim_end() == "#EXT-X-DEFINE" {
+            // Malformed definitions are reported when the playlist is parsed
+            // A repeated name is an error when the playlist is parsed, so the first one is kept
+            if let Ok((_, HlsElement::Define(builder))) = hls_define(trimmed, options)
+                && let Ok(definition) = builder.build()
+            {
+                definitions
+                    .entry(definition.name().to_owned())
+                    .or_insert(definition);
+            }
+            res.push_str(line);
+            continue;
+        }
+        if trimmed.starts_with('#') && !trimmed.starts_with("#EXT") {
+            res.push_str(line);
+            continue;
+        }
+
+        let mut substitute = |text: &str, hex: bool, res: &mut String| {
+            substitute_references(text, hex, &definitions, i + 1, &mut on_error, res)
+        };
+        let Some((name, attributes)) = trimmed.split_once(':').filter(|_| trimmed.starts_with('#'))
+        else {
+            if trimmed.starts_with('#') {
+                res.push_str(line);
+            } else {
+                substitute(line, false, &mut res)?;
+            }
+            continue;
+        };
+
+        // Tokenize the attribute list, so that only attribute values are substituted
+        res.push_str(&line[..line.len() - trimmed.len()]);
+        res.push_str(name);
+        res.push(':');
+        let mut rest = attributes;
+        while !rest.is_empty() {
+            // Text before a `,` without a `=`, e.g. an `#EXTINF` duration, isn't an attribute
+            let Some(equals) = rest
+                .find([',', '='])
+                .filter(|&i| rest[i..].starts_with('='))
+            else {
+                let end = rest.find(',').map_or(rest.len(), |i| i + 1);
+                res.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            };
+            res.push_str(&rest[..=equals]);
+            rest = &rest[equals + 1..];
+            let value_len = match rest.strip_prefix('"') {
+                // Up to and including the closing quote, if any
+                Some(quoted) => match quoted.find(['"', '\r', '\n']) {
+                    Some(i) if quoted[i..].starts_with('"') => i + 2,
+                    Some(i) => i + 1,
+                    None => rest.len(),
+                },
+                None => rest.find([',', '\r', '\n']).unwrap_or(rest.len()),
+            };
+            let (value, after) = rest.split_at(value_len);
+            if let Some(quoted) = value.strip_prefix('"') {
+                res.push('"');
+                let (text, close) = quoted.split_at(quoted.trim_end_matches('"').len());
+                substitute(text, false, &mut res)?;
+                res.push_str(close);
+            } else if value.starts_with("0x") || value.starts_with("0X") {
+                substitute(value, true, &mut res)?;
+            } else {
+                res.push_str(value);
+            }
+            let end = after.find(',').map_or(after.len(), |i| i + 1);
+            res.push_str(&after[..end]);
+            rest = &after[end..];
+        }
+    }
+    Ok(Cow::Owned(res))
+}
+
+/// Append `text`, found on the 1-based `line`, to `res`, substituting its variable references with
+/// the values in `definitions`, as `substitute_variables` does. If `hex` is set, `text` is
+/// a hexadecimal value, so only hexadecimal values are substituted into it.
+fn substitute_references(
+    text: &str,
+    hex: bool,
+    definitions: &HashMap<String, Definition>,
+    line: usize,
+    on_error: &mut impl FnMut(HlsParseError, usize) -> Result<(), HlsParseError>,
+    res: &mut String,
+) -> Result<(), HlsParseError> {
+    let mut rest = text;
+    while let Some(start) = rest.find("{$") {
+        res.push_str(&rest[..start]);
+        let reference = &rest[start..];
+        let name = reference[2..].split_once('}').map(|(name, _)| name);
+        let Some(name) = name.filter(|name| is_variable_name(name)) else {
+            res.push_str("{$");
+            rest = &reference[2..];
+            continue;
+        };
+        let reference_len = name.len() + 3;
+        match definitions.get(name) {
+            Some(Definition::Value { value, .. })
+                if !hex || value.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                res.push_str(value)
+            }
+            Some(Definition::Value { value, .. }) => {
+                let error = HlsParseError::InvalidElement(format!(
+                    "variable {name:?} referenced in a hexadecimal value at line {line} isn't hexadecimal: {value:?}"
+                ));
+                on_error(error, line)?;
+                res.push_str(&reference[..reference_len]);
+            }
+            Some(_) => res.push_str(&reference[..reference_len]),
+            None => {
+                let error = HlsParseError::UndefinedVariable {
+                    name: name.to_owned(),
+                    line,
+                };
+                on_error(error, line)?;
+                res.push_str(&reference[..reference_len]);
+            }
+        }
+        rest = &reference[reference_len..];
+    }
+    res.push_str(rest);
+    Ok(())
+}
+
+/// Whether `name` is a valid variable name: non-empty, of letters, digits, `-` and `_`.
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Skip a leading UTF-8 byte order mark and whitespace (e.g. blank lines) that some servers
+/// prepend to playlists. Line numbers are still counted from the start of `data`.
+fn playlist_start(data: &str) -> &str {
+    data.strip_prefix('\u{feff}').unwrap_or(data).trim_start()
+}
+
+/// Whether the first line of `data`, after any preamble, is the `#EXTM3U` header.
+fn has_header(data: &str) -> bool {
+    playlist_start(data)
+        .lines()
+        .next()
+        .is_some_and(|line| line.trim_end() == "#EXTM3U")
+}
+
+/// Wrap `parser` so that it also returns the 1-based line of `data` on which its element starts,
+/// and the element's source text.
+/// `data` must be the full input that `parser` is (eventually) applied to.
+fn with_line<'a>(
+    data: &'a str,
+    mut parser: impl Parser<&'a str, Output = HlsElement<'a>, Error = NomStrError<'a>>,
+) -> impl Parser<&'a str, Output = (usize, &'a str, HlsElement<'a>), Error = NomStrError<'a>> {
+    // Offset into `data` and line number of the previous input, so that only the newlines
+    // consumed since are counted, rather than rescanning `data` for every element
+    let mut last = (0, 1);
+    move |input: &'a str| {
+        let offset = data.len() - input.len();
+        let line = match last {
+            (last_offset, last_line) if last_offset <= offset => {
+                last_line + data[last_offset..offset].matches('\n').count()
+            }
+            _ => line_number(data, input),
+        };
+        last = (offset, line);
+        let (rest, elt) = parser.parse(input)?;
+        Ok((rest, (line, consumed(input, rest), elt)))
+    }
+}
+
+/// The text a parser consumed from `input`, leaving `rest`, without trailing newlines.
+pub(crate) fn consumed<'a>(input: &'a str, rest: &str) -> &'a str {
+    input[..input.len() - rest.len()].trim_end()
+}
+
+/// The 1-based line of `data` on which `rest`, a suffix of `data`, starts.
+fn line_number(data: &str, rest: &str) -> usize {
+    let consumed = &data[..data.len() - rest.len()];
+    consumed.matches('\n').count() + 1
+}
+
 /// Return a function that can be used to parse the `#EXT-X-` prefix of a line in the HLS playlist.
 /// Does perform any parsing - solely meant for composition with other parsers.
 // NOTE: This impl is constrained to &str but could be made generic.
@@ -88,16 +1159,25 @@ fn extension_prefix<'a>() -> impl Parser<&'a str, Error = NomStrError<'a>> {
 
 /// Parse an HLS comment. Anything that starts with `#`.
 /// **Try other `hls_*` functions first**, as this matches on `#EXT-X-*` lines.
-fn hls_comment<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
-    map_res((tag("#"), not_line_ending, newline), |_| {
-        Ok::<_, NomStrError<'a>>(HlsElement::NoData)
-    })
+/// Returns `HlsElement::Comment` for plain comments, and `HlsElement::NoData` for `#EXT` lines.
+fn hls_comment<'a>(data: &'a str) -> IResult<&'a str, HlsElement<'a>> {
+    // Final line of the input may lack a line ending
+    map_res(
+        (tag("#"), not_line_ending, alt((line_ending, eof))),
+        |(_, text, _): (_, &str, _)| {
+            Ok::<_, NomStrError<'a>>(if text.starts_with("EXT") {
+                HlsElement::NoData
+            } else {
+                HlsElement::Comment(text)
+            })
+        },
+    )
     .parse(data)
 }
 
 /// Parse a `#EXTM3U` header.
 /// Returns `HlsElement::NoData` on success. Modifies the input to move past the tag.
-fn hls_header<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
+fn hls_header(data: &str) -> IResult<&str, HlsElement<'_>> {
     // Toss parser results, converting to `HlsElement::NoData` instead.
     map_res((tag("#EXTM3U"), multispace0), |_| {
         Ok::<_, NomStrError>(HlsElement::NoData)
@@ -106,10 +1186,9 @@ fn hls_header<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
 }
 
 /// Parse an HLS independent segments param from the given string.
-/// Returns `HlsElement::NoData` on success. Modifies the input to "move past" the tag.
-// TODO: return and store this parameter?
-fn hls_independent_segments<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
-    // Toss parser results, converting to `HlsElement::NoData` instead.
+/// Returns `HlsElement::IndependentSegments` on success. Modifies the input to "move past" the tag.
+fn hls_independent_segments(data: &str) -> IResult<&str, HlsElement<'_>> {
+    // Toss parser results, converting to `HlsElement::IndependentSegments` instead.
     map_res(
         (
             // Parse #EXT-X-INDEPENDENT-SEGMENTS
@@ -118,13 +1197,27 @@ fn hls_independent_segments<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
             // Clear subsequent whitespace/newlines/eof
             multispace0,
         ),
-        |_| Ok::<_, NomStrError>(HlsElement::NoData),
+        |_| Ok::<_, NomStrError>(HlsElement::IndependentSegments),
+    )
+    .parse(data)
+}
+
+/// Parse any `#EXT-X-` tag, returning the full line verbatim as `HlsElement::UnknownTag`.
+/// **Try other `hls_*` functions first**, as this matches on every extension tag.
+fn hls_unknown_tag(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            recognize((extension_prefix(), not_line_ending)),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(line, _): (&str, _)| Ok::<_, NomStrError>(HlsElement::UnknownTag(line)),
     )
     .parse(data)
 }
 
 /// Parse an HLS `#EXT-X-VERSION` param, returning the value as a `str` to be parsed to int later.
-fn hls_version<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
+fn hls_version(data: &str) -> IResult<&str, HlsElement<'_>> {
     // Toss parser results, converting to `HlsElement::NoData` instead.
     map_res(
         (
@@ -140,158 +1233,810 @@ fn hls_version<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
     .parse(data)
 }
 
-/// Parse HLS audio media (starts with #EXT-X-MEDIA, contains TYPE=AUDIO param).
-/// Return a `HlsElement::Audio` that represents the parsed data.
-// TODO: support subtitle variants
-fn hls_audio<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
-    // Parse the beginning of an audio stream tag
-    let (rest, _) = (
-        extension_prefix(),
-        tag("MEDIA:"),
+/// Params of `#EXT-X-MEDIA` tags of every `TYPE`.
+const MEDIA_COMMON_PARAMS: &[(&str, ParamEnclose)] = &[
+    // TODO: repr GROUP-ID with enum given known-good set
+    (P_GROUP_ID, ParamEnclose::DoubleQuotes),
+    (P_NAME, ParamEnclose::DoubleQuotes),
+    (P_LANGUAGE, ParamEnclose::DoubleQuotes),
+    (P_DEFAULT, ParamEnclose::None),
+    (P_AUTOSELECT, ParamEnclose::None),
+    (P_STABLE_RENDITION_ID, ParamEnclose::DoubleQuotes),
+    (P_URI, ParamEnclose::DoubleQuotes),
+];
+
+/// Params of `#EXT-X-MEDIA:TYPE=AUDIO` tags, besides `MEDIA_COMMON_PARAMS`.
+const MEDIA_AUDIO_PARAMS: &[(&str, ParamEnclose)] = &[
+    (P_ASSOC_LANGUAGE, ParamEnclose::DoubleQuotes),
+    (P_CHANNELS, ParamEnclose::DoubleQuotes),
+    (P_CHARACTERISTICS, ParamEnclose::DoubleQuotes),
+    (P_SAMPLE_RATE, ParamEnclose::None),
+    (P_BIT_DEPTH, ParamEnclose::None),
+];
+
+/// Params of `#EXT-X-MEDIA:TYPE=SUBTITLES` tags, besides `MEDIA_COMMON_PARAMS`.
+const MEDIA_SUBTITLES_PARAMS: &[(&str, ParamEnclose)] = &[
+    (P_ASSOC_LANGUAGE, ParamEnclose::DoubleQuotes),
+    (P_CHARACTERISTICS, ParamEnclose::DoubleQuotes),
+    (P_FORCED, ParamEnclose::None),
+];
+
+/// Params of `#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS` tags, besides `MEDIA_COMMON_PARAMS`.
+const MEDIA_CLOSED_CAPTIONS_PARAMS: &[(&str, ParamEnclose)] = &[
+    (P_ASSOC_LANGUAGE, ParamEnclose::DoubleQuotes),
+    (P_CHARACTERISTICS, ParamEnclose::DoubleQuotes),
+    (P_INSTREAM_ID, ParamEnclose::DoubleQuotes),
+];
+
+/// Parse an HLS `#EXT-X-MEDIA` tag of any `TYPE`, reading `TYPE` (which must come first)
+/// to choose the params allowed besides those shared by all types.
+/// Return a `HlsElement::Audio`, `HlsElement::VideoRendition` or `HlsElement::SubtitleRendition`
+/// that represents the parsed data. Closed captions aren't modeled yet, so are returned as
+/// `HlsElement::ClosedCaptions` with their `INSTREAM-ID` converted.
+pub(crate) fn hls_media<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    let (rest, media_type) = hls_media_type(data, options)?;
+    let type_params = match media_type {
+        "AUDIO" => MEDIA_AUDIO_PARAMS,
+        "SUBTITLES" => MEDIA_SUBTITLES_PARAMS,
+        "CLOSED-CAPTIONS" => MEDIA_CLOSED_CAPTIONS_PARAMS,
+        _ => &[],
+    };
+
+    // Try parsers of the common and type-specific params, collecting the (name, value) pair of each.
+    // Some params are enclosed by quotes, which are stripped from the value.
+    let (rest, params) = many1(any_param(
+        MEDIA_COMMON_PARAMS.iter().chain(type_params),
+        options,
+    ))
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    let elt = match media_type {
+        "AUDIO" => HlsElement::Audio(params),
+        "VIDEO" => HlsElement::VideoRendition(params),
+        "SUBTITLES" => HlsElement::SubtitleRendition(params),
+        "CLOSED-CAPTIONS" => {
+            let instream_id = params
+                .iter()
+                .find(|(name, _)| *name == P_INSTREAM_ID)
+                .map(|(_, value)| value_from_param_str(P_INSTREAM_ID, value));
+            HlsElement::ClosedCaptions(not_line_ending(data)?.1, instream_id)
+        }
+        _ => unreachable!("unhandled media type {media_type}"),
+    };
+    Ok((rest, elt))
+}
+
+/// Parse the beginning of an HLS `#EXT-X-MEDIA` tag, up to and including its `TYPE` param
+/// (which must come first), returning the type.
+fn hls_media_type<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, &'a str> {
+    delimited(
+        (
+            extension_prefix(),
+            tag("MEDIA:"),
+            space0,
+            param_name(P_TYPE, options),
+            tag("="),
+        ),
+        alt((
+            tag("AUDIO"),
+            tag("VIDEO"),
+            tag("SUBTITLES"),
+            tag("CLOSED-CAPTIONS"),
+        )),
+        (space0, tag(",")),
+    )
+    .parse(data)
+}
+
+/// `INSTREAM-ID` of an `#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS` tag, given its full line, if valid.
+pub(crate) fn closed_captions_instream_id(line: &str) -> Option<InstreamId> {
+    match hls_media(line, &ParseOptions::default()) {
+        Ok((_, HlsElement::ClosedCaptions(_, Some(Ok(id))))) => Some(id),
+        _ => None,
+    }
+}
+
+/// Parse an HLS `#EXT-X-MEDIA:TYPE=AUDIO` tag, as `hls_media` does, failing on other types.
+pub(crate) fn hls_audio<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    verify(
+        |d| hls_media(d, options),
+        |elt: &HlsElement| matches!(elt, HlsElement::Audio(_)),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-MEDIA:TYPE=VIDEO` tag, as `hls_media` does, failing on other types.
+pub(crate) fn hls_video_rendition<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    verify(
+        |d| hls_media(d, options),
+        |elt: &HlsElement| matches!(elt, HlsElement::VideoRendition(_)),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS stream (starts with #EXT-X-STREAM-INF).
+/// Return a `HlsElement::StreamInfo` that represents the parsed data.
+pub(crate) fn hls_stream_info<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a video stream tag
+    let (rest, _) = (extension_prefix(), tag("STREAM-INF:"), space0).parse(data)?;
+
+    // Try parsers of the stream params, collecting the (name, value) pair of each.
+    // Some params are enclosed by quotes, which are stripped from the value.
+    let (rest, params) = many1(any_param(STREAM_INFO_PARAMS.iter(), options)).parse(rest)?;
+    let (rest, uri) = stream_uri(options).parse(rest)?;
+
+    Ok((rest, HlsElement::StreamInfo(params, uri)))
+}
+
+/// Parse an HLS video stream as `hls_stream_info` does, but skip params that fail to parse,
+/// e.g. unknown ones, rather than failing. Returns the stream along with the source text
+/// (`NAME=value`) of each skipped param, for parsing leniently.
+fn hls_stream_info_skipping_params<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, (HlsElement<'a>, Vec<&'a str>)> {
+    let (rest, _) = (extension_prefix(), tag("STREAM-INF:"), space0).parse(data)?;
+
+    let lenient = options.lenient;
+    let skipped_param = (
+        recognize((
+            take_till1(|c: char| c == '=' || c == ',' || c.is_whitespace()),
+            tag("="),
+            alt((recognize(param_value_double_quoted), move |d| {
+                param_value_no_enclosure(d, lenient)
+            })),
+        )),
         space0,
-        tag("TYPE=AUDIO"),
+        param_terminator(lenient),
+    )
+        .map(|(param, _, _)| Err(param));
+    let (rest, params) = many1(alt((
+        any_param(STREAM_INFO_PARAMS.iter(), options).map(Ok),
+        skipped_param,
+    )))
+    .parse(rest)?;
+    let (rest, uri) = stream_uri(options).parse(rest)?;
+
+    let (params, skipped): (Vec<_>, Vec<_>) = params.into_iter().partition(Result::is_ok);
+    let params = params.into_iter().flatten().collect();
+    let skipped = skipped.into_iter().filter_map(Result::err).collect();
+    Ok((rest, (HlsElement::StreamInfo(params, uri), skipped)))
+}
+
+/// Params of `#EXT-X-STREAM-INF` tags.
+const STREAM_INFO_PARAMS: &[(&str, ParamEnclose)] = &[
+    (P_BANDWIDTH, ParamEnclose::None),
+    (P_AVERAGE_BANDWIDTH, ParamEnclose::None),
+    (P_CODECS, ParamEnclose::DoubleQuotes),
+    (P_SUPPLEMENTAL_CODECS, ParamEnclose::DoubleQuotes),
+    (P_RESOLUTION, ParamEnclose::None),
+    (P_FRAME_RATE, ParamEnclose::None),
+    (P_VIDEO_RANGE, ParamEnclose::None),
+    (P_AUDIO, ParamEnclose::DoubleQuotes),
+    (P_VIDEO, ParamEnclose::DoubleQuotes),
+    (P_CLOSED_CAPTIONS, ParamEnclose::None),
+    (P_PATHWAY_ID, ParamEnclose::DoubleQuotes),
+    (P_SCORE, ParamEnclose::None),
+    (P_STABLE_VARIANT_ID, ParamEnclose::DoubleQuotes),
+    (P_ALLOWED_CPC, ParamEnclose::DoubleQuotes),
+    (P_PROGRAM_ID, ParamEnclose::None),
+];
+
+/// Return a parser for the end of an `#EXT-X-STREAM-INF` tag: the resource URI expected on
+/// the next line, then newlines or end of input. Line endings may be LF or CRLF.
+fn stream_uri<'a>(
+    options: &ParseOptions,
+) -> impl Parser<&'a str, Output = &'a str, Error = NomStrError<'a>> {
+    (
+        trailing_comment(options),
         space0,
-        tag(","),
+        line_ending,
+        not_line_ending,
+        alt((multispace0, eof)),
+    )
+        .map(|tuple: (_, _, _, &str, _)| tuple.3.trim_end())
+}
+
+/// Parse an HLS iframe stream (starts with #EXT-X-I-FRAME-STREAM-INF).
+/// Return a `HlsElement::IframeStreamInfo` that represents the parsed data.
+pub(crate) fn hls_iframe_stream_info<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of an ifram video stream tag
+    let (rest, _) = (extension_prefix(), tag("I-FRAME-STREAM-INF:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, collecting the (name, value) pair of each.
+    // Some params are enclosed by quotes, which are stripped from the value.
+    let (rest, params) = many1(alt((
+        comma_terminated_param(P_BANDWIDTH, ParamEnclose::None, options),
+        comma_terminated_param(P_AVERAGE_BANDWIDTH, ParamEnclose::None, options),
+        comma_terminated_param(P_CODECS, ParamEnclose::DoubleQuotes, options),
+        comma_terminated_param(P_SUPPLEMENTAL_CODECS, ParamEnclose::DoubleQuotes, options),
+        comma_terminated_param(P_RESOLUTION, ParamEnclose::None, options),
+        comma_terminated_param(P_VIDEO_RANGE, ParamEnclose::None, options),
+        comma_terminated_param(P_STABLE_VARIANT_ID, ParamEnclose::DoubleQuotes, options),
+        comma_terminated_param(P_ALLOWED_CPC, ParamEnclose::DoubleQuotes, options),
+        comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+    )))
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::IframeStreamInfo(params)))
+}
+
+/// Parse an HLS `#EXT-X-CONTENT-STEERING` tag.
+/// Return a `HlsElement::ContentSteering` that represents the parsed data.
+pub(crate) fn hls_content_steering<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a content steering tag
+    let (rest, _) = (extension_prefix(), tag("CONTENT-STEERING:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, collecting the (name, value) pair of each.
+    let (rest, params) = many1(alt((
+        comma_terminated_param(P_SERVER_URI, ParamEnclose::DoubleQuotes, options),
+        comma_terminated_param(P_PATHWAY_ID, ParamEnclose::DoubleQuotes, options),
+    )))
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::ContentSteering(params)))
+}
+
+/// Parse an HLS `#EXT-X-DEFINE` tag, which may appear in master and media playlists.
+/// Return a `HlsElement::Define` that represents the parsed data.
+fn hls_define<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a define tag
+    let (rest, _) = (extension_prefix(), tag("DEFINE:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
+    let (rest, builder) = fold_many1(
+        alt((
+            comma_terminated_param(P_NAME, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_VALUE, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_IMPORT, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_QUERYPARAM, ParamEnclose::DoubleQuotes, options),
+        )),
+        DefinitionBuilder::default,
+        |builder, param_tuple| builder.incorporate(param_tuple),
+    )
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::Define(builder)))
+}
+
+// ---------- Functions for parsing media playlist tags ----------
+
+/// Parse an HLS `#EXT-X-TARGETDURATION` tag, returning the value in seconds.
+fn hls_target_duration(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            // Parse #EXT-X-TARGETDURATION:<num>
+            extension_prefix(),
+            tag("TARGETDURATION:"),
+            map_res(digit1, usize::from_str),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(_, _, d, _)| Ok::<_, NomStrError>(HlsElement::TargetDuration(d)),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-BITRATE` tag, returning the approximate segment bitrate in kbit/s.
+fn hls_bitrate(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            // Parse #EXT-X-BITRATE:<kbps>
+            extension_prefix(),
+            tag("BITRATE:"),
+            map_res(digit1, u64::from_str),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(_, _, b, _)| Ok::<_, NomStrError>(HlsElement::Bitrate(b)),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-MEDIA-SEQUENCE` tag, returning the sequence number of the first segment.
+fn hls_media_sequence(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            // Parse #EXT-X-MEDIA-SEQUENCE:<num>
+            extension_prefix(),
+            tag("MEDIA-SEQUENCE:"),
+            map_res(digit1, usize::from_str),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(_, _, n, _)| Ok::<_, NomStrError>(HlsElement::MediaSequence(n)),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-I-FRAMES-ONLY` tag.
+/// Returns `HlsElement::IframesOnly` on success. Modifies the input to "move past" the tag.
+fn hls_iframes_only(data: &str) -> IResult<&str, HlsElement<'_>> {
+    // Toss parser results, converting to `HlsElement::IframesOnly` instead.
+    map_res(
+        (
+            // Parse #EXT-X-I-FRAMES-ONLY
+            extension_prefix(),
+            tag("I-FRAMES-ONLY"),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |_| Ok::<_, NomStrError>(HlsElement::IframesOnly),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-ENDLIST` tag.
+/// Returns `HlsElement::EndList` on success. Modifies the input to "move past" the tag.
+fn hls_end_list(data: &str) -> IResult<&str, HlsElement<'_>> {
+    // Toss parser results, converting to `HlsElement::EndList` instead.
+    map_res(
+        (
+            // Parse #EXT-X-ENDLIST
+            extension_prefix(),
+            tag("ENDLIST"),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |_| Ok::<_, NomStrError>(HlsElement::EndList),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-GAP` tag.
+/// Returns `HlsElement::Gap` on success. Modifies the input to "move past" the tag.
+fn hls_gap(data: &str) -> IResult<&str, HlsElement<'_>> {
+    // Toss parser results, converting to `HlsElement::Gap` instead.
+    map_res(
+        (
+            // Parse #EXT-X-GAP
+            extension_prefix(),
+            tag("GAP"),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |_| Ok::<_, NomStrError>(HlsElement::Gap),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXTINF:<duration>,[<title>]` tag, which describes the next media segment.
+fn hls_segment_info(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            tag("#EXTINF:"),
+            map_res(
+                take_till(|c: char| c == ',' || c.is_whitespace()),
+                f32::from_str,
+            ),
+            // Title is optional, as is the comma preceding it
+            opt((tag(","), not_line_ending)),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(_, duration, title, _)| {
+            let title = title
+                .map(|(_, t): (_, &str)| t.trim())
+                .filter(|t| !t.is_empty())
+                .map(str::to_owned);
+            Ok::<_, NomStrError>(HlsElement::SegmentInfo(duration, title))
+        },
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-KEY` tag, which applies to all subsequent segments until the next key tag.
+/// Return a `HlsElement::Key` that represents the parsed data.
+fn hls_key<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a key tag
+    let (rest, _) = (extension_prefix(), tag("KEY:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
+    let (rest, builder) = fold_many1(
+        alt((
+            comma_terminated_param(P_METHOD, ParamEnclose::None, options),
+            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_IV, ParamEnclose::None, options),
+            comma_terminated_param(P_KEYFORMATVERSIONS, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_KEYFORMAT, ParamEnclose::DoubleQuotes, options),
+        )),
+        KeyBuilder::default,
+        |builder, param_tuple| builder.incorporate(param_tuple),
+    )
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::Key(builder)))
+}
+
+/// Parse an HLS `#EXT-X-MAP` tag, which applies to all subsequent segments until the next map tag.
+/// Return a `HlsElement::Map` that represents the parsed data.
+fn hls_map<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a map tag
+    let (rest, _) = (extension_prefix(), tag("MAP:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
+    let (rest, builder) = fold_many1(
+        alt((
+            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_BYTERANGE, ParamEnclose::DoubleQuotes, options),
+        )),
+        MapBuilder::default,
+        |builder, param_tuple| builder.incorporate(param_tuple),
     )
-        .parse(data)?;
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::Map(builder)))
+}
+
+/// Parse an HLS `#EXT-X-PART` tag, i.e. a partial segment.
+/// Return a `HlsElement::Part` that represents the parsed data.
+fn hls_part<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a part tag
+    let (rest, _) = (extension_prefix(), tag("PART:"), space0).parse(data)?;
 
     // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
     // Some params are enclosed by quotes and/or need conversion from the returned str value into another type.
     let (rest, builder) = fold_many1(
         alt((
-            // TODO: repr GROUP-ID with enum given known-good set
-            comma_terminated_param(P_GROUP_ID, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_NAME, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_LANGUAGE, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_DEFAULT, ParamEnclose::None),
-            comma_terminated_param(P_AUTOSELECT, ParamEnclose::None),
-            comma_terminated_param(P_CHANNELS, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes),
+            comma_terminated_param(P_DURATION, ParamEnclose::None, options),
+            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_INDEPENDENT, ParamEnclose::None, options),
+            comma_terminated_param(P_BYTERANGE, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_GAP, ParamEnclose::None, options),
         )),
-        AudioBuilder::default,
+        || PartBuilder::new(options),
         |builder, param_tuple| builder.incorporate(param_tuple),
     )
     .parse(rest)?;
 
     // Strip newline expected before next tag, or recognize end of input
-    let (rest, _) = alt((multispace0, eof)).parse(rest)?;
+    let (rest, _) = end_of_tag(options).parse(rest)?;
 
-    Ok((rest, HlsElement::Audio(builder)))
+    Ok((rest, HlsElement::Part(builder)))
 }
 
-/// Parse an HLS stream (starts with #EXT-X-STREAM-INF).
-/// Return a `HlsElement::StreamInfo` that represents the parsed data.
-fn hls_stream_info<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
-    // Parse the beginning of a video stream tag
-    let (rest, _) = (extension_prefix(), tag("STREAM-INF:"), space0).parse(data)?;
+/// Parse an HLS `#EXT-X-PART-INF` tag, returning the part target duration in seconds.
+fn hls_part_inf<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    map_res(
+        (
+            // Parse #EXT-X-PART-INF:PART-TARGET=<seconds>
+            extension_prefix(),
+            tag("PART-INF:"),
+            space0,
+            comma_terminated_param(P_PART_TARGET, ParamEnclose::None, options),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(_, _, _, (_, target), _)| target.parse().map(HlsElement::PartInf),
+    )
+    .parse(data)
+}
+
+/// Parse an HLS `#EXT-X-PRELOAD-HINT` tag.
+/// Return a `HlsElement::PreloadHint` that represents the parsed data.
+fn hls_preload_hint<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a preload hint tag
+    let (rest, _) = (extension_prefix(), tag("PRELOAD-HINT:"), space0).parse(data)?;
 
     // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
     // Some params are enclosed by quotes and/or need conversion from the returned str value into another type.
-    let (rest, mut builder) = fold_many1(
+    let (rest, builder) = fold_many1(
         alt((
-            comma_terminated_param(P_BANDWIDTH, ParamEnclose::None),
-            comma_terminated_param(P_AVERAGE_BANDWIDTH, ParamEnclose::None),
-            comma_terminated_param(P_CODECS, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_RESOLUTION, ParamEnclose::None),
-            comma_terminated_param(P_FRAME_RATE, ParamEnclose::None),
-            comma_terminated_param(P_VIDEO_RANGE, ParamEnclose::None),
-            comma_terminated_param(P_AUDIO, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_CLOSED_CAPTIONS, ParamEnclose::None),
+            comma_terminated_param(P_TYPE, ParamEnclose::None, options),
+            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_BYTERANGE_START, ParamEnclose::None, options),
+            comma_terminated_param(P_BYTERANGE_LENGTH, ParamEnclose::None, options),
         )),
-        StreamInfoBuilder::default,
+        PreloadHintBuilder::default,
         |builder, param_tuple| builder.incorporate(param_tuple),
     )
     .parse(rest)?;
 
-    // Parse resource URI expected on the next line, then newlines or end of input
-    let (rest, uri) = map_res(
-        (space0, newline, not_line_ending, alt((multispace0, eof))),
-        |tuple| Ok::<_, NomStrError<'a>>(tuple.2),
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::PreloadHint(builder)))
+}
+
+/// Parse an HLS `#EXT-X-RENDITION-REPORT` tag.
+/// Return a `HlsElement::RenditionReport` that represents the parsed data.
+fn hls_rendition_report<'a>(
+    data: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a rendition report tag
+    let (rest, _) = (extension_prefix(), tag("RENDITION-REPORT:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
+    let (rest, builder) = fold_many1(
+        alt((
+            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_LAST_MSN, ParamEnclose::None, options),
+            comma_terminated_param(P_LAST_PART, ParamEnclose::None, options),
+        )),
+        RenditionReportBuilder::default,
+        |builder, param_tuple| builder.incorporate(param_tuple),
     )
     .parse(rest)?;
 
-    builder.common.uri = Some(uri.to_owned());
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
 
-    Ok((rest, HlsElement::StreamInfo(builder)))
+    Ok((rest, HlsElement::RenditionReport(builder)))
 }
 
-/// Parse an HLS iframe stream (starts with #EXT-X-I-FRAME-STREAM-INF).
-/// Return a `HlsElement::IframeStreamInfo` that represents the parsed data.
-fn hls_iframe_stream_info<'a>(data: &'a str) -> IResult<&'a str, HlsElement> {
-    // Parse the beginning of an ifram video stream tag
-    let (rest, _) = (extension_prefix(), tag("I-FRAME-STREAM-INF:"), space0).parse(data)?;
+/// Parse an HLS `#EXT-X-SKIP` tag, found in delta playlist updates.
+/// Return a `HlsElement::Skip` that represents the parsed data.
+fn hls_skip<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a skip tag
+    let (rest, _) = (extension_prefix(), tag("SKIP:"), space0).parse(data)?;
+
+    // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
+    let (rest, builder) = fold_many1(
+        alt((
+            comma_terminated_param(P_SKIPPED_SEGMENTS, ParamEnclose::None, options),
+            comma_terminated_param(
+                P_RECENTLY_REMOVED_DATERANGES,
+                ParamEnclose::DoubleQuotes,
+                options,
+            ),
+        )),
+        SkipBuilder::default,
+        |builder, param_tuple| builder.incorporate(param_tuple),
+    )
+    .parse(rest)?;
+
+    // Strip newline expected before next tag, or recognize end of input
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::Skip(builder)))
+}
+
+/// Parse an HLS `#EXT-X-DATERANGE` tag, e.g. an ad marker.
+/// Return a `HlsElement::DateRange` that represents the parsed data.
+fn hls_date_range<'a>(data: &'a str, options: &ParseOptions) -> IResult<&'a str, HlsElement<'a>> {
+    // Parse the beginning of a date range tag
+    let (rest, _) = (extension_prefix(), tag("DATERANGE:"), space0).parse(data)?;
 
     // Try any of the following parameter parsers, folding the result into a builer struct for the desired type.
     // Some params are enclosed by quotes and/or need conversion from the returned str value into another type.
     let (rest, builder) = fold_many1(
         alt((
-            comma_terminated_param(P_BANDWIDTH, ParamEnclose::None),
-            comma_terminated_param(P_CODECS, ParamEnclose::DoubleQuotes),
-            comma_terminated_param(P_RESOLUTION, ParamEnclose::None),
-            comma_terminated_param(P_VIDEO_RANGE, ParamEnclose::None),
-            comma_terminated_param(P_URI, ParamEnclose::DoubleQuotes),
+            comma_terminated_param(P_ID, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_CLASS, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_START_DATE, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_END_DATE, ParamEnclose::DoubleQuotes, options),
+            comma_terminated_param(P_DURATION, ParamEnclose::None, options),
+            comma_terminated_param(P_PLANNED_DURATION, ParamEnclose::None, options),
+            comma_terminated_param(P_END_ON_NEXT, ParamEnclose::None, options),
+            comma_terminated_param(P_SCTE35_CMD, ParamEnclose::None, options),
+            comma_terminated_param(P_SCTE35_OUT, ParamEnclose::None, options),
+            comma_terminated_param(P_SCTE35_IN, ParamEnclose::None, options),
+            client_attribute_param(options),
         )),
-        IframeStreamInfoBuilder::default,
+        || DateRangeBuilder::new(options),
         |builder, param_tuple| builder.incorporate(param_tuple),
     )
     .parse(rest)?;
 
     // Strip newline expected before next tag, or recognize end of input
-    let (rest, _) = alt((multispace0, eof)).parse(rest)?;
+    let (rest, _) = end_of_tag(options).parse(rest)?;
+
+    Ok((rest, HlsElement::DateRange(builder)))
+}
 
-    Ok((rest, HlsElement::IframeStreamInfo(builder)))
+/// Parse a URI line (any non-empty line not starting with `#`), which ends a media segment.
+fn hls_uri(data: &str) -> IResult<&str, HlsElement<'_>> {
+    map_res(
+        (
+            verify(not_line_ending, |line: &str| {
+                !line.is_empty() && !line.starts_with('#')
+            }),
+            // Clear subsequent whitespace/newlines/eof
+            multispace0,
+        ),
+        |(uri, _): (&str, _)| Ok::<_, NomStrError>(HlsElement::Uri(uri.trim_end().to_owned())),
+    )
+    .parse(data)
 }
 
 // ---------- Functions and utilities for parsing HLS parameters ----------
 
+/// Return a parser for the end of a tag's line, after its last param: newlines expected before
+/// the next tag, or the end of input. In lenient mode, a trailing comment is discarded first.
+fn end_of_tag<'a>(options: &ParseOptions) -> impl Parser<&'a str, Error = NomStrError<'a>> {
+    (trailing_comment(options), alt((multispace0, eof)))
+}
+
+/// Return a parser that, in lenient mode, discards a `;` comment that some hand-edited playlists
+/// append after a tag's params, up to the end of the line. Matches nothing in strict mode.
+fn trailing_comment<'a>(
+    options: &ParseOptions,
+) -> impl Parser<&'a str, Output = (), Error = NomStrError<'a>> {
+    let lenient = options.lenient;
+    move |data: &'a str| {
+        if lenient {
+            let (rest, _) = opt((tag(";"), not_line_ending)).parse(data)?;
+            Ok((rest, ()))
+        } else {
+            Ok((data, ()))
+        }
+    }
+}
+
 /// Represents the chars surrounding an HLS param, for flexibility parsing
 /// params of the form 'PARAM_NAME=<value>' that may be wrapped in quotes.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum ParamEnclose {
     // NOTE: other param value wrappers may be added here
     None,
     DoubleQuotes,
 }
 
+/// Return a parser that matches the given param name exactly,
+/// or regardless of case if `options.lenient` is set.
+fn param_name<'a>(
+    name: &'a str,
+    options: &ParseOptions,
+) -> impl Parser<&'a str, Output = &'a str, Error = NomStrError<'a>> {
+    let lenient = options.lenient;
+    move |data: &'a str| {
+        if lenient {
+            tag_no_case(name).parse(data)
+        } else {
+            tag(name).parse(data)
+        }
+    }
+}
+
 /// Given a param_name, returns a parser function that matches on '<param_name>=<value>,'
 /// and returns a tuple containing the parameter name and value. Tolerates spaces.
 /// Uses `enclosed_by` to parse delimiters surrounding the parameter value.
+/// In lenient mode, the name is matched regardless of case and the value is trimmed.
 fn comma_terminated_param<'a>(
-    param_name: &'a str,
+    name: &'a str,
     enclosed_by: ParamEnclose,
+    options: &ParseOptions,
 ) -> impl Parser<&'a str, Output = (&'a str, &'a str), Error = NomStrError<'a>> {
+    let lenient = options.lenient;
     // Map result of the combined parser to just the parameter value, returned from a param_value_* function
     map_res(
         (
-            tag(param_name),
+            param_name(name, options),
             space0,
             tag("="),
             space0,
-            match enclosed_by {
-                ParamEnclose::None => param_value_no_enclosure,
-                ParamEnclose::DoubleQuotes => param_value_double_quoted,
+            move |d| match enclosed_by {
+                ParamEnclose::None => param_value_no_enclosure(d, lenient),
+                ParamEnclose::DoubleQuotes => param_value_double_quoted(d),
             },
             space0,
-            // Take comma if present - friendly towards last param in a given line
-            opt(tag(",")),
+            param_terminator(lenient),
+        ),
+        move |tuple| {
+            let value = if lenient { tuple.4.trim() } else { tuple.4 };
+            Ok::<_, NomStrError<'a>>((name, value))
+        },
+    )
+}
+
+/// Return a parser that matches any one of `params`, given as (name, enclosure) pairs,
+/// as `comma_terminated_param` does. Params are tried in order.
+fn any_param<'a>(
+    params: impl Iterator<Item = &'a (&'a str, ParamEnclose)> + Clone,
+    options: &ParseOptions,
+) -> impl Parser<&'a str, Output = (&'a str, &'a str), Error = NomStrError<'a>> {
+    let options = *options;
+    move |data: &'a str| {
+        for &(name, enclosed_by) in params.clone() {
+            match comma_terminated_param(name, enclosed_by, &options).parse(data) {
+                Err(nom::Err::Error(_)) => continue,
+                res => return res,
+            }
+        }
+        Err(nom::Err::Error(NomStrError::new(
+            data,
+            nom::error::ErrorKind::Alt,
+        )))
+    }
+}
+
+/// Return a parser that takes the comma following a param. The last param in a line may omit it,
+/// in which case the end of the line (or, in lenient mode, a trailing `;` comment) is recognized
+/// but not consumed. Params not separated by a comma are rejected.
+fn param_terminator<'a>(
+    lenient: bool,
+) -> impl Parser<&'a str, Output = &'a str, Error = NomStrError<'a>> {
+    alt((
+        tag(","),
+        peek(alt((line_ending, eof))),
+        verify(peek(tag(";")), move |_: &str| lenient),
+    ))
+}
+
+/// Returns a parser function that matches on a client-defined attribute, '<X-NAME>=<value>,',
+/// and returns a tuple containing the full attribute name and value. Tolerates spaces.
+/// The value may be quoted or not; quotes are stripped.
+fn client_attribute_param<'a>(
+    options: &ParseOptions,
+) -> impl Parser<&'a str, Output = (&'a str, &'a str), Error = NomStrError<'a>> {
+    let lenient = options.lenient;
+    map_res(
+        (
+            recognize((
+                param_name(P_CLIENT_ATTRIBUTE_PREFIX, options),
+                take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-'),
+            )),
+            space0,
+            tag("="),
+            space0,
+            alt((param_value_double_quoted, move |d| {
+                param_value_no_enclosure(d, lenient)
+            })),
+            space0,
+            param_terminator(lenient),
         ),
-        move |tuple| Ok::<_, NomStrError<'a>>((param_name, tuple.4)),
+        move |tuple| {
+            let value = if lenient { tuple.4.trim() } else { tuple.4 };
+            Ok::<_, NomStrError<'a>>((tuple.0, value))
+        },
     )
 }
 
-/// Parse and return a parameter value with no enclosing quotes. Terminated at whitespace or comma.
-fn param_value_no_enclosure<'a>(data: &'a str) -> IResult<&'a str, &'a str, NomStrError<'a>> {
-    // Try whitespace- and comma-terminated parsers, using what works
-    alt((take_till(|c: char| c == ',' || c.is_whitespace()),)).parse(data)
+/// Parse and return a parameter value with no enclosing quotes. Terminated at whitespace or comma,
+/// and in lenient mode also at `;`, which starts a trailing comment (see `trailing_comment`).
+fn param_value_no_enclosure<'a>(
+    data: &'a str,
+    lenient: bool,
+) -> IResult<&'a str, &'a str, NomStrError<'a>> {
+    take_till(|c: char| c == ',' || c.is_whitespace() || (lenient && c == ';')).parse(data)
 }
 
 /// Parse and return a parameter value enclosed in double quotes.
+/// The value may contain commas, but per the HLS spec never a double quote or line ending,
+/// so an unterminated value fails rather than running into following lines.
 fn param_value_double_quoted<'a>(data: &'a str) -> IResult<&'a str, &'a str, NomStrError<'a>> {
     // Map result to the parameter value returned by the middle parser.
     map_res(
         (
             tag("\""),
-            take_until::<&'a str, &'a str, _>("\""),
+            take_till(|c: char| c == '"' || c == '\r' || c == '\n'),
             tag("\""),
         ),
         |tuple| Ok::<_, NomStrError<'a>>(tuple.1),
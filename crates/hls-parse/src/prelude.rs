@@ -0,0 +1,26 @@
+//! Commonly used types, re-exported so that one glob import covers most uses of the crate.
+//!
+//! The set is kept small and stable: types are added here once their shape has settled,
+//! and are only removed in a breaking release.
+//!
+//! ```
+//! use std::str::FromStr;
+//!
+//! use hls_parse::prelude::*;
+//!
+//! let data = "#EXTM3U
+//! #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/en.m3u8\"
+//! #EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f,mp4a.40.2\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,AUDIO=\"aac\",CLOSED-CAPTIONS=NONE
+//! low/index.m3u8
+//! ";
+//! let playlist = HlsPlaylist::from_str(data).unwrap();
+//! let audio: &Audio = &playlist.audio_streams.inner[0];
+//! assert_eq!(audio.channel_info.channels, 2);
+//! let stream: &StreamInfo = &playlist.streams.inner[0];
+//! assert_eq!(stream.common.resolution.height, 720);
+//! ```
+
+pub use crate::types::media::{Audio, AudioChannelInfo, InstreamId, VideoRendition};
+pub use crate::types::segment::{EncryptionKey, KeyMethod, Segment};
+pub use crate::types::stream_info::{Codec, HdrFormat, IframeStreamInfo, Resolution, StreamInfo};
+pub use crate::{HlsParseError, HlsPlaylist, MediaPlaylist, ParseOptions, Playlist};
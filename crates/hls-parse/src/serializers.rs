@@ -0,0 +1,425 @@
+//! Functions to serialize parsed HLS data back into M3U8 text.
+//!
+//! Mirrors the `parsers` module: each `hls_*` function renders a single HLS element,
+//! including the URI line that follows it where applicable.
+
+use crate::HlsPlaylist;
+use crate::constants::*;
+use crate::types::define::Definition;
+use crate::types::media::{Audio, SubtitleRendition, VideoRendition};
+use crate::types::segment::{EncryptionKey, MediaInitialization, Part, Segment};
+use crate::types::steering::ContentSteering;
+use crate::types::stream_info::{IframeStreamInfo, Resolution, StreamInfo, StreamInfoCommon};
+
+/// Serialize the entire playlist, one element per line (or two, for elements followed by a URI).
+/// Known elements are written in a fixed order; unknown tags are then inserted at their recorded positions.
+/// Positions index the source playlist, so an unknown tag only keeps its neighbours if the known
+/// elements were already in this order; otherwise it lands at the same index among other elements.
+pub(crate) fn serialize_hls_playlist(playlist: &HlsPlaylist) -> String {
+    let mut elements = Vec::new();
+    if playlist.version != 0 {
+        elements.push(format!("#EXT-X-VERSION:{}", playlist.version));
+    }
+    if playlist.independent_segments {
+        elements.push("#EXT-X-INDEPENDENT-SEGMENTS".to_owned());
+    }
+    elements.extend(playlist.definitions.iter().map(hls_define));
+    if let Some(steering) = &playlist.content_steering {
+        elements.push(hls_content_steering(steering));
+    }
+    elements.extend(playlist.audio_streams.inner.iter().map(hls_audio));
+    elements.extend(
+        playlist
+            .video_renditions
+            .inner
+            .iter()
+            .map(hls_video_rendition),
+    );
+    elements.extend(playlist.subtitles.iter().map(hls_subtitle_rendition));
+    elements.extend(playlist.streams.inner.iter().map(hls_stream_info));
+    elements.extend(
+        playlist
+            .iframe_streams
+            .inner
+            .iter()
+            .map(hls_iframe_stream_info),
+    );
+
+    // Unknown tags are ordered by position, so each insert lands at the index the tag was parsed from
+    for tag in playlist.unknown_tags.iter() {
+        elements.insert(tag.position.min(elements.len()), tag.line.clone());
+    }
+
+    let mut res = String::from("#EXTM3U\n");
+    for elt in elements {
+        res.push_str(&elt);
+        res.push('\n');
+    }
+    res
+}
+
+/// Names of all attributes the serializer writes for the playlist's tags, in order, with repeats.
+pub(crate) fn attribute_names(playlist: &HlsPlaylist) -> Vec<&'static str> {
+    let mut params = Vec::new();
+    if let Some(steering) = &playlist.content_steering {
+        params.extend(content_steering_params(steering));
+    }
+    params.extend(playlist.audio_streams.inner.iter().flat_map(audio_params));
+    params.extend(
+        playlist
+            .video_renditions
+            .inner
+            .iter()
+            .flat_map(video_rendition_params),
+    );
+    params.extend(
+        playlist
+            .subtitles
+            .iter()
+            .flat_map(subtitle_rendition_params),
+    );
+    params.extend(playlist.streams.inner.iter().flat_map(stream_info_params));
+    params.extend(
+        playlist
+            .iframe_streams
+            .inner
+            .iter()
+            .flat_map(iframe_stream_info_params),
+    );
+    params.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Serialize an `#EXT-X-MEDIA:TYPE=AUDIO` tag.
+fn hls_audio(audio: &Audio) -> String {
+    let params = in_source_order(audio_params(audio), &audio.attribute_order);
+    format!("#EXT-X-MEDIA:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-MEDIA:TYPE=VIDEO` tag.
+fn hls_video_rendition(video: &VideoRendition) -> String {
+    let params = in_source_order(video_rendition_params(video), &video.attribute_order);
+    format!("#EXT-X-MEDIA:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-MEDIA:TYPE=SUBTITLES` tag.
+fn hls_subtitle_rendition(subtitles: &SubtitleRendition) -> String {
+    let params = in_source_order(
+        subtitle_rendition_params(subtitles),
+        &subtitles.attribute_order,
+    );
+    format!("#EXT-X-MEDIA:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-STREAM-INF` tag, followed by the stream URI on the next line.
+pub(crate) fn hls_stream_info(stream: &StreamInfo) -> String {
+    let params = in_source_order(stream_info_params(stream), &stream.common.attribute_order);
+    format!(
+        "#EXT-X-STREAM-INF:{}\n{}",
+        param_list(&params),
+        stream.common.uri
+    )
+}
+
+/// Serialize an `#EXT-X-I-FRAME-STREAM-INF` tag.
+fn hls_iframe_stream_info(stream: &IframeStreamInfo) -> String {
+    let params = in_source_order(
+        iframe_stream_info_params(stream),
+        &stream.common.attribute_order,
+    );
+    format!("#EXT-X-I-FRAME-STREAM-INF:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-CONTENT-STEERING` tag.
+fn hls_content_steering(steering: &ContentSteering) -> String {
+    let params = in_source_order(content_steering_params(steering), &steering.attribute_order);
+    format!("#EXT-X-CONTENT-STEERING:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-DEFINE` tag.
+fn hls_define(definition: &Definition) -> String {
+    let params = match definition {
+        Definition::Value { name, value } => {
+            format!("{P_NAME}={},{P_VALUE}={}", quoted(name), quoted(value))
+        }
+        Definition::Import(name) => format!("{P_IMPORT}={}", quoted(name)),
+        Definition::QueryParam(name) => format!("{P_QUERYPARAM}={}", quoted(name)),
+    };
+    format!("#EXT-X-DEFINE:{params}")
+}
+
+// ---------- Functions for serializing media playlist tags ----------
+
+/// Serialize an `#EXT-X-KEY` tag, with `METHOD=NONE` if following segments aren't encrypted.
+pub(crate) fn hls_key(key: Option<&EncryptionKey>) -> String {
+    let Some(key) = key else {
+        return format!("#EXT-X-KEY:{P_METHOD}=NONE");
+    };
+    let mut params = vec![
+        (P_METHOD, key.method.to_string()),
+        (P_URI, quoted(&key.uri)),
+    ];
+    if let Some(iv) = &key.iv {
+        params.push((P_IV, iv.clone()));
+    }
+    if let Some(key_format) = &key.key_format {
+        params.push((P_KEYFORMAT, quoted(key_format)));
+    }
+    if let Some(versions) = &key.key_format_versions {
+        params.push((P_KEYFORMATVERSIONS, quoted(versions)));
+    }
+    format!("#EXT-X-KEY:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-MAP` tag.
+pub(crate) fn hls_map(map: &MediaInitialization) -> String {
+    let mut params = vec![(P_URI, quoted(&map.uri))];
+    if let Some(byte_range) = &map.byte_range {
+        params.push((P_BYTERANGE, quoted(&byte_range.to_string())));
+    }
+    format!("#EXT-X-MAP:{}", param_list(&params))
+}
+
+/// Serialize an `#EXT-X-PART` tag.
+pub(crate) fn hls_part(part: &Part) -> String {
+    let mut params = vec![
+        (P_DURATION, part.duration.to_string()),
+        (P_URI, quoted(&part.uri)),
+    ];
+    if part.independent {
+        params.push((P_INDEPENDENT, yes_no(true)));
+    }
+    if let Some(byte_range) = &part.byte_range {
+        params.push((P_BYTERANGE, quoted(&byte_range.to_string())));
+    }
+    if part.gap {
+        params.push((P_GAP, yes_no(true)));
+    }
+    format!("#EXT-X-PART:{}", param_list(&params))
+}
+
+/// Serialize a segment's `#EXTINF` tag, followed by the segment URI on the next line.
+/// Tags that apply to the segment, such as its key, are serialized separately.
+pub(crate) fn hls_segment_info(segment: &Segment) -> String {
+    format!(
+        "#EXTINF:{},{}\n{}",
+        segment.duration,
+        segment.title.as_deref().unwrap_or_default(),
+        segment.uri
+    )
+}
+
+// ---------- Functions and utilities for serializing HLS parameters ----------
+
+/// Params of an `#EXT-X-MEDIA:TYPE=AUDIO` tag.
+fn audio_params(audio: &Audio) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        (P_TYPE, "AUDIO".to_owned()),
+        (P_GROUP_ID, quoted(&audio.group_id)),
+        (P_NAME, quoted(&audio.name)),
+        (P_LANGUAGE, quoted(&audio.language)),
+    ];
+    if let Some(assoc_language) = &audio.assoc_language {
+        params.push((P_ASSOC_LANGUAGE, quoted(assoc_language)));
+    }
+    params.extend([
+        (P_DEFAULT, yes_no(audio.default)),
+        (P_AUTOSELECT, yes_no(audio.auto_select)),
+        (P_CHANNELS, quoted(&audio.channel_info.param_value())),
+    ]);
+    if let Some(characteristics) = &audio.characteristics {
+        params.push((P_CHARACTERISTICS, quoted(&characteristics.join(","))));
+    }
+    if let Some(stable_rendition_id) = &audio.stable_rendition_id {
+        params.push((P_STABLE_RENDITION_ID, quoted(stable_rendition_id)));
+    }
+    if let Some(sample_rate) = audio.sample_rate {
+        params.push((P_SAMPLE_RATE, sample_rate.to_string()));
+    }
+    if let Some(bit_depth) = audio.bit_depth {
+        params.push((P_BIT_DEPTH, bit_depth.to_string()));
+    }
+    params.push((P_URI, quoted(&audio.uri)));
+    params
+}
+
+/// Params of an `#EXT-X-MEDIA:TYPE=VIDEO` tag.
+fn video_rendition_params(video: &VideoRendition) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        (P_TYPE, "VIDEO".to_owned()),
+        (P_GROUP_ID, quoted(&video.group_id)),
+        (P_NAME, quoted(&video.name)),
+    ];
+    if let Some(language) = &video.language {
+        params.push((P_LANGUAGE, quoted(language)));
+    }
+    // Both default to NO, so are only written if YES or given in the source
+    let given = |name: &str| video.attribute_order.iter().any(|n| n == name);
+    if video.default || given(P_DEFAULT) {
+        params.push((P_DEFAULT, yes_no(video.default)));
+    }
+    if video.auto_select || given(P_AUTOSELECT) {
+        params.push((P_AUTOSELECT, yes_no(video.auto_select)));
+    }
+    if let Some(stable_rendition_id) = &video.stable_rendition_id {
+        params.push((P_STABLE_RENDITION_ID, quoted(stable_rendition_id)));
+    }
+    if let Some(uri) = &video.uri {
+        params.push((P_URI, quoted(uri)));
+    }
+    params
+}
+
+/// Params of an `#EXT-X-MEDIA:TYPE=SUBTITLES` tag.
+fn subtitle_rendition_params(subtitles: &SubtitleRendition) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        (P_TYPE, "SUBTITLES".to_owned()),
+        (P_GROUP_ID, quoted(&subtitles.group_id)),
+        (P_NAME, quoted(&subtitles.name)),
+    ];
+    if let Some(language) = &subtitles.language {
+        params.push((P_LANGUAGE, quoted(language)));
+    }
+    if let Some(assoc_language) = &subtitles.assoc_language {
+        params.push((P_ASSOC_LANGUAGE, quoted(assoc_language)));
+    }
+    // All default to NO, so are only written if YES or given in the source
+    let given = |name: &str| subtitles.attribute_order.iter().any(|n| n == name);
+    if subtitles.default || given(P_DEFAULT) {
+        params.push((P_DEFAULT, yes_no(subtitles.default)));
+    }
+    if subtitles.auto_select || given(P_AUTOSELECT) {
+        params.push((P_AUTOSELECT, yes_no(subtitles.auto_select)));
+    }
+    if subtitles.forced || given(P_FORCED) {
+        params.push((P_FORCED, yes_no(subtitles.forced)));
+    }
+    if let Some(characteristics) = &subtitles.characteristics {
+        params.push((P_CHARACTERISTICS, quoted(&characteristics.join(","))));
+    }
+    if let Some(stable_rendition_id) = &subtitles.stable_rendition_id {
+        params.push((P_STABLE_RENDITION_ID, quoted(stable_rendition_id)));
+    }
+    params.push((P_URI, quoted(&subtitles.uri)));
+    params
+}
+
+/// Params of an `#EXT-X-STREAM-INF` tag. The stream URI isn't a param, so isn't included.
+fn stream_info_params(stream: &StreamInfo) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    if let Some(program_id) = stream.program_id {
+        params.push((P_PROGRAM_ID, program_id.to_string()));
+    }
+    params.push((P_BANDWIDTH, stream.common.bandwidth.to_string()));
+    if let Some(average_bandwidth) = stream.average_bandwidth {
+        params.push((P_AVERAGE_BANDWIDTH, average_bandwidth.to_string()));
+    }
+    params.push((P_CODECS, quoted(&stream.common.codecs.join(","))));
+    params.extend(supplemental_codecs(&stream.common));
+    params.push((P_RESOLUTION, resolution(&stream.common.resolution)));
+    if let Some(frame_rate) = stream.frame_rate {
+        params.push((P_FRAME_RATE, frame_rate.to_string()));
+    }
+    params.push((P_VIDEO_RANGE, stream.common.video_range.clone()));
+    if let Some(audio_group) = &stream.audio_group {
+        params.push((P_AUDIO, quoted(audio_group)));
+    }
+    if let Some(video_group) = &stream.video_group {
+        params.push((P_VIDEO, quoted(video_group)));
+    }
+    params.push((P_CLOSED_CAPTIONS, stream.closed_captions.clone()));
+    if let Some(pathway_id) = &stream.pathway_id {
+        params.push((P_PATHWAY_ID, quoted(pathway_id)));
+    }
+    if let Some(score) = stream.score {
+        params.push((P_SCORE, score.to_string()));
+    }
+    params.extend(stable_variant_id(&stream.common));
+    params.extend(allowed_cpc(&stream.common));
+    params
+}
+
+/// Params of an `#EXT-X-I-FRAME-STREAM-INF` tag.
+fn iframe_stream_info_params(stream: &IframeStreamInfo) -> Vec<(&'static str, String)> {
+    let mut params = vec![(P_BANDWIDTH, stream.common.bandwidth.to_string())];
+    if let Some(average_bandwidth) = stream.average_bandwidth {
+        params.push((P_AVERAGE_BANDWIDTH, average_bandwidth.to_string()));
+    }
+    params.push((P_CODECS, quoted(&stream.common.codecs.join(","))));
+    params.extend(supplemental_codecs(&stream.common));
+    params.extend([
+        (P_RESOLUTION, resolution(&stream.common.resolution)),
+        (P_VIDEO_RANGE, stream.common.video_range.clone()),
+    ]);
+    params.extend(stable_variant_id(&stream.common));
+    params.extend(allowed_cpc(&stream.common));
+    params.push((P_URI, quoted(&stream.common.uri)));
+    params
+}
+
+/// Params of an `#EXT-X-CONTENT-STEERING` tag.
+fn content_steering_params(steering: &ContentSteering) -> Vec<(&'static str, String)> {
+    let mut params = vec![(P_SERVER_URI, quoted(&steering.server_uri))];
+    if let Some(pathway_id) = &steering.pathway_id {
+        params.push((P_PATHWAY_ID, quoted(pathway_id)));
+    }
+    params
+}
+
+/// Reorder params (given in canonical order) to match `order`, the param names of the source tag.
+/// Params missing from `order` keep their canonical order, after those that are present.
+fn in_source_order(
+    mut params: Vec<(&'static str, String)>,
+    order: &[String],
+) -> Vec<(&'static str, String)> {
+    // Stable sort, so that unordered params stay canonical
+    params.sort_by_key(|(name, _)| order.iter().position(|n| n == name).unwrap_or(usize::MAX));
+    params
+}
+
+/// Join (name, value) pairs into an HLS attribute list of the form `NAME=value,NAME=value`.
+fn param_list(params: &[(&str, String)]) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The `SUPPLEMENTAL-CODECS` param of a stream, if present.
+fn supplemental_codecs(common: &StreamInfoCommon) -> Option<(&'static str, String)> {
+    common
+        .supplemental_codecs
+        .as_ref()
+        .map(|codecs| (P_SUPPLEMENTAL_CODECS, quoted(&codecs.join(","))))
+}
+
+/// The `ALLOWED-CPC` param of a stream, if present.
+fn allowed_cpc(common: &StreamInfoCommon) -> Option<(&'static str, String)> {
+    common.allowed_cpc.as_ref().map(|entries| {
+        let entries = entries.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        (P_ALLOWED_CPC, quoted(&entries.join(",")))
+    })
+}
+
+/// `STABLE-VARIANT-ID` param of a video or iframe stream, if it has one.
+fn stable_variant_id(common: &StreamInfoCommon) -> Option<(&'static str, String)> {
+    common
+        .stable_variant_id
+        .as_ref()
+        .map(|id| (P_STABLE_VARIANT_ID, quoted(id)))
+}
+
+/// Format a resolution as `WxH`, without the padding used by its `Display` impl.
+pub(crate) fn resolution(resolution: &Resolution) -> String {
+    format!("{}x{}", resolution.width, resolution.height)
+}
+
+/// Wrap a parameter value in double quotes.
+fn quoted(value: &str) -> String {
+    format!("\"{value}\"")
+}
+
+/// Convert a boolean to its HLS parameter representation (YES/NO).
+fn yes_no(value: bool) -> String {
+    if value { "YES" } else { "NO" }.to_owned()
+}
@@ -0,0 +1,232 @@
+//! Table rendering for playlists, with column widths computed from the data.
+
+use crate::{
+    HlsPlaylist,
+    constants::*,
+    serializers::resolution,
+    types::{
+        media::{Audio, VideoRendition},
+        stream_info::{IframeStreamInfo, StreamInfo},
+    },
+};
+
+/// Options controlling how [`HlsPlaylist::format_table`] lays out its tables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableOptions {
+    /// Maximum width of any column, in terminal columns.
+    /// Longer values are truncated, ending in `…`. `None` never truncates.
+    pub max_width: Option<usize>,
+    /// Drop borders and padding, separating columns by two spaces.
+    pub compact: bool,
+    /// Leave out the audio streams table.
+    pub hide_audio: bool,
+    /// Leave out the video renditions and video streams tables.
+    pub hide_video: bool,
+    /// Leave out the iframe streams table.
+    pub hide_iframe: bool,
+}
+
+impl HlsPlaylist {
+    /// Render audio, video and iframe streams as tables, sizing each column to fit its contents.
+    /// Video renditions are included only if the playlist has any.
+    /// Tables hidden by `opts` are left out entirely.
+    pub fn format_table(&self, opts: &TableOptions) -> String {
+        let mut res = String::new();
+        if !opts.hide_audio {
+            res.push_str(&render(
+                "Audio Streams",
+                &[
+                    P_GROUP_ID,
+                    P_NAME,
+                    P_LANGUAGE,
+                    P_DEFAULT,
+                    P_AUTOSELECT,
+                    P_CHANNELS,
+                    P_URI,
+                ],
+                self.audio_streams.inner.iter().map(audio_row),
+                opts,
+            ));
+        }
+        if !opts.hide_video && !self.video_renditions.inner.is_empty() {
+            res.push_str(&render(
+                "Video Renditions",
+                &[
+                    P_GROUP_ID,
+                    P_NAME,
+                    P_LANGUAGE,
+                    P_DEFAULT,
+                    P_AUTOSELECT,
+                    P_URI,
+                ],
+                self.video_renditions.inner.iter().map(video_rendition_row),
+                opts,
+            ));
+        }
+        if !opts.hide_video {
+            res.push_str(&render(
+                "Video Streams",
+                &[
+                    P_BANDWIDTH,
+                    P_AVERAGE_BANDWIDTH,
+                    P_CODECS,
+                    P_RESOLUTION,
+                    P_FRAME_RATE,
+                    P_VIDEO_RANGE,
+                    P_AUDIO,
+                    P_CLOSED_CAPTIONS,
+                    P_URI,
+                ],
+                self.streams.inner.iter().map(stream_row),
+                opts,
+            ));
+        }
+        if !opts.hide_iframe {
+            res.push_str(&render(
+                "IFrame Streams",
+                &[P_BANDWIDTH, P_CODECS, P_RESOLUTION, P_VIDEO_RANGE, P_URI],
+                self.iframe_streams.inner.iter().map(iframe_stream_row),
+                opts,
+            ));
+        }
+        res
+    }
+}
+
+fn audio_row(a: &Audio) -> Vec<String> {
+    vec![
+        a.group_id.clone(),
+        a.name.clone(),
+        a.language.clone(),
+        a.default.to_string(),
+        a.auto_select.to_string(),
+        a.channel_info.param_value(),
+        a.uri.clone(),
+    ]
+}
+
+fn video_rendition_row(v: &VideoRendition) -> Vec<String> {
+    vec![
+        v.group_id.clone(),
+        v.name.clone(),
+        v.language.clone().unwrap_or_default(),
+        v.default.to_string(),
+        v.auto_select.to_string(),
+        v.uri.clone().unwrap_or_default(),
+    ]
+}
+
+fn stream_row(s: &StreamInfo) -> Vec<String> {
+    vec![
+        s.common.bandwidth.to_string(),
+        s.average_bandwidth
+            .map(|b| b.to_string())
+            .unwrap_or_default(),
+        s.common.codecs.join(", "),
+        resolution(&s.common.resolution),
+        s.frame_rate.map(|r| r.to_string()).unwrap_or_default(),
+        s.common.video_range.clone(),
+        s.audio_group.clone().unwrap_or_default(),
+        s.closed_captions.clone(),
+        s.common.uri.clone(),
+    ]
+}
+
+fn iframe_stream_row(s: &IframeStreamInfo) -> Vec<String> {
+    vec![
+        s.common.bandwidth.to_string(),
+        s.common.codecs.join(", "),
+        resolution(&s.common.resolution),
+        s.common.video_range.clone(),
+        s.common.uri.clone(),
+    ]
+}
+
+/// Render one titled table. Each column is as wide as its widest cell, capped at `opts.max_width`.
+fn render(
+    title: &str,
+    headers: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+    opts: &TableOptions,
+) -> String {
+    let fit = |cell: String| match opts.max_width {
+        Some(max) if display_width(&cell) > max => {
+            let mut cut = String::new();
+            let mut width = 0;
+            for c in cell.chars() {
+                width += char_width(c);
+                if width > max.saturating_sub(1) {
+                    break;
+                }
+                cut.push(c);
+            }
+            cut.push('…');
+            cut
+        }
+        _ => cell,
+    };
+    let header: Vec<String> = headers.iter().map(|h| fit(h.to_string())).collect();
+    let rows: Vec<Vec<String>> = rows.map(|r| r.into_iter().map(fit).collect()).collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(rows.iter())
+                .map(|r| display_width(&r[i]))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let line = |cells: &[String]| {
+        let padded = cells.iter().zip(&widths);
+        if opts.compact {
+            let cols: Vec<String> = padded.map(|(c, w)| pad_left(c, *w)).collect();
+            format!("{}\n", cols.join("  ").trim_end())
+        } else {
+            let cols: Vec<String> = padded.map(|(c, w)| pad_center(c, *w)).collect();
+            format!("| {} |\n", cols.join(" | "))
+        }
+    };
+
+    let mut res = format!("{title}\n");
+    if !opts.compact {
+        res.push_str(&format!("{}\n", "-".repeat(title.len())));
+    }
+    res.push_str(&line(&header));
+    for row in rows.iter() {
+        res.push_str(&line(row));
+    }
+    res
+}
+
+/// Number of terminal columns `c` takes up: 0 or 2 for some characters with the `unicode-width` feature,
+/// otherwise always 1.
+fn char_width(c: char) -> usize {
+    #[cfg(feature = "unicode-width")]
+    {
+        unicode_width::UnicodeWidthChar::width(c).unwrap_or_default()
+    }
+    #[cfg(not(feature = "unicode-width"))]
+    {
+        let _ = c;
+        1
+    }
+}
+
+/// Number of terminal columns `s` takes up.
+/// Without the `unicode-width` feature, this is its number of chars, so wide characters misalign.
+pub(crate) fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// Center `s` in `width` columns, like `format!("{s:^width$}")` but by display width.
+pub(crate) fn pad_center(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{}{s}{}", " ".repeat(pad / 2), " ".repeat(pad - pad / 2))
+}
+
+/// Left-align `s` in `width` columns, like `format!("{s:<width$}")` but by display width.
+pub(crate) fn pad_left(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{s}{}", " ".repeat(pad))
+}
@@ -5,6 +5,7 @@
 // Types of media under tag #EXT-X-MEDIA
 pub mod media {
     use crate::constants::*;
+    use crate::table::pad_center;
 
     use std::fmt::Display;
     use std::str::FromStr;
@@ -12,11 +13,25 @@ pub mod media {
     use anyhow::Context;
 
     /// Collection of all iframe streams parsed from an HLS playlist
-    #[derive(Debug, Default)]
+    #[derive(Debug, Default, Clone, PartialEq)]
     pub struct AudioStreams {
         pub inner: Vec<Audio>,
     }
 
+    impl FromIterator<Audio> for AudioStreams {
+        fn from_iter<I: IntoIterator<Item = Audio>>(iter: I) -> Self {
+            Self {
+                inner: iter.into_iter().collect(),
+            }
+        }
+    }
+
+    impl Extend<Audio> for AudioStreams {
+        fn extend<I: IntoIterator<Item = Audio>>(&mut self, iter: I) {
+            self.inner.extend(iter);
+        }
+    }
+
     impl Display for AudioStreams {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             writeln!(f, "Audio Streams")?;
@@ -34,81 +49,293 @@ pub mod media {
     }
 
     /// Represents parsed audio stream metadata (`#EXT-X-MEDIA:TYPE=AUDIO`)
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
     pub struct Audio {
         pub group_id: String,
         pub name: String,
         pub language: String,
+        /// Language associated with `language` in a different role (`ASSOC-LANGUAGE`),
+        /// e.g. spoken vs. written
+        pub assoc_language: Option<String>,
         pub default: bool,
         pub auto_select: bool,
         pub channel_info: AudioChannelInfo,
+        /// Uniform Type Identifiers of the rendition's characteristics (`CHARACTERISTICS`),
+        /// e.g. `public.accessibility.describes-video`
+        pub characteristics: Option<Vec<String>>,
+        /// Identifier of the rendition that stays the same across content steering pathways
+        /// (`STABLE-RENDITION-ID`)
+        pub stable_rendition_id: Option<String>,
+        /// Sample rate in Hz (`SAMPLE-RATE`), e.g. 96000 for hi-res audio
+        pub sample_rate: Option<u32>,
+        /// Bits per sample (`BIT-DEPTH`), e.g. 24 for hi-res audio
+        pub bit_depth: Option<u8>,
         /// URI of the audio stream the other metadata fields describe
         // TODO: represent as http::uri::Uri ?
         pub uri: String,
+        /// Names of the tag's params in source order, used to serialize them in that order.
+        /// Empty if not parsed from a playlist, in which case params are serialized in a canonical order.
+        pub attribute_order: Vec<String>,
+        /// Source text of the tag, recorded only if parsed with `ParseOptions::keep_raw`.
+        pub raw: Option<String>,
     }
 
     impl FromStr for AudioChannelInfo {
         type Err = anyhow::Error;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            // Detect optional "/JOC" on channels string
-            let split = s.split('/').collect::<Vec<_>>();
+            // Channel count, followed by optional '/'-separated identifiers (e.g. "/JOC")
+            let mut split = s.split('/');
+            let channels = split.next().unwrap_or_default();
+            if channels.is_empty() {
+                anyhow::bail!("channel count is missing: {s:?}");
+            }
+            let identifiers = split.map(str::to_owned).collect::<Vec<_>>();
             Ok(Self {
-                channels: split[0]
+                channels: channels
                     .parse::<usize>()
-                    .with_context(|| format!("failed to parse channel count: {}", split[0]))?,
-                joc: split.len() > 1 && split[1] == "JOC",
+                    .with_context(|| format!("failed to parse channel count: {channels}"))?,
+                joc: identifiers.first().is_some_and(|id| id == "JOC"),
+                identifiers,
             })
         }
     }
 
+    impl FromStr for Audio {
+        type Err = anyhow::Error;
+
+        /// Parse a single `#EXT-X-MEDIA:TYPE=AUDIO` line.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(crate::parsers::parse_audio(s)?)
+        }
+    }
+
     impl Display for Audio {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             write!(
                 f,
-                "| {:^10} | {:^10} | {:^10} | {:^7} | {:^10} | {} | {:^35} |",
-                self.group_id,
-                self.name,
-                self.language,
+                "| {} | {} | {} | {:^7} | {:^10} | {} | {} |",
+                pad_center(&self.group_id, 10),
+                pad_center(&self.name, 10),
+                pad_center(&self.language, 10),
                 self.default,
                 self.auto_select,
                 self.channel_info,
-                self.uri
+                pad_center(&self.uri, 35)
             )
         }
     }
 
     /// Represents the parsed value of an audio stream's `CHANNELS` parameter
-    #[derive(Debug, Eq, PartialEq, PartialOrd)]
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
     pub struct AudioChannelInfo {
+        /// The count leading the param: the maximum number of independent, simultaneous audio channels,
+        /// or for Joint Object Coding (e.g. Dolby Atmos), the maximum number of audio objects.
+        /// See `object_count` for the latter.
         pub channels: usize,
+        /// Whether the audio uses Joint Object Coding, i.e. the first identifier is `JOC`
         pub joc: bool,
+        /// All identifiers following the channel count, in order
+        /// (e.g. spatial audio and special usage identifiers like `JOC` or `BINAURAL`)
+        pub identifiers: Vec<String>,
+    }
+
+    impl AudioChannelInfo {
+        /// Maximum number of audio objects, e.g. 16 for `16/JOC`, if the audio uses Joint Object Coding.
+        pub fn object_count(&self) -> Option<usize> {
+            self.joc.then_some(self.channels)
+        }
+
+        /// The `CHANNELS` param value this was parsed from, e.g. `16/JOC`.
+        pub(crate) fn param_value(&self) -> String {
+            let mut res = self.channels.to_string();
+            if self.identifiers.is_empty() && self.joc {
+                res.push_str("/JOC");
+            }
+            for id in self.identifiers.iter() {
+                res.push('/');
+                res.push_str(id);
+            }
+            res
+        }
     }
 
     impl Display for AudioChannelInfo {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(
-                f,
-                "{:^8}",
-                format!("{}{}", self.channels, if self.joc { "/JOC" } else { "" })
-            )
+            write!(f, "{:^8}", self.param_value())
+        }
+    }
+
+    impl PartialOrd for AudioChannelInfo {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
         }
     }
 
     impl Ord for AudioChannelInfo {
         fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            // Sort by channels first, breaking tie on joc
+            // Sort by channels first, breaking tie on joc, then on other identifiers
             if self.channels < other.channels {
                 std::cmp::Ordering::Less
             } else if self.channels > other.channels {
                 std::cmp::Ordering::Greater
             } else {
-                self.joc.cmp(&other.joc)
+                self.joc
+                    .cmp(&other.joc)
+                    .then_with(|| self.identifiers.cmp(&other.identifiers))
             }
         }
     }
 
-    // TODO: implement subtitles
+    /// Collection of all video renditions parsed from an HLS playlist
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct VideoRenditions {
+        pub inner: Vec<VideoRendition>,
+    }
+
+    impl FromIterator<VideoRendition> for VideoRenditions {
+        fn from_iter<I: IntoIterator<Item = VideoRendition>>(iter: I) -> Self {
+            Self {
+                inner: iter.into_iter().collect(),
+            }
+        }
+    }
+
+    impl Extend<VideoRendition> for VideoRenditions {
+        fn extend<I: IntoIterator<Item = VideoRendition>>(&mut self, iter: I) {
+            self.inner.extend(iter);
+        }
+    }
+
+    impl Display for VideoRenditions {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "Video Renditions")?;
+            writeln!(f, "----------------")?;
+            writeln!(
+                f,
+                "| {:^10} | {:^10} | {:^10} | {:^7} | {:^10} | {:^35} |",
+                P_GROUP_ID, P_NAME, P_LANGUAGE, P_DEFAULT, P_AUTOSELECT, P_URI,
+            )?;
+            for i in self.inner.iter() {
+                writeln!(f, "{i}")?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Represents parsed video rendition metadata (`#EXT-X-MEDIA:TYPE=VIDEO`),
+    /// e.g. an alternative camera angle referenced by a stream's `VIDEO` param
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct VideoRendition {
+        pub group_id: String,
+        pub name: String,
+        pub language: Option<String>,
+        pub default: bool,
+        pub auto_select: bool,
+        /// Identifier of the rendition that stays the same across content steering pathways
+        /// (`STABLE-RENDITION-ID`)
+        pub stable_rendition_id: Option<String>,
+        /// URI of the rendition's media playlist. If absent, the rendition is included
+        /// in the media playlist of any stream that references its group.
+        pub uri: Option<String>,
+        /// Names of the tag's params in source order, used to serialize them in that order.
+        /// Empty if not parsed from a playlist, in which case params are serialized in a canonical order.
+        pub attribute_order: Vec<String>,
+        /// Source text of the tag, recorded only if parsed with `ParseOptions::keep_raw`.
+        pub raw: Option<String>,
+    }
+
+    impl Display for VideoRendition {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "| {} | {} | {} | {:^7} | {:^10} | {} |",
+                pad_center(&self.group_id, 10),
+                pad_center(&self.name, 10),
+                pad_center(self.language.as_deref().unwrap_or_default(), 10),
+                self.default,
+                self.auto_select,
+                pad_center(self.uri.as_deref().unwrap_or_default(), 35)
+            )
+        }
+    }
+
+    /// Represents parsed subtitle rendition metadata (`#EXT-X-MEDIA:TYPE=SUBTITLES`)
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct SubtitleRendition {
+        pub group_id: String,
+        pub name: String,
+        pub language: Option<String>,
+        pub assoc_language: Option<String>,
+        pub default: bool,
+        pub auto_select: bool,
+        /// Whether the subtitles are shown even if the user hasn't selected any (`FORCED`)
+        pub forced: bool,
+        pub characteristics: Option<Vec<String>>,
+        /// Identifier of the rendition that stays the same across content steering pathways
+        /// (`STABLE-RENDITION-ID`)
+        pub stable_rendition_id: Option<String>,
+        /// URI of the rendition's media playlist
+        pub uri: String,
+        /// Names of the tag's params in source order, used to serialize them in that order.
+        /// Empty if not parsed from a playlist, in which case params are serialized in a canonical order.
+        pub attribute_order: Vec<String>,
+        /// Source text of the tag, recorded only if parsed with `ParseOptions::keep_raw`.
+        pub raw: Option<String>,
+    }
+
+    /// Represents an `INSTREAM-ID` param, identifying a closed-caption channel within a video stream
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+    #[non_exhaustive]
+    pub enum InstreamId {
+        /// CEA-608 caption channel, 1 to 4 (`CC1`-`CC4`)
+        Cc(u8),
+        /// CEA-708 caption service, 1 to 63 (`SERVICE1`-`SERVICE63`)
+        Service(u8),
+    }
+
+    impl FromStr for InstreamId {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (number, range, id): (_, _, fn(u8) -> Self) =
+                if let Some(number) = s.strip_prefix("CC") {
+                    (number, 1..=4, InstreamId::Cc)
+                } else if let Some(number) = s.strip_prefix("SERVICE") {
+                    (number, 1..=63, InstreamId::Service)
+                } else {
+                    anyhow::bail!("instream ID must be CC<n> or SERVICE<n>: {s}");
+                };
+            // Digits only: `u8::from_str` would also accept a leading `+`
+            let n = Some(number)
+                .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| range.contains(n))
+                .with_context(|| {
+                    format!(
+                        "instream ID number must be {}-{}: {s}",
+                        range.start(),
+                        range.end()
+                    )
+                })?;
+            Ok(id(n))
+        }
+    }
+
+    impl Display for InstreamId {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                InstreamId::Cc(n) => write!(f, "CC{n}"),
+                InstreamId::Service(n) => write!(f, "SERVICE{n}"),
+            }
+        }
+    }
+
+    // TODO: implement closed-caption renditions
+    // (closed-caption tags are kept as unknown tags, with `INSTREAM-ID` validated as above)
 }
 
 // Types for parsing #EXT-X-STREAM-INF
@@ -120,23 +347,181 @@ pub mod stream_info {
     use anyhow::Context;
 
     /// Data related to all stream types (regular and iframe streams).
-    #[derive(Debug, Default, PartialEq)]
+    #[derive(Debug, Default, Clone)]
+    #[non_exhaustive]
     pub struct StreamInfoCommon {
-        pub bandwidth: usize,
+        pub bandwidth: u64,
         pub codecs: Vec<String>,
+        /// Codecs from the `SUPPLEMENTAL-CODECS` param (e.g. Dolby Vision),
+        /// each kept verbatim including its `/`-delimited compatibility brands
+        pub supplemental_codecs: Option<Vec<String>>,
         pub resolution: Resolution,
         pub video_range: String,
+        /// Identifier of the variant that stays the same across content steering pathways
+        /// (`STABLE-VARIANT-ID`)
+        pub stable_variant_id: Option<String>,
+        /// Content protection configurations allowed for each key format (`ALLOWED-CPC`), if given
+        pub allowed_cpc: Option<Vec<CpcEntry>>,
         /// URI of the media playlist that other metadata fields describe
         // TODO: represent as http::uri::Uri ?
         pub uri: String,
+        /// Names of the tag's params in source order, used to serialize them in that order.
+        /// Empty if not parsed from a playlist, in which case params are serialized in a canonical order.
+        pub attribute_order: Vec<String>,
+        /// Source text of the tag, recorded only if parsed with `ParseOptions::keep_raw`.
+        /// Includes the URI line following `#EXT-X-STREAM-INF`. Ignored when comparing streams.
+        pub raw: Option<String>,
+    }
+
+    // Consistent with the order of streams, which doesn't depend on whether source text was kept
+    impl PartialEq for StreamInfoCommon {
+        fn eq(&self, other: &Self) -> bool {
+            cmp_common(self, other).is_eq()
+        }
+    }
+
+    impl Eq for StreamInfoCommon {}
+
+    impl StreamInfoCommon {
+        /// Codecs from the `CODECS` param, parsed into structured form.
+        pub fn parsed_codecs(&self) -> Vec<Codec> {
+            self.codecs
+                .iter()
+                .map(|c| Codec::from(c.as_str()))
+                .collect()
+        }
+
+        /// HDR formats signalled by this stream, derived from its `VIDEO-RANGE` and `CODECS` params.
+        pub fn hdr_formats(&self) -> Vec<HdrFormat> {
+            let mut formats = Vec::new();
+            match self.video_range.as_str() {
+                "SDR" => formats.push(HdrFormat::Sdr),
+                "HLG" => formats.push(HdrFormat::Hlg),
+                "PQ" => formats.push(HdrFormat::Pq),
+                _ => (),
+            }
+            // Dolby Vision is signalled by codec (fourCC) rather than video range,
+            // either as the main codec or as a supplemental codec on a backwards-compatible stream
+            let supplemental_codecs = self.supplemental_codecs.iter().flatten();
+            if self.codecs.iter().chain(supplemental_codecs).any(|c| {
+                ["dvh1", "dvhe", "dva1", "dvav", "dav1"]
+                    .iter()
+                    .any(|prefix| c.starts_with(prefix))
+            }) {
+                formats.push(HdrFormat::DolbyVision);
+            }
+            formats
+        }
+    }
+
+    /// An entry of a stream's `ALLOWED-CPC` param, e.g. `com.example.drm1:SMART-TV/PC`:
+    /// the content protection configurations (CPCs) a client must meet to play the stream
+    /// with keys of one `KEYFORMAT`.
+    #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct CpcEntry {
+        pub key_format: String,
+        /// CPC labels, from the `/`-separated list following the key format
+        pub classes: Vec<String>,
+    }
+
+    impl FromStr for CpcEntry {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            // Expects format KEYFORMAT:CLASS/CLASS. The key format may itself contain ':'.
+            let (key_format, classes) = s
+                .rsplit_once(':')
+                .with_context(|| format!("CPC entry is not of the form KEYFORMAT:CLASSES: {s}"))?;
+            if key_format.is_empty() || classes.is_empty() {
+                anyhow::bail!("CPC entry is missing its key format or classes: {s}");
+            }
+            let classes = classes.split('/').map(str::to_owned).collect::<Vec<_>>();
+            if classes.iter().any(String::is_empty) {
+                anyhow::bail!("CPC entry has an empty class: {s}");
+            }
+            Ok(Self {
+                key_format: key_format.to_owned(),
+                classes,
+            })
+        }
+    }
+
+    impl Display for CpcEntry {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}:{}", self.key_format, self.classes.join("/"))
+        }
+    }
+
+    /// A codec parsed from an entry of a stream's `CODECS` param, identified by its fourCC prefix.
+    /// Profile/level details remain available in the raw codec strings.
+    #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[non_exhaustive]
+    pub enum Codec {
+        H264,
+        Hevc,
+        Aac,
+        Ac3,
+        Ec3,
+        /// Codec not recognized by this crate, holding the raw codec string
+        Other(String),
+    }
+
+    impl Codec {
+        /// Whether this is a known video codec.
+        pub fn is_video(&self) -> bool {
+            matches!(self, Codec::H264 | Codec::Hevc)
+        }
+
+        /// Whether this is a known audio codec.
+        pub fn is_audio(&self) -> bool {
+            matches!(self, Codec::Aac | Codec::Ac3 | Codec::Ec3)
+        }
+    }
+
+    impl From<&str> for Codec {
+        fn from(s: &str) -> Self {
+            // fourCC precedes the first '.', if any (e.g. "hvc1.2.4.L90.90", "mp4a.40.2", "ec-3")
+            match s.split('.').next().unwrap_or_default() {
+                "avc1" | "avc3" => Codec::H264,
+                "hvc1" | "hev1" => Codec::Hevc,
+                "mp4a" => Codec::Aac,
+                "ac-3" => Codec::Ac3,
+                "ec-3" => Codec::Ec3,
+                _ => Codec::Other(s.to_owned()),
+            }
+        }
+    }
+
+    /// Dynamic range formats a stream may be encoded in
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+    #[non_exhaustive]
+    pub enum HdrFormat {
+        Sdr,
+        Hlg,
+        Pq,
+        DolbyVision,
     }
 
     /// Collection of all video streams parsed from an HLS playlist
-    #[derive(Debug, Default)]
+    #[derive(Debug, Default, Clone, PartialEq)]
     pub struct Streams {
         pub inner: Vec<StreamInfo>,
     }
 
+    impl FromIterator<StreamInfo> for Streams {
+        fn from_iter<I: IntoIterator<Item = StreamInfo>>(iter: I) -> Self {
+            Self {
+                inner: iter.into_iter().collect(),
+            }
+        }
+    }
+
+    impl Extend<StreamInfo> for Streams {
+        fn extend<I: IntoIterator<Item = StreamInfo>>(&mut self, iter: I) {
+            self.inner.extend(iter);
+        }
+    }
+
     impl Display for Streams {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             writeln!(f, "Video Streams")?;
@@ -161,15 +546,42 @@ pub mod stream_info {
         }
     }
 
-    /// Represents parsed video stream metadata (`#EXT-X-STREAM-INF`)
-    #[derive(Debug, Default, PartialEq)]
+    /// Represents parsed video stream metadata (`#EXT-X-STREAM-INF`).
+    ///
+    /// Streams are ordered by quality: by bandwidth, then by resolution.
+    /// Remaining fields only break ties, so that streams compare equal only if they are `==`.
+    /// Like `==`, this includes param order, so the order of otherwise identical streams depends on
+    /// how their params were written, but not the source text kept by `ParseOptions::keep_raw`.
+    /// A stream without a frame rate sorts after streams with one, as when sorting by frame rate in `hls-sort`.
+    #[derive(Debug, Default, Clone)]
+    #[non_exhaustive]
     pub struct StreamInfo {
         pub common: StreamInfoCommon,
-        pub average_bandwidth: usize,
-        pub frame_rate: f32,
-        // TODO: use enum of common audio formats?
-        pub audio_codec: String,
+        /// Average segment bitrate of the stream (`AVERAGE-BANDWIDTH`), if given
+        pub average_bandwidth: Option<u64>,
+        /// Maximum frame rate of the stream (`FRAME-RATE`), if given.
+        /// The `f32` is the canonical form of the rate: parsing only produces positive, finite values,
+        /// and representations of the same rate (e.g. `30` and `30.000`) parse to the same value.
+        pub frame_rate: Option<f32>,
+        /// `GROUP-ID` of the audio renditions for this stream (`AUDIO`), if it has any
+        pub audio_group: Option<String>,
+        /// `GROUP-ID` of the video renditions for this stream (`VIDEO`), if it has any
+        pub video_group: Option<String>,
         pub closed_captions: String,
+        /// Content steering pathway this stream belongs to (`PATHWAY-ID`)
+        pub pathway_id: Option<String>,
+        /// Relative preference among variants (`SCORE`); higher is better. Never negative.
+        pub score: Option<f32>,
+        /// Deprecated `PROGRAM-ID`, removed in protocol version 6 but kept by legacy playlists
+        pub program_id: Option<usize>,
+    }
+
+    impl StreamInfo {
+        /// Former name of `audio_group`, which holds an audio `GROUP-ID` rather than a codec.
+        #[deprecated(since = "0.2.0", note = "use the `audio_group` field instead")]
+        pub fn audio_codec(&self) -> Option<&str> {
+            self.audio_group.as_deref()
+        }
     }
 
     impl Display for StreamInfo {
@@ -178,24 +590,95 @@ pub mod stream_info {
                 f,
                 "| {:^10} | {:^17} | {:^30} | {} | {:^10} | {:^11} | {:^10} | {:^15} | {:^30} |",
                 self.common.bandwidth,
-                self.average_bandwidth,
+                self.average_bandwidth
+                    .map(|b| b.to_string())
+                    .unwrap_or_default(),
                 self.common.codecs.join(", "),
                 self.common.resolution,
-                self.frame_rate,
+                self.frame_rate.map(|r| r.to_string()).unwrap_or_default(),
                 self.common.video_range,
-                self.audio_codec,
+                self.audio_group.as_deref().unwrap_or_default(),
                 self.closed_captions,
                 self.common.uri
             )
         }
     }
 
+    impl FromStr for StreamInfo {
+        type Err = anyhow::Error;
+
+        /// Parse a single `#EXT-X-STREAM-INF` line, followed by its URI line.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(crate::parsers::parse_stream_info(s)?)
+        }
+    }
+
+    impl FromStr for IframeStreamInfo {
+        type Err = anyhow::Error;
+
+        /// Parse a single `#EXT-X-I-FRAME-STREAM-INF` line.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(crate::parsers::parse_iframe_stream_info(s)?)
+        }
+    }
+
+    // Float fields are compared with `total_cmp`, as by `Ord`, so that `==` is reflexive even for NaN
+    // set by hand, and agrees with the order: e.g. `0.0` and `-0.0` are neither equal nor ordered equal.
+    impl PartialEq for StreamInfo {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other).is_eq()
+        }
+    }
+
+    impl Eq for StreamInfo {}
+
+    impl PartialOrd for StreamInfo {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for StreamInfo {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            cmp_common(&self.common, &other.common)
+                .then_with(|| self.average_bandwidth.cmp(&other.average_bandwidth))
+                .then_with(|| match (self.frame_rate, other.frame_rate) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    // Missing frame rates sort last
+                    (x, y) => y.is_some().cmp(&x.is_some()),
+                })
+                .then_with(|| self.audio_group.cmp(&other.audio_group))
+                .then_with(|| self.video_group.cmp(&other.video_group))
+                .then_with(|| self.closed_captions.cmp(&other.closed_captions))
+                .then_with(|| self.pathway_id.cmp(&other.pathway_id))
+                .then_with(|| self.program_id.cmp(&other.program_id))
+                .then_with(|| match (self.score, other.score) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    (x, y) => x.is_some().cmp(&y.is_some()),
+                })
+        }
+    }
+
     /// Collection of all iframe streams parsed from an HLS playlist
-    #[derive(Debug, Default)]
+    #[derive(Debug, Default, Clone, PartialEq)]
     pub struct IframeStreams {
         pub inner: Vec<IframeStreamInfo>,
     }
 
+    impl FromIterator<IframeStreamInfo> for IframeStreams {
+        fn from_iter<I: IntoIterator<Item = IframeStreamInfo>>(iter: I) -> Self {
+            Self {
+                inner: iter.into_iter().collect(),
+            }
+        }
+    }
+
+    impl Extend<IframeStreamInfo> for IframeStreams {
+        fn extend<I: IntoIterator<Item = IframeStreamInfo>>(&mut self, iter: I) {
+            self.inner.extend(iter);
+        }
+    }
+
     impl Display for IframeStreams {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             writeln!(f, "IFrame Streams")?;
@@ -212,10 +695,18 @@ pub mod stream_info {
         }
     }
 
-    /// Represents parsed iframe stream metadata (`#EXT-X-I-FRAME-STREAM-INF`)
-    #[derive(Debug, Default, PartialEq)]
+    /// Represents parsed iframe stream metadata (`#EXT-X-I-FRAME-STREAM-INF`).
+    ///
+    /// Streams are ordered by quality: by bandwidth, then by resolution.
+    /// Remaining fields only break ties, so that streams compare equal only if they are `==`.
+    /// Like `==`, this includes param order, so the order of otherwise identical streams depends on
+    /// how their params were written, but not the source text kept by `ParseOptions::keep_raw`.
+    #[derive(Debug, Default, Clone, Eq, PartialEq)]
+    #[non_exhaustive]
     pub struct IframeStreamInfo {
         pub common: StreamInfoCommon,
+        /// Average segment bitrate of the stream (`AVERAGE-BANDWIDTH`), if given
+        pub average_bandwidth: Option<u64>,
     }
 
     impl Display for IframeStreamInfo {
@@ -232,28 +723,117 @@ pub mod stream_info {
         }
     }
 
+    impl PartialOrd for IframeStreamInfo {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for IframeStreamInfo {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            cmp_common(&self.common, &other.common)
+                .then_with(|| self.average_bandwidth.cmp(&other.average_bandwidth))
+        }
+    }
+
+    /// Order stream data by bandwidth, then resolution, then the remaining fields to break ties.
+    /// Source text isn't compared, so that it doesn't depend on `ParseOptions::keep_raw`.
+    fn cmp_common(x: &StreamInfoCommon, y: &StreamInfoCommon) -> std::cmp::Ordering {
+        x.bandwidth
+            .cmp(&y.bandwidth)
+            .then_with(|| x.resolution.cmp(&y.resolution))
+            .then_with(|| x.codecs.cmp(&y.codecs))
+            .then_with(|| x.supplemental_codecs.cmp(&y.supplemental_codecs))
+            .then_with(|| x.video_range.cmp(&y.video_range))
+            .then_with(|| x.stable_variant_id.cmp(&y.stable_variant_id))
+            .then_with(|| x.allowed_cpc.cmp(&y.allowed_cpc))
+            .then_with(|| x.uri.cmp(&y.uri))
+            .then_with(|| x.attribute_order.cmp(&y.attribute_order))
+    }
+
+    /// Summary statistics of a playlist's video stream ladder
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct BitrateSummary {
+        pub min_bandwidth: u64,
+        pub max_bandwidth: u64,
+        pub mean_bandwidth: f64,
+        pub min_resolution: Resolution,
+        pub max_resolution: Resolution,
+        /// Number of distinct `VIDEO-RANGE` values
+        pub video_range_count: usize,
+        /// Number of distinct codec strings, across all `CODECS` entries
+        pub codec_count: usize,
+    }
+
     /// Represents a parsed `RESOLUTION` parameter
-    #[derive(Debug, Default, Eq, PartialEq, PartialOrd)]
+    #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
     pub struct Resolution {
         // TODO: could store as u16, as max reasonable value is ~8k
         pub width: usize,
         pub height: usize,
     }
 
+    impl Resolution {
+        /// 1280x720 (720p)
+        pub const HD_720: Self = Self {
+            width: 1280,
+            height: 720,
+        };
+        /// 1920x1080 (1080p)
+        pub const HD_1080: Self = Self {
+            width: 1920,
+            height: 1080,
+        };
+        /// 3840x2160 (4K UHD)
+        pub const UHD_4K: Self = Self {
+            width: 3840,
+            height: 2160,
+        };
+
+        /// Create a resolution, or `None` if either dimension is zero.
+        pub const fn new(width: usize, height: usize) -> Option<Self> {
+            if width == 0 || height == 0 {
+                None
+            } else {
+                Some(Self { width, height })
+            }
+        }
+
+        /// Number of pixels in a frame, or `None` if it overflows `usize`.
+        pub fn total_pixels(&self) -> Option<usize> {
+            self.width.checked_mul(self.height)
+        }
+
+        /// Width divided by height, e.g. `16.0 / 9.0` for 1920x1080.
+        /// Not finite if the height is zero, which `new` and `from_str` rule out.
+        pub fn aspect_ratio(&self) -> f32 {
+            self.width as f32 / self.height as f32
+        }
+    }
+
     impl FromStr for Resolution {
         type Err = anyhow::Error;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
             // Expects format WxH. Split on 'x' and parse each surrounding string to int.
-            let split = s.split('x').collect::<Vec<_>>();
-            Ok(Self {
-                width: split[0]
+            let (width, height) = s
+                .split_once('x')
+                .with_context(|| format!("resolution is not of the form WxH: {s}"))?;
+            if width.is_empty() || height.is_empty() {
+                anyhow::bail!("resolution is missing its width or height: {s}");
+            }
+            if height.contains('x') {
+                anyhow::bail!("resolution has more than two components: {s}");
+            }
+            Self::new(
+                width
                     .parse::<usize>()
-                    .with_context(|| format!("failed to parse pixed width: {}", split[0]))?,
-                height: split[1]
+                    .with_context(|| format!("failed to parse pixed width: {width}"))?,
+                height
                     .parse::<usize>()
-                    .with_context(|| format!("failed to parse pixed height: {}", split[1]))?,
-            })
+                    .with_context(|| format!("failed to parse pixed height: {height}"))?,
+            )
+            .with_context(|| format!("resolution has a zero width or height: {s}"))
         }
     }
 
@@ -263,13 +843,391 @@ pub mod stream_info {
         }
     }
 
+    impl PartialOrd for Resolution {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
     impl Ord for Resolution {
         fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            let width_cmp = self.width.cmp(&other.width);
-            match width_cmp {
-                std::cmp::Ordering::Equal => width_cmp,
-                _ => self.height.cmp(&other.height),
+            // Sort by width first, breaking tie on height
+            self.width
+                .cmp(&other.width)
+                .then_with(|| self.height.cmp(&other.height))
+        }
+    }
+}
+
+// Types for parsing media playlists (#EXTINF and tags that apply to segments)
+pub mod segment {
+    use std::{fmt::Display, str::FromStr};
+
+    use anyhow::Context;
+
+    /// Represents a parsed media segment (`#EXTINF`, followed by the segment URI)
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct Segment {
+        /// Duration of the segment in seconds
+        pub duration: f32,
+        pub title: Option<String>,
+        /// URI of the media segment
+        pub uri: String,
+        /// Keys that decrypt this segment, one per `KEYFORMAT`: the most recent preceding `#EXT-X-KEY`
+        /// of each format since the last `METHOD=NONE`, in playlist order. Empty if the segment isn't encrypted.
+        pub keys: Vec<EncryptionKey>,
+        /// Initialization section needed to decode this segment: the most recent preceding `#EXT-X-MAP`,
+        /// or `None` if the segment doesn't need one
+        pub map: Option<MediaInitialization>,
+        /// Approximate bitrate of this segment in kbit/s: the most recent preceding `#EXT-X-BITRATE`,
+        /// or `None` if no bitrate was declared
+        pub bitrate: Option<u64>,
+        /// Partial segments (`#EXT-X-PART`) that make up this segment in low-latency playlists, in order
+        pub parts: Vec<Part>,
+        /// Whether the segment's media is intentionally missing (`#EXT-X-GAP`), so shouldn't be loaded
+        pub gap: bool,
+    }
+
+    /// Represents a parsed `#EXT-X-PART` tag: a partial segment, published ahead of its full segment
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct Part {
+        /// Duration of the part in seconds
+        pub duration: f32,
+        /// URI of the partial segment
+        pub uri: String,
+        /// Whether the part contains an independent frame (`INDEPENDENT=YES`)
+        pub independent: bool,
+        /// Sub-range of the resource at `uri` containing the part, if not all of it
+        pub byte_range: Option<ByteRange>,
+        /// Whether the part is unavailable (`GAP=YES`), and shouldn't be loaded
+        pub gap: bool,
+    }
+
+    /// Represents a parsed `#EXT-X-MAP` tag, i.e. the initialization section of subsequent segments
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MediaInitialization {
+        /// URI of the resource containing the initialization section
+        pub uri: String,
+        /// Sub-range of the resource containing the initialization section, if not all of it
+        pub byte_range: Option<ByteRange>,
+    }
+
+    /// Represents a parsed byte range, of the form `<length>[@<offset>]`
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct ByteRange {
+        /// Length of the sub-range in bytes
+        pub length: usize,
+        /// Start of the sub-range, as a byte offset from the beginning of the resource
+        pub offset: Option<usize>,
+    }
+
+    impl FromStr for ByteRange {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            // Offset is optional, following '@' if present
+            let (length, offset) = match s.split_once('@') {
+                Some((length, offset)) => (length, Some(offset)),
+                None => (s, None),
+            };
+            Ok(Self {
+                length: length
+                    .parse::<usize>()
+                    .with_context(|| format!("failed to parse byte range length: {length}"))?,
+                offset: offset
+                    .map(|o| {
+                        o.parse::<usize>()
+                            .with_context(|| format!("failed to parse byte range offset: {o}"))
+                    })
+                    .transpose()?,
+            })
+        }
+    }
+
+    impl Display for ByteRange {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.length)?;
+            if let Some(offset) = self.offset {
+                write!(f, "@{offset}")?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Represents a parsed `#EXT-X-KEY` tag, excluding `METHOD=NONE`
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct EncryptionKey {
+        pub method: KeyMethod,
+        /// URI to fetch the key from
+        pub uri: String,
+        /// Initialization vector, as the raw hex string (`0x...`)
+        pub iv: Option<String>,
+        pub key_format: Option<String>,
+        pub key_format_versions: Option<String>,
+    }
+
+    impl EncryptionKey {
+        /// Format of the key (`KEYFORMAT`), which defaults to `identity`
+        pub fn format(&self) -> &str {
+            self.key_format.as_deref().unwrap_or("identity")
+        }
+    }
+
+    /// Encryption method of an `#EXT-X-KEY` tag
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    #[non_exhaustive]
+    pub enum KeyMethod {
+        Aes128,
+        SampleAes,
+        SampleAesCtr,
+    }
+
+    impl FromStr for KeyMethod {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "AES-128" => Ok(KeyMethod::Aes128),
+                "SAMPLE-AES" => Ok(KeyMethod::SampleAes),
+                "SAMPLE-AES-CTR" => Ok(KeyMethod::SampleAesCtr),
+                _ => anyhow::bail!("unknown encryption method: {s}"),
+            }
+        }
+    }
+
+    impl Display for KeyMethod {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                KeyMethod::Aes128 => write!(f, "AES-128"),
+                KeyMethod::SampleAes => write!(f, "SAMPLE-AES"),
+                KeyMethod::SampleAesCtr => write!(f, "SAMPLE-AES-CTR"),
+            }
+        }
+    }
+
+    /// Represents a parsed `#EXT-X-PRELOAD-HINT` tag: a resource the client may request
+    /// before it's listed in the playlist, to reduce latency
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct PreloadHint {
+        pub hint_type: PreloadHintType,
+        /// URI of the hinted resource
+        pub uri: String,
+        /// Byte offset of the hinted range in the resource (`BYTERANGE-START`), if not its start
+        pub byte_range_start: Option<usize>,
+        /// Length of the hinted range in bytes (`BYTERANGE-LENGTH`), if not the rest of the resource
+        pub byte_range_length: Option<usize>,
+    }
+
+    /// Kind of resource a preload hint refers to (`TYPE`)
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    #[non_exhaustive]
+    pub enum PreloadHintType {
+        /// A partial segment
+        Part,
+        /// A media initialization section
+        Map,
+    }
+
+    impl FromStr for PreloadHintType {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "PART" => Ok(PreloadHintType::Part),
+                "MAP" => Ok(PreloadHintType::Map),
+                _ => anyhow::bail!("unknown preload hint type: {s}"),
+            }
+        }
+    }
+
+    impl Display for PreloadHintType {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                PreloadHintType::Part => write!(f, "PART"),
+                PreloadHintType::Map => write!(f, "MAP"),
+            }
+        }
+    }
+
+    /// Represents a parsed `#EXT-X-RENDITION-REPORT` tag: the latest segment and part
+    /// of another rendition, so clients can switch to it without reloading its playlist first
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct RenditionReport {
+        /// URI of the reported rendition's media playlist, relative to this playlist
+        pub uri: String,
+        /// Media sequence number of the rendition's last segment (`LAST-MSN`)
+        pub last_msn: Option<usize>,
+        /// Index of the last part of that segment (`LAST-PART`)
+        pub last_part: Option<usize>,
+    }
+
+    /// Represents a parsed `#EXT-X-SKIP` tag, which replaces earlier segments in a delta playlist update
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct Skip {
+        /// Number of segments omitted from the start of the playlist (`SKIPPED-SEGMENTS`)
+        pub skipped_segments: usize,
+        /// IDs of date ranges removed since the last update (`RECENTLY-REMOVED-DATERANGES`), if given
+        pub recently_removed_dateranges: Option<Vec<String>>,
+    }
+}
+
+// Types for parsing #EXT-X-DATERANGE
+pub mod date_range {
+    use std::collections::HashMap;
+
+    /// Represents a parsed `#EXT-X-DATERANGE` tag, e.g. marking an ad break inserted by SSAI
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct DateRange {
+        pub id: String,
+        /// Identifies the semantics of a set of date ranges with the same `CLASS`
+        pub class: Option<String>,
+        /// Start of the range, as the raw ISO-8601 date string
+        pub start_date: String,
+        /// End of the range, as the raw ISO-8601 date string
+        pub end_date: Option<String>,
+        /// Duration of the range in seconds
+        pub duration: Option<f32>,
+        /// Expected duration of the range in seconds, if the actual duration isn't yet known
+        pub planned_duration: Option<f32>,
+        /// Whether the range ends at the start of the next range with the same class (`END-ON-NEXT`)
+        pub end_on_next: bool,
+        /// Client-defined `X-` attributes, keyed by full attribute name (including `X-`).
+        /// Quoted values are stored without their quotes.
+        pub client_attributes: HashMap<String, String>,
+        /// Bytes of the splice info section from `SCTE35-CMD`
+        pub scte35_cmd: Option<Vec<u8>>,
+        /// Bytes of the splice info section from `SCTE35-OUT`, marking an ad break start
+        pub scte35_out: Option<Vec<u8>>,
+        /// Bytes of the splice info section from `SCTE35-IN`, marking an ad break end
+        pub scte35_in: Option<Vec<u8>>,
+    }
+}
+
+// Types for parsing #EXT-X-CONTENT-STEERING
+pub mod steering {
+    /// Represents a parsed `#EXT-X-CONTENT-STEERING` tag, used to steer clients between CDNs
+    #[derive(Debug, Clone, PartialEq)]
+    #[non_exhaustive]
+    pub struct ContentSteering {
+        /// URI of the steering manifest
+        pub server_uri: String,
+        /// Pathway to use until the steering manifest is fetched
+        pub pathway_id: Option<String>,
+        /// Names of the tag's params in source order, used to serialize them in that order.
+        /// Empty if not parsed from a playlist, in which case params are serialized in a canonical order.
+        pub attribute_order: Vec<String>,
+    }
+}
+
+// Types for parsing #EXT-X-DEFINE
+pub mod define {
+    /// Represents a parsed `#EXT-X-DEFINE` tag, declaring a variable that
+    /// may be referenced as `{$name}` in attribute values and URI lines.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum Definition {
+        /// Variable with a value given in the playlist (`NAME` and `VALUE`)
+        Value { name: String, value: String },
+        /// Variable whose value is taken from the master playlist that references this one (`IMPORT`)
+        Import(String),
+        /// Variable whose value is taken from a query parameter of the playlist's URI (`QUERYPARAM`)
+        QueryParam(String),
+    }
+
+    impl Definition {
+        /// Name of the defined variable.
+        pub fn name(&self) -> &str {
+            match self {
+                Definition::Value { name, .. } => name,
+                Definition::Import(name) | Definition::QueryParam(name) => name,
             }
         }
+
+        /// Value of the variable, if given in the playlist itself.
+        pub fn value(&self) -> Option<&str> {
+            match self {
+                Definition::Value { value, .. } => Some(value),
+                Definition::Import(_) | Definition::QueryParam(_) => None,
+            }
+        }
+    }
+}
+
+// Tags not otherwise modeled by this crate
+pub mod unknown {
+    /// An `#EXT-X-*` tag that isn't otherwise parsed, kept so it can be re-emitted verbatim.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct UnknownTag {
+        /// Full line of the tag, as it appeared in the source playlist
+        pub line: String,
+        /// Index of the tag among all serialized playlist elements (tags and their URIs),
+        /// not counting the `#EXTM3U` header. Serialization reinserts the tag at this index,
+        /// so its neighbours change if the playlist's known elements weren't in serialization order.
+        pub position: usize,
+    }
+
+    impl UnknownTag {
+        /// Name of the tag, without its value, e.g. `#EXT-X-FOO` for `#EXT-X-FOO:bar`
+        pub fn name(&self) -> &str {
+            crate::parsers::tag_name(&self.line)
+        }
+    }
+}
+
+// Borrowed counterparts of parsed tags, produced by `HlsPlaylistRef`
+pub mod borrowed {
+    /// A tag's params as slices of the input playlist, before conversion into an owned type.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TagRef<'a> {
+        /// (name, value) pairs of the tag's params in source order.
+        /// Quotes around values are stripped.
+        pub params: Vec<(&'a str, &'a str)>,
+        /// URI on the line following the tag, for tags that have one (`#EXT-X-STREAM-INF`)
+        pub uri: Option<&'a str>,
+        /// 1-based line number of the tag
+        pub line: usize,
+        /// Source text of the tag (and its URI line, if any), without the newlines that follow it
+        pub raw: &'a str,
+    }
+
+    impl<'a> TagRef<'a> {
+        /// Create a tag on the first line of its input, parsed from source text `raw`.
+        pub(crate) fn new(
+            params: Vec<(&'a str, &'a str)>,
+            uri: Option<&'a str>,
+            raw: &'a str,
+        ) -> Self {
+            Self {
+                params,
+                uri,
+                line: 1,
+                raw,
+            }
+        }
+
+        /// Value of the first param named `name`, if present.
+        pub fn get(&self, name: &str) -> Option<&'a str> {
+            self.params
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|&(_, value)| value)
+        }
+    }
+
+    /// Borrowed counterpart of `crate::types::unknown::UnknownTag`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct UnknownTagRef<'a> {
+        /// Full line of the tag, as it appeared in the source playlist
+        pub line: &'a str,
+        /// Index of the tag among all serialized playlist elements (tags and their URIs),
+        /// not counting the `#EXTM3U` header
+        pub position: usize,
     }
 }
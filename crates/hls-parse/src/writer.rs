@@ -0,0 +1,102 @@
+//! Incremental M3U8 output, for playlists too large to build as an `HlsPlaylist` or `MediaPlaylist`.
+
+use std::io::{self, Write};
+
+use crate::serializers;
+use crate::types::segment::{EncryptionKey, MediaInitialization, Segment};
+use crate::types::stream_info::StreamInfo;
+
+/// Writes a playlist to `W` one element at a time, using the same serialization as `to_m3u8`.
+///
+/// Call `write_header` first, then write elements in playlist order, and finally `finish`.
+/// A media playlist also needs `write_target_duration` before its first segment.
+/// Tags that apply to a run of segments (`#EXT-X-KEY`, `#EXT-X-MAP` and `#EXT-X-BITRATE`)
+/// are written only when a segment's value differs from the previous segment's.
+#[derive(Debug)]
+pub struct PlaylistWriter<W: Write> {
+    inner: W,
+    /// Keys of the last segment written, empty if unencrypted
+    keys: Vec<EncryptionKey>,
+    /// Initialization section of the last segment written
+    map: Option<MediaInitialization>,
+    /// Bitrate of the last segment written
+    bitrate: Option<u64>,
+}
+
+impl<W: Write> PlaylistWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            keys: Vec::new(),
+            map: None,
+            bitrate: None,
+        }
+    }
+
+    /// Write the `#EXTM3U` header, followed by `#EXT-X-VERSION` unless `version` is 0.
+    pub fn write_header(&mut self, version: usize) -> io::Result<()> {
+        writeln!(self.inner, "#EXTM3U")?;
+        if version != 0 {
+            writeln!(self.inner, "#EXT-X-VERSION:{version}")?;
+        }
+        Ok(())
+    }
+
+    /// Write the `#EXT-X-TARGETDURATION` tag that media playlists require, in seconds.
+    pub fn write_target_duration(&mut self, seconds: usize) -> io::Result<()> {
+        writeln!(self.inner, "#EXT-X-TARGETDURATION:{seconds}")
+    }
+
+    /// Write a media segment: tags whose value changed since the previous segment,
+    /// its parts, and its `#EXTINF` tag followed by its URI.
+    /// A segment can't drop a map or bitrate that the previous one had, as HLS has no tag to do so.
+    pub fn write_segment(&mut self, segment: &Segment) -> io::Result<()> {
+        if segment.keys != self.keys {
+            // A key only replaces one of its own format, so dropping a format takes `METHOD=NONE`
+            let dropped =
+                (self.keys.iter()).any(|k| !segment.keys.iter().any(|s| s.format() == k.format()));
+            if dropped {
+                writeln!(self.inner, "{}", serializers::hls_key(None))?;
+            }
+            for key in segment.keys.iter() {
+                writeln!(self.inner, "{}", serializers::hls_key(Some(key)))?;
+            }
+            self.keys = segment.keys.clone();
+        }
+        if let Some(map) = &segment.map
+            && self.map.as_ref() != Some(map)
+        {
+            writeln!(self.inner, "{}", serializers::hls_map(map))?;
+            self.map = Some(map.clone());
+        }
+        if let Some(bitrate) = segment.bitrate
+            && self.bitrate != Some(bitrate)
+        {
+            writeln!(self.inner, "#EXT-X-BITRATE:{bitrate}")?;
+            self.bitrate = Some(bitrate);
+        }
+        for part in segment.parts.iter() {
+            writeln!(self.inner, "{}", serializers::hls_part(part))?;
+        }
+        if segment.gap {
+            writeln!(self.inner, "#EXT-X-GAP")?;
+        }
+        writeln!(self.inner, "{}", serializers::hls_segment_info(segment))
+    }
+
+    /// Write a video stream of a master playlist, followed by its URI.
+    pub fn write_stream(&mut self, stream: &StreamInfo) -> io::Result<()> {
+        writeln!(self.inner, "{}", serializers::hls_stream_info(stream))
+    }
+
+    /// Write `#EXT-X-ENDLIST`, marking that no more segments will be added to a media playlist.
+    pub fn write_end_list(&mut self) -> io::Result<()> {
+        writeln!(self.inner, "#EXT-X-ENDLIST")
+    }
+
+    /// Flush all output, returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
@@ -1,25 +1,27 @@
-use std::str::FromStr;
+use std::cmp::Ordering;
 
-use anyhow::Context;
 use clap::{Parser, ValueEnum};
 use hls_parse::{
-    HlsPlaylist,
+    HlsPlaylist, TableOptions,
     types::{
         media::Audio,
         stream_info::{IframeStreamInfo, StreamInfo},
     },
 };
 
-const DEFAULT_HLS_URL: &'static str =
+const DEFAULT_HLS_URL: &str =
     "https://lw.bamgrid.com/2.0/hls/vod/bam/ms02/hls/dplus/bao/master_unenc_hdr10_all.m3u8";
-const CLAP_HELP: &'static str =
+/// Environment variable holding the playlist URL, used when `-u` isn't given
+const URL_ENV_VAR: &str = "HLS_FETCH_URL";
+const CLAP_HELP: &str =
     "A simple viewing/sorting tool for HLS playlists fetched from a URL.
 When no sort is selected for a given tag type, results are ordered as they appear in the raw HLS playlist.";
 
 #[derive(Parser)]
 #[command(about = CLAP_HELP)]
 struct Args {
-    /// (override) URL to fetch HLS playlist from
+    /// (override) URL to fetch HLS playlist from. Falls back to the `HLS_FETCH_URL` environment variable,
+    /// then a default playlist.
     #[arg(short = 'u')]
     url: Option<String>,
     /// Sort HLS audio streams by a parameter value
@@ -31,6 +33,74 @@ struct Args {
     /// Sort HLS iframe streams by a parameter value
     #[arg(short = 'i')]
     sort_iframe: Option<VideoSort>,
+    /// Secondary sort key for video streams, breaking ties left by `-v`
+    #[arg(long, value_name = "SORT", requires = "sort_video")]
+    then_video: Option<VideoSort>,
+    /// Secondary sort key for iframe streams, breaking ties left by `-i`
+    #[arg(long, value_name = "SORT", requires = "sort_iframe")]
+    then_iframe: Option<VideoSort>,
+    /// Sort without preserving the playlist order of ties, which is faster for large playlists
+    #[arg(long)]
+    unstable_sort: bool,
+    /// Sort in descending order. Applies to every active sort, including secondary keys
+    #[arg(short = 'r', long)]
+    reverse: bool,
+    /// Print tables without borders or padding
+    #[arg(long)]
+    compact: bool,
+    /// Print only the given section. Repeat to print several; all are printed by default
+    #[arg(long, value_name = "SECTION")]
+    only: Vec<Section>,
+}
+
+impl Args {
+    /// URL to fetch the playlist from: `-u` if given, then `HLS_FETCH_URL`, then the default playlist.
+    fn playlist_url(&self) -> String {
+        playlist_url(self.url.clone(), std::env::var(URL_ENV_VAR).ok())
+    }
+
+    /// How the requested sorts are applied.
+    fn sort_order(&self) -> SortOrder {
+        SortOrder {
+            unstable: self.unstable_sort,
+            reverse: self.reverse,
+        }
+    }
+
+    /// How tables are printed, including which sections are shown.
+    fn table_options(&self) -> TableOptions {
+        let hide = |section| !self.only.is_empty() && !self.only.contains(&section);
+        TableOptions {
+            compact: self.compact,
+            hide_audio: hide(Section::Audio),
+            hide_video: hide(Section::Video),
+            hide_iframe: hide(Section::Iframe),
+            ..Default::default()
+        }
+    }
+}
+
+/// URL to fetch the playlist from: `flag` if given, then `env`, then the default playlist.
+fn playlist_url(flag: Option<String>, env: Option<String>) -> String {
+    flag.or(env).unwrap_or(DEFAULT_HLS_URL.to_owned())
+}
+
+/// Sections of the printed playlist, selected by `--only`.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Section {
+    Audio,
+    /// Video renditions and streams
+    Video,
+    Iframe,
+}
+
+/// How sorts are applied, shared by all tag types.
+#[derive(Clone, Copy, Default)]
+struct SortOrder {
+    /// Don't preserve the playlist order of ties
+    unstable: bool,
+    /// Sort in descending order
+    reverse: bool,
 }
 
 /// Enables sorting audio streams by HLS parameters.
@@ -39,6 +109,10 @@ struct Args {
 enum AudioSort {
     Channels,
     GroupId,
+    /// Case-insensitive
+    Language,
+    /// Case-insensitive
+    Name,
 }
 
 /// Enables sorting video stream types by HLS parameters.
@@ -47,54 +121,378 @@ enum AudioSort {
 enum VideoSort {
     Bandwidth,
     Resolution,
+    /// Video streams only. Streams without a frame rate sort last.
+    FrameRate,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    // Fetch data from URL and store body for parsing
-    let hls_raw_data = reqwest::blocking::get(args.url.unwrap_or(DEFAULT_HLS_URL.to_owned()))
-        .context("failed to GET from requested http endpoint")?
-        .text()?;
-
-    // Parse HLS playlist to structured data
-    let mut playlist = HlsPlaylist::from_str(hls_raw_data.as_str())?;
+    // Fetch HLS playlist from URL and parse to structured data
+    let mut playlist = HlsPlaylist::from_url(&args.playlist_url())?;
+    let order = args.sort_order();
+    let table_options = args.table_options();
 
     // Perform sorts, if requested
     if let Some(sorter) = args.sort_audio {
-        let sort_fn = match sorter {
-            AudioSort::Channels => |x: &Audio, y: &Audio| x.channel_info.cmp(&y.channel_info),
-            AudioSort::GroupId => |x: &Audio, y: &Audio| x.group_id.cmp(&y.group_id),
-        };
-        playlist.audio_streams.inner.sort_by(sort_fn);
+        sort_audio(&mut playlist.audio_streams.inner, &sorter, order);
     }
 
     // TODO: Could reduce code duplication below by implementing a trait that returns &StreamInfoCommon for various
     //       video stream types, then converting `VideoSort` to a matching sorting function that takes &StreamInfoCommon.
     if let Some(sorter) = args.sort_video {
-        let sort_fn = match sorter {
-            VideoSort::Bandwidth => {
-                |x: &StreamInfo, y: &StreamInfo| x.common.bandwidth.cmp(&y.common.bandwidth)
-            }
-            VideoSort::Resolution => {
-                |x: &StreamInfo, y: &StreamInfo| x.common.resolution.cmp(&y.common.resolution)
-            }
-        };
-        playlist.streams.inner.sort_by(sort_fn);
+        let then = args
+            .then_video
+            .as_ref()
+            .map(|then| video_cmp(then, order.reverse));
+        sort_with(
+            &mut playlist.streams.inner,
+            then_by(video_cmp(&sorter, order.reverse), then),
+            order.unstable,
+        );
     }
 
     if let Some(sorter) = args.sort_iframe {
-        let sort_fn = match sorter {
-            VideoSort::Bandwidth => |x: &IframeStreamInfo, y: &IframeStreamInfo| {
-                x.common.bandwidth.cmp(&y.common.bandwidth)
-            },
-            VideoSort::Resolution => |x: &IframeStreamInfo, y: &IframeStreamInfo| {
-                x.common.resolution.cmp(&y.common.resolution)
-            },
-        };
-        playlist.iframe_streams.inner.sort_by(sort_fn);
+        let then = (args.then_iframe.as_ref())
+            .map(|then| iframe_cmp(then, order.reverse))
+            .transpose()?;
+        sort_with(
+            &mut playlist.iframe_streams.inner,
+            then_by(iframe_cmp(&sorter, order.reverse)?, then),
+            order.unstable,
+        );
     }
 
     // Display HLS playlist and exit
-    println!("{}", playlist);
+    println!("{}", playlist.format_table(&table_options));
     Ok(())
 }
+
+/// Sort audio streams by the requested parameter.
+fn sort_audio(streams: &mut [Audio], sorter: &AudioSort, order: SortOrder) {
+    let sort_fn = match sorter {
+        AudioSort::Channels => |x: &Audio, y: &Audio| x.channel_info.cmp(&y.channel_info),
+        AudioSort::GroupId => |x: &Audio, y: &Audio| x.group_id.cmp(&y.group_id),
+        AudioSort::Language => {
+            |x: &Audio, y: &Audio| x.language.to_lowercase().cmp(&y.language.to_lowercase())
+        }
+        AudioSort::Name => |x: &Audio, y: &Audio| x.name.to_lowercase().cmp(&y.name.to_lowercase()),
+    };
+    sort_with(
+        streams,
+        |x, y| directed(sort_fn(x, y), order.reverse),
+        order.unstable,
+    );
+}
+
+/// Comparator of two items of the same kind, e.g. video streams.
+type Comparator<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
+/// Comparator for video streams by the requested parameter, in descending order if `reverse`.
+fn video_cmp(sorter: &VideoSort, reverse: bool) -> Comparator<StreamInfo> {
+    match sorter {
+        VideoSort::Bandwidth => {
+            Box::new(move |x, y| directed(x.common.bandwidth.cmp(&y.common.bandwidth), reverse))
+        }
+        VideoSort::Resolution => {
+            Box::new(move |x, y| directed(x.common.resolution.cmp(&y.common.resolution), reverse))
+        }
+        VideoSort::FrameRate => Box::new(move |x, y| match (x.frame_rate, y.frame_rate) {
+            (Some(x), Some(y)) => directed(x.total_cmp(&y), reverse),
+            // Missing frame rates sort last in either direction, as in `StreamInfo`'s order
+            (x, y) => y.is_some().cmp(&x.is_some()),
+        }),
+    }
+}
+
+/// Comparator for iframe streams by the requested parameter, in descending order if `reverse`.
+/// Fails for parameters iframe streams lack.
+fn iframe_cmp(sorter: &VideoSort, reverse: bool) -> anyhow::Result<Comparator<IframeStreamInfo>> {
+    Ok(match sorter {
+        VideoSort::Bandwidth => {
+            Box::new(move |x, y| directed(x.common.bandwidth.cmp(&y.common.bandwidth), reverse))
+        }
+        VideoSort::Resolution => {
+            Box::new(move |x, y| directed(x.common.resolution.cmp(&y.common.resolution), reverse))
+        }
+        VideoSort::FrameRate => anyhow::bail!("iframe streams have no frame rate to sort by"),
+    })
+}
+
+/// `ordering`, reversed if `reverse`. Applied per sort key, so that keys can order
+/// missing values last regardless of direction.
+fn directed(ordering: Ordering, reverse: bool) -> Ordering {
+    if reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Compose comparators: order by `first`, breaking ties on `then` if given.
+fn then_by<T>(
+    first: impl Fn(&T, &T) -> Ordering,
+    then: Option<impl Fn(&T, &T) -> Ordering>,
+) -> impl Fn(&T, &T) -> Ordering {
+    move |x, y| first(x, y).then_with(|| then.as_ref().map_or(Ordering::Equal, |then| then(x, y)))
+}
+
+/// Sort with `cmp`, keeping ties in playlist order unless `unstable` is set.
+/// Comparators apply a reverse sort themselves, so ties still keep playlist order.
+fn sort_with<T>(items: &mut [T], cmp: impl Fn(&T, &T) -> Ordering, unstable: bool) {
+    if unstable {
+        items.sort_unstable_by(cmp);
+    } else {
+        items.sort_by(cmp);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// The URL comes from `-u`, then the environment, then the default.
+    #[test]
+    fn test_url_precedence() {
+        let flag = || Some("https://example.com/flag.m3u8".to_owned());
+        let env = || Some("https://example.com/env.m3u8".to_owned());
+        assert_eq!(playlist_url(None, None), DEFAULT_HLS_URL);
+        assert_eq!(playlist_url(None, env()), "https://example.com/env.m3u8");
+        assert_eq!(playlist_url(flag(), env()), "https://example.com/flag.m3u8");
+        assert_eq!(playlist_url(flag(), None), "https://example.com/flag.m3u8");
+
+        let args =
+            Args::try_parse_from(["hls-sort", "-u", "https://example.com/flag.m3u8"]).unwrap();
+        assert_eq!(args.url, flag());
+    }
+
+    const SAMPLE_INPUT: &str = include_str!("../../hls-parse/test-fixtures/sample-input.txt");
+
+    fn group_ids(streams: &[Audio]) -> Vec<&str> {
+        streams.iter().map(|a| a.group_id.as_str()).collect()
+    }
+
+    /// The sample's audio entries share a language, so sorting by it keeps playlist order.
+    #[test]
+    fn test_sort_audio_by_language() {
+        let mut playlist = HlsPlaylist::from_str(SAMPLE_INPUT).unwrap();
+        sort_audio(
+            &mut playlist.audio_streams.inner,
+            &AudioSort::Language,
+            SortOrder::default(),
+        );
+        assert_eq!(
+            group_ids(&playlist.audio_streams.inner),
+            ["aac-128k", "aac-64k", "eac3", "atmos"]
+        );
+    }
+
+    /// Language sorting ignores case.
+    #[test]
+    fn test_sort_audio_by_language_case_insensitive() {
+        let data = SAMPLE_INPUT
+            .replace(
+                r#"GROUP-ID="eac3",NAME="English",LANGUAGE="en""#,
+                r#"GROUP-ID="eac3",NAME="English",LANGUAGE="DE""#,
+            )
+            .replace(
+                r#"GROUP-ID="atmos",NAME="English",LANGUAGE="en""#,
+                r#"GROUP-ID="atmos",NAME="English",LANGUAGE="fr""#,
+            );
+        let mut playlist = HlsPlaylist::from_str(&data).unwrap();
+        sort_audio(
+            &mut playlist.audio_streams.inner,
+            &AudioSort::Language,
+            SortOrder::default(),
+        );
+        assert_eq!(
+            group_ids(&playlist.audio_streams.inner),
+            ["eac3", "aac-128k", "aac-64k", "atmos"]
+        );
+    }
+
+    /// Ties on the primary key break on the secondary key, and remaining ties keep playlist order.
+    #[test]
+    fn test_sort_video_secondary_key() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=3000000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+high-1080.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS=\"avc1.640028\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low-720.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+low-1080.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080,VIDEO-RANGE=PQ,CLOSED-CAPTIONS=NONE
+low-1080-pq.m3u8
+";
+        let uris = |streams: &[StreamInfo]| {
+            streams
+                .iter()
+                .map(|s| s.common.uri.clone())
+                .collect::<Vec<_>>()
+        };
+        let playlist = HlsPlaylist::from_str(data).unwrap();
+
+        let mut streams = playlist.streams.inner.clone();
+        sort_with(
+            &mut streams,
+            then_by(
+                video_cmp(&VideoSort::Resolution, false),
+                None::<fn(&_, &_) -> _>,
+            ),
+            false,
+        );
+        assert_eq!(
+            uris(&streams),
+            [
+                "low-720.m3u8",
+                "high-1080.m3u8",
+                "low-1080.m3u8",
+                "low-1080-pq.m3u8"
+            ]
+        );
+
+        let mut streams = playlist.streams.inner.clone();
+        sort_with(
+            &mut streams,
+            then_by(
+                video_cmp(&VideoSort::Resolution, false),
+                Some(video_cmp(&VideoSort::Bandwidth, false)),
+            ),
+            false,
+        );
+        assert_eq!(
+            uris(&streams),
+            [
+                "low-720.m3u8",
+                "low-1080.m3u8",
+                "low-1080-pq.m3u8",
+                "high-1080.m3u8"
+            ]
+        );
+    }
+
+    /// `--then-iframe frame-rate` is rejected for iframe streams.
+    #[test]
+    fn test_iframe_secondary_key_frame_rate() {
+        assert!(iframe_cmp(&VideoSort::Bandwidth, false).is_ok());
+        assert!(iframe_cmp(&VideoSort::FrameRate, false).is_err());
+    }
+
+    /// Each secondary key requires the primary sort of its stream type.
+    #[test]
+    fn test_secondary_key_requires_primary() {
+        let args = |argv: &[&str]| Args::try_parse_from(argv);
+        assert!(args(&["hls-sort", "--then-video", "bandwidth"]).is_err());
+        assert!(args(&["hls-sort", "-i", "resolution", "--then-video", "bandwidth"]).is_err());
+        assert!(args(&["hls-sort", "--then-iframe", "bandwidth"]).is_err());
+
+        let both = args(&[
+            "hls-sort",
+            "-v",
+            "resolution",
+            "--then-video",
+            "frame-rate",
+            "-i",
+            "resolution",
+            "--then-iframe",
+            "bandwidth",
+        ])
+        .unwrap();
+        assert!(matches!(both.then_video, Some(VideoSort::FrameRate)));
+        assert!(matches!(both.then_iframe, Some(VideoSort::Bandwidth)));
+    }
+
+    /// `--reverse` sorts by bandwidth in descending order, keeping ties in playlist order.
+    #[test]
+    fn test_sort_video_reverse() {
+        let args = Args::try_parse_from(["hls-sort", "-v", "bandwidth", "-r"]).unwrap();
+        let mut playlist = HlsPlaylist::from_str(SAMPLE_INPUT).unwrap();
+        let original = playlist.streams.inner.clone();
+        let order = args.sort_order();
+        sort_with(
+            &mut playlist.streams.inner,
+            video_cmp(&VideoSort::Bandwidth, order.reverse),
+            order.unstable,
+        );
+
+        let streams = &playlist.streams.inner;
+        assert!(
+            streams
+                .windows(2)
+                .all(|w| w[0].common.bandwidth >= w[1].common.bandwidth)
+        );
+        assert!(streams[0].common.bandwidth > streams[streams.len() - 1].common.bandwidth);
+        // Ties are in the order they appear in the playlist
+        for w in streams.windows(2) {
+            if w[0].common.bandwidth == w[1].common.bandwidth {
+                let position = |s: &StreamInfo| original.iter().position(|o| o == s).unwrap();
+                assert!(position(&w[0]) < position(&w[1]));
+            }
+        }
+
+        let mut iframes = playlist.iframe_streams.inner.clone();
+        sort_with(
+            &mut iframes,
+            iframe_cmp(&VideoSort::Bandwidth, order.reverse).unwrap(),
+            order.unstable,
+        );
+        assert!(
+            iframes
+                .windows(2)
+                .all(|w| w[0].common.bandwidth >= w[1].common.bandwidth)
+        );
+    }
+
+    /// `--reverse` sorts frame rates in descending order, still with missing frame rates last.
+    #[test]
+    fn test_sort_frame_rate_reverse_missing_last() {
+        let data = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS=\"avc1.640028\",RESOLUTION=1280x720,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+none.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS=\"avc1.640028\",RESOLUTION=1280x720,FRAME-RATE=25.000,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+25.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1000000,CODECS=\"avc1.640028\",RESOLUTION=1280x720,FRAME-RATE=60.000,VIDEO-RANGE=SDR,CLOSED-CAPTIONS=NONE
+60.m3u8
+";
+        let args = Args::try_parse_from(["hls-sort", "-v", "frame-rate", "-r"]).unwrap();
+        let order = args.sort_order();
+        let mut playlist = HlsPlaylist::from_str(data).unwrap();
+        sort_with(
+            &mut playlist.streams.inner,
+            video_cmp(&args.sort_video.unwrap(), order.reverse),
+            order.unstable,
+        );
+        let uris = (playlist.streams.inner.iter())
+            .map(|s| s.common.uri.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(uris, ["60.m3u8", "25.m3u8", "none.m3u8"]);
+    }
+
+    /// `--only` prints just the requested sections, and everything is printed without it.
+    #[test]
+    fn test_only_sections() {
+        let playlist = HlsPlaylist::from_str(SAMPLE_INPUT).unwrap();
+        let print = |argv: &[&str]| {
+            let args = Args::try_parse_from(argv).unwrap();
+            playlist.format_table(&args.table_options())
+        };
+
+        let all = print(&["hls-sort"]);
+        assert!(all.contains("Audio Streams"));
+        assert!(all.contains("Video Streams"));
+        assert!(all.contains("IFrame Streams"));
+
+        let audio = print(&["hls-sort", "--only", "audio"]);
+        assert!(audio.contains("Audio Streams"));
+        assert!(!audio.contains("Video Streams"));
+        assert!(!audio.contains("IFrame Streams"));
+
+        let video_iframe = print(&["hls-sort", "--only", "video", "--only", "iframe"]);
+        assert!(!video_iframe.contains("Audio Streams"));
+        assert!(video_iframe.contains("Video Streams"));
+        assert!(video_iframe.contains("IFrame Streams"));
+
+        assert!(Args::try_parse_from(["hls-sort", "--only", "subtitles"]).is_err());
+    }
+}